@@ -0,0 +1,198 @@
+//! 64 字节与 4 KiB 布局的编码性能，`*_legacy` 为改写前逐字节查找所在字段的迭代器
+
+use std::ops::RangeInclusive;
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{IntoBeIter, IntoLeIter};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// 16 个 u32，共 64 字节
+#[bytemap(packed)]
+#[derive(Debug, Clone)]
+struct Small {
+    f0: u32,
+    f1: u32,
+    f2: u32,
+    f3: u32,
+    f4: u32,
+    f5: u32,
+    f6: u32,
+    f7: u32,
+    f8: u32,
+    f9: u32,
+    f10: u32,
+    f11: u32,
+    f12: u32,
+    f13: u32,
+    f14: u32,
+    f15: u32,
+}
+
+/// 32 个 `[u32; 32]`，共 4 KiB
+#[bytemap(packed)]
+#[derive(Debug, Clone)]
+struct Large {
+    f0: [u32; 32],
+    f1: [u32; 32],
+    f2: [u32; 32],
+    f3: [u32; 32],
+    f4: [u32; 32],
+    f5: [u32; 32],
+    f6: [u32; 32],
+    f7: [u32; 32],
+    f8: [u32; 32],
+    f9: [u32; 32],
+    f10: [u32; 32],
+    f11: [u32; 32],
+    f12: [u32; 32],
+    f13: [u32; 32],
+    f14: [u32; 32],
+    f15: [u32; 32],
+    f16: [u32; 32],
+    f17: [u32; 32],
+    f18: [u32; 32],
+    f19: [u32; 32],
+    f20: [u32; 32],
+    f21: [u32; 32],
+    f22: [u32; 32],
+    f23: [u32; 32],
+    f24: [u32; 32],
+    f25: [u32; 32],
+    f26: [u32; 32],
+    f27: [u32; 32],
+    f28: [u32; 32],
+    f29: [u32; 32],
+    f30: [u32; 32],
+    f31: [u32; 32],
+}
+
+/// 改写前生成的迭代器：保存每个字段的迭代器与范围，每个字节依次检查各字段的范围
+macro_rules! legacy_le_iter {
+    ($name:ident, $iter:ident, $field_ty:ty, $width:expr, [$($field:ident),*]) => {
+        struct $iter {
+            $($field: (<$field_ty as IntoLeIter>::IntoIter, RangeInclusive<usize>),)*
+            current: usize,
+            len: usize,
+        }
+
+        impl Iterator for $iter {
+            type Item = u8;
+            fn next(&mut self) -> Option<u8> {
+                if self.current >= self.len {
+                    return None;
+                }
+                $(
+                    if self.$field.1.contains(&self.current) {
+                        self.current += 1;
+                        return Some(self.$field.0.next().unwrap_or(0));
+                    }
+                )*
+                self.current += 1;
+                Some(0)
+            }
+        }
+
+        impl $name {
+            fn legacy_leiter(self) -> $iter {
+                let mut start = 0usize;
+                $iter {
+                    $($field: {
+                        let pos = start..=start + $width - 1;
+                        start += $width;
+                        (self.$field.into_leiter(), pos)
+                    },)*
+                    current: 0,
+                    len: start,
+                }
+            }
+        }
+    };
+}
+
+legacy_le_iter!(
+    Small,
+    SmallLegacyIter,
+    u32,
+    4,
+    [f0, f1, f2, f3, f4, f5, f6, f7, f8, f9, f10, f11, f12, f13, f14, f15]
+);
+
+legacy_le_iter!(
+    Large,
+    LargeLegacyIter,
+    [u32; 32],
+    128,
+    [
+        f0, f1, f2, f3, f4, f5, f6, f7, f8, f9, f10, f11, f12, f13, f14, f15, f16, f17, f18, f19,
+        f20, f21, f22, f23, f24, f25, f26, f27, f28, f29, f30, f31
+    ]
+);
+
+fn small() -> Small {
+    let bytes: Vec<u8> = (0..64u8).collect();
+    Small::try_from(binary_proc_rt::endian::Le(&bytes[..])).unwrap()
+}
+
+fn large() -> Large {
+    let bytes: Vec<u8> = (0..4096u32).map(|x| x as u8).collect();
+    Large::try_from(binary_proc_rt::endian::Le(&bytes[..])).unwrap()
+}
+
+/// 逐字节消费迭代器，不收集到 Vec
+fn drain(iter: impl Iterator<Item = u8>) -> u8 {
+    iter.fold(0, |acc, x| acc ^ black_box(x))
+}
+
+fn encode(c: &mut Criterion) {
+    let small = small();
+    let large = large();
+    assert!(small
+        .clone()
+        .legacy_leiter()
+        .eq(small.clone().into_leiter()));
+    assert!(large
+        .clone()
+        .legacy_leiter()
+        .eq(large.clone().into_leiter()));
+    c.bench_function("small_le_iter", |b| {
+        b.iter(|| drain(black_box(small.clone()).into_leiter()))
+    });
+    c.bench_function("small_be_iter", |b| {
+        b.iter(|| drain(black_box(small.clone()).into_beiter()))
+    });
+    c.bench_function("large_le_iter", |b| {
+        b.iter(|| drain(black_box(large.clone()).into_leiter()))
+    });
+    c.bench_function("small_le_iter_legacy", |b| {
+        b.iter(|| drain(black_box(small.clone()).legacy_leiter()))
+    });
+    c.bench_function("large_le_iter_legacy", |b| {
+        b.iter(|| drain(black_box(large.clone()).legacy_leiter()))
+    });
+    c.bench_function("large_be_iter", |b| {
+        b.iter(|| drain(black_box(large.clone()).into_beiter()))
+    });
+    // 分配 Vec 的路径
+    c.bench_function("small_le_vec", |b| {
+        b.iter(|| black_box(small.clone()).encode_le())
+    });
+    c.bench_function("large_le_vec", |b| {
+        b.iter(|| black_box(large.clone()).encode_le())
+    });
+    let mut buf = [0u8; 4096];
+    c.bench_function("small_le_into", |b| {
+        b.iter(|| black_box(small.clone()).encode_le_into(&mut buf))
+    });
+    c.bench_function("small_be_into", |b| {
+        b.iter(|| black_box(small.clone()).encode_be_into(&mut buf))
+    });
+    c.bench_function("large_le_into", |b| {
+        b.iter(|| black_box(large.clone()).encode_le_into(&mut buf))
+    });
+    c.bench_function("large_be_into", |b| {
+        b.iter(|| black_box(large.clone()).encode_be_into(&mut buf))
+    });
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! bitmap 生成代码使用的位操作
+
+use core::ops::RangeInclusive;
+
+/// 容器中 `range` 范围内的位，`value` 已右移至最低位
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitRange<T> {
+    pub value: T,
+    pub range: RangeInclusive<u32>,
+}
+
+impl<T: Copy> BitRange<T> {
+    pub fn read(&self) -> T {
+        self.value
+    }
+}
+
+pub trait Bits: Sized + Copy {
+    /// 读取 `range` 范围内的位
+    fn bits(self, range: RangeInclusive<u32>) -> BitRange<Self>;
+    /// 将 `value` 的低位写入 `range` 范围，超出位宽的部分被截断
+    fn set_bits(self, range: RangeInclusive<u32>, value: Self) -> Self;
+}
+
+macro_rules! impl_bits {
+    ($($ty:ty),*) => {
+        $(
+            impl Bits for $ty {
+                fn bits(self, range: RangeInclusive<u32>) -> BitRange<Self> {
+                    let width = range.end() + 1 - range.start();
+                    let mask = <$ty>::MAX.checked_shr(<$ty>::BITS - width).unwrap_or(0);
+                    BitRange {
+                        value: (self >> range.start()) & mask,
+                        range,
+                    }
+                }
+                fn set_bits(self, range: RangeInclusive<u32>, value: Self) -> Self {
+                    let width = range.end() + 1 - range.start();
+                    let mask = <$ty>::MAX.checked_shr(<$ty>::BITS - width).unwrap_or(0);
+                    (self & !(mask << range.start())) | ((value & mask) << range.start())
+                }
+            }
+        )*
+    };
+}
+
+impl_bits!(u8, u16, u32, u64, u128);
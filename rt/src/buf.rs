@@ -0,0 +1,178 @@
+//! `bytes` feature：从 `bytes::Buf` 解码、向 `bytes::BufMut` 编码。
+//!
+//! 数据可跨越多个 chunk（如 `VecDeque<u8>`），此时仅复制解码所需的字节。`chunks_vectored`
+//! 不一定给出全部 chunk，此时只能从 `buf` 的副本中取出字节，因此 `buf` 需实现 `Clone`
+//! （`Chain`、`Take` 等未实现 `Clone`）。解码成功时推进 `buf`，`Incomplete` 与 `Error` 时保持不变：
+//!
+//! ```
+//! use std::collections::VecDeque;
+//!
+//! use binary_proc_rt::{bytemap, bytes::Buf, endian::Le, Decode};
+//!
+//! #[bytemap]
+//! #[derive(Debug, PartialEq)]
+//! struct A {
+//!     #[pos(0..=3)]
+//!     a: u32,
+//! }
+//!
+//! let mut buf = VecDeque::from([1u8, 0, 0]);
+//! assert_eq!(A::decode_from_buf(Le(&mut buf)), Decode::Incomplete { needed: 1 });
+//! assert_eq!(buf.remaining(), 3);
+//! buf.extend([0, 9]);
+//! assert_eq!(A::decode_from_buf(Le(&mut buf)), Decode::Complete(A { a: 1 }, 4));
+//! assert_eq!(buf.remaining(), 1);
+//!
+//! // 当前 chunk 足够时直接编码到其中
+//! let mut out = bytes::BytesMut::new();
+//! assert_eq!(A { a: 2 }.encode_to_buf_be(&mut out), Ok(4));
+//! assert_eq!(&out[..], &[0, 0, 0, 2]);
+//! ```
+
+use std::borrow::Cow;
+use std::io::IoSlice;
+
+use bytes::{Buf, BufMut};
+
+use crate::endian::{Be, Le};
+use crate::{Decode, EncodeError, TryDecode};
+
+/// 不消耗地取出 `buf` 开头的 `limit` 字节，跨越多个 chunk 时复制
+///
+/// `chunks_vectored` 可能只给出部分 chunk（如默认实现只给出 `chunk()`，`Take` 至多给出 16 个），
+/// 此时 `Chain` 拼接出的字节会缺失中间的部分，无法据此查看，返回 None
+fn peek<B: Buf>(buf: &B, limit: usize) -> Option<Cow<'_, [u8]>> {
+    let chunk = buf.chunk();
+    if chunk.len() >= limit {
+        return Some(Cow::Borrowed(&chunk[..limit]));
+    }
+    let mut slices = vec![IoSlice::new(&[]); 16];
+    loop {
+        let count = buf.chunks_vectored(&mut slices);
+        if count == slices.len() {
+            slices.resize(slices.len() * 2, IoSlice::new(&[]));
+            continue;
+        }
+        let total = slices[..count].iter().map(|x| x.len()).sum::<usize>();
+        if total != buf.remaining() {
+            return None;
+        }
+        let mut bytes = Vec::with_capacity(limit);
+        for slice in slices[..count].iter() {
+            let take = slice.len().min(limit - bytes.len());
+            bytes.extend_from_slice(&slice[..take]);
+        }
+        return Some(Cow::Owned(bytes));
+    }
+}
+
+/// 逐步扩大查看的范围直至解码完成，成功时推进 `buf`，否则 `buf` 保持不变
+fn decode<T, B: Buf + Clone>(buf: &mut B, decode: impl Fn(&[u8]) -> Decode<T>) -> Decode<T> {
+    let remaining = buf.remaining();
+    let mut limit = buf.chunk().len().min(remaining);
+    loop {
+        let bytes = match peek(buf, limit) {
+            Some(bytes) => bytes,
+            None => return decode_copied(buf, limit, decode),
+        };
+        // `needed` 相对于实际交给解码的字节数
+        let len = bytes.len();
+        match decode(&bytes) {
+            Decode::Complete(value, consumed) => {
+                buf.advance(consumed);
+                return Decode::Complete(value, consumed);
+            }
+            Decode::Incomplete { needed } if len + needed <= remaining => limit = len + needed,
+            Decode::Incomplete { needed } => {
+                return Decode::Incomplete {
+                    needed: len + needed - remaining,
+                }
+            }
+            Decode::Error(err) => return Decode::Error(err),
+        }
+    }
+}
+
+/// 无法查看 `buf` 时从其副本中逐步取出解码必需的字节，成功时才推进 `buf`
+///
+/// `Incomplete` 给出的是所需字节数的下界，取出的字节在解码成功时必然被消耗
+fn decode_copied<T, B: Buf + Clone>(
+    buf: &mut B,
+    mut limit: usize,
+    decode: impl Fn(&[u8]) -> Decode<T>,
+) -> Decode<T> {
+    let remaining = buf.remaining();
+    let mut copy = buf.clone();
+    let mut bytes = Vec::with_capacity(limit);
+    loop {
+        let start = bytes.len();
+        bytes.resize(limit, 0);
+        copy.copy_to_slice(&mut bytes[start..]);
+        match decode(&bytes) {
+            Decode::Complete(value, consumed) => {
+                buf.advance(consumed);
+                return Decode::Complete(value, consumed);
+            }
+            Decode::Incomplete { needed } if limit + needed <= remaining => limit += needed,
+            Decode::Incomplete { needed } => {
+                return Decode::Incomplete {
+                    needed: limit + needed - remaining,
+                }
+            }
+            Decode::Error(err) => return Decode::Error(err),
+        }
+    }
+}
+
+/// `#[bytemap]` 在 `bytes` feature 下生成 `decode_from_buf`，仅适用于不借用输入的结构体
+pub trait DecodeFromBuf<V>: Sized {
+    fn decode_from_buf(buf: V) -> Decode<Self>;
+}
+
+impl<T, B: Buf + Clone> DecodeFromBuf<Le<&mut B>> for T
+where
+    T: for<'a> TryDecode<Le<&'a [u8]>>,
+{
+    fn decode_from_buf(buf: Le<&mut B>) -> Decode<Self> {
+        decode(buf.0, |bytes| T::try_decode(Le(bytes)))
+    }
+}
+
+impl<T, B: Buf + Clone> DecodeFromBuf<Be<&mut B>> for T
+where
+    T: for<'a> TryDecode<Be<&'a [u8]>>,
+{
+    fn decode_from_buf(buf: Be<&mut B>) -> Decode<Self> {
+        decode(buf.0, |bytes| T::try_decode(Be(bytes)))
+    }
+}
+
+/// `#[bytemap]` 生成的 `encode_to_buf_le`/`encode_to_buf_be` 使用：以 `encode` 编码 `len` 字节写入 `buf`
+///
+/// 当前 chunk 足够时直接在其中编码，否则编码到临时缓冲区后写入；编码失败时 `buf` 保持不变
+pub fn encode_to_buf<B: BufMut>(
+    buf: &mut B,
+    len: usize,
+    encode: impl FnOnce(&mut [u8]) -> Result<usize, EncodeError>,
+) -> Result<usize, EncodeError> {
+    if buf.remaining_mut() < len {
+        return Err(EncodeError::BufferTooSmall { needed: len });
+    }
+    let chunk = buf.chunk_mut();
+    if chunk.len() >= len {
+        let ptr = chunk.as_mut_ptr();
+        // SAFETY: chunk 至少有 `len` 字节可写，先以 0 初始化后再作为切片使用
+        let bytes = unsafe {
+            ptr.write_bytes(0, len);
+            std::slice::from_raw_parts_mut(ptr, len)
+        };
+        let written = encode(bytes)?;
+        // SAFETY: 前 `written` 字节已初始化
+        unsafe { buf.advance_mut(written) };
+        return Ok(written);
+    }
+    let mut bytes = vec![0u8; len];
+    let written = encode(&mut bytes)?;
+    buf.put_slice(&bytes[..written]);
+    Ok(written)
+}
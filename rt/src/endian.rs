@@ -0,0 +1,176 @@
+//! 字节序包装类型
+//!
+//! `Le(&[u8])`/`Be(&[u8])` 作为解码的输入，`IntoLeIter`/`IntoBeIter` 将值编码为字节序列。
+
+use crate::BinarySize;
+
+/// 以小端字节序解释的数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Le<T>(pub T);
+
+/// 以大端字节序解释的数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Be<T>(pub T);
+
+impl<T> Le<T> {
+    /// 以相同的字节序包装 `value`
+    pub fn with<U>(&self, value: U) -> Le<U> {
+        Le(value)
+    }
+}
+
+impl<T> Be<T> {
+    /// 以相同的字节序包装 `value`
+    pub fn with<U>(&self, value: U) -> Be<U> {
+        Be(value)
+    }
+}
+
+/// 字节序列的长度或内容与目标类型不符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBytes;
+
+/// 按小端编码为字节迭代器
+///
+/// `#[bytemap]` 的实现在字段的值不能原样解码时 panic，需要处理该错误时使用 `encode_le_into`
+pub trait IntoLeIter {
+    type Item;
+    type IntoIter: Iterator<Item = Self::Item>;
+    fn into_leiter(self) -> Self::IntoIter;
+}
+
+/// 按大端编码为字节迭代器，见 [`IntoLeIter`]
+pub trait IntoBeIter {
+    type Item;
+    type IntoIter: Iterator<Item = Self::Item>;
+    fn into_beiter(self) -> Self::IntoIter;
+}
+
+macro_rules! impl_number {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Le<&[u8]>> for $ty {
+                type Error = InvalidBytes;
+                fn try_from(value: Le<&[u8]>) -> Result<Self, Self::Error> {
+                    Ok(<$ty>::from_le_bytes(value.0.try_into().map_err(|_| InvalidBytes)?))
+                }
+            }
+            impl TryFrom<Be<&[u8]>> for $ty {
+                type Error = InvalidBytes;
+                fn try_from(value: Be<&[u8]>) -> Result<Self, Self::Error> {
+                    Ok(<$ty>::from_be_bytes(value.0.try_into().map_err(|_| InvalidBytes)?))
+                }
+            }
+            impl IntoLeIter for $ty {
+                type Item = u8;
+                type IntoIter = core::array::IntoIter<u8, { core::mem::size_of::<$ty>() }>;
+                fn into_leiter(self) -> Self::IntoIter {
+                    self.to_le_bytes().into_iter()
+                }
+            }
+            impl IntoBeIter for $ty {
+                type Item = u8;
+                type IntoIter = core::array::IntoIter<u8, { core::mem::size_of::<$ty>() }>;
+                fn into_beiter(self) -> Self::IntoIter {
+                    self.to_be_bytes().into_iter()
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// 全为 0 时为 false，否则为 true
+impl TryFrom<Le<&[u8]>> for bool {
+    type Error = InvalidBytes;
+    fn try_from(value: Le<&[u8]>) -> Result<Self, Self::Error> {
+        Ok(value.0.iter().any(|x| *x != 0))
+    }
+}
+
+impl TryFrom<Be<&[u8]>> for bool {
+    type Error = InvalidBytes;
+    fn try_from(value: Be<&[u8]>) -> Result<Self, Self::Error> {
+        Ok(value.0.iter().any(|x| *x != 0))
+    }
+}
+
+impl IntoLeIter for bool {
+    type Item = u8;
+    type IntoIter = core::iter::Once<u8>;
+    fn into_leiter(self) -> Self::IntoIter {
+        core::iter::once(self as u8)
+    }
+}
+
+impl IntoBeIter for bool {
+    type Item = u8;
+    type IntoIter = core::iter::Once<u8>;
+    fn into_beiter(self) -> Self::IntoIter {
+        core::iter::once(self as u8)
+    }
+}
+
+/// 数组按元素依次解码，每个元素占用 `T::SIZE` 字节
+impl<T, const N: usize> TryFrom<Le<&[u8]>> for [T; N]
+where
+    T: for<'a> TryFrom<Le<&'a [u8]>> + BinarySize,
+{
+    type Error = InvalidBytes;
+    fn try_from(value: Le<&[u8]>) -> Result<Self, Self::Error> {
+        if value.0.len() != T::SIZE * N {
+            return Err(InvalidBytes);
+        }
+        let mut items = value
+            .0
+            .chunks_exact(T::SIZE.max(1))
+            .map(|x| T::try_from(Le(x)));
+        let array = core::array::from_fn(|_| items.next().and_then(Result::ok));
+        if array.iter().any(Option::is_none) {
+            return Err(InvalidBytes);
+        }
+        Ok(array.map(Option::unwrap))
+    }
+}
+
+impl<T, const N: usize> TryFrom<Be<&[u8]>> for [T; N]
+where
+    T: for<'a> TryFrom<Be<&'a [u8]>> + BinarySize,
+{
+    type Error = InvalidBytes;
+    fn try_from(value: Be<&[u8]>) -> Result<Self, Self::Error> {
+        if value.0.len() != T::SIZE * N {
+            return Err(InvalidBytes);
+        }
+        let mut items = value
+            .0
+            .chunks_exact(T::SIZE.max(1))
+            .map(|x| T::try_from(Be(x)));
+        let array = core::array::from_fn(|_| items.next().and_then(Result::ok));
+        if array.iter().any(Option::is_none) {
+            return Err(InvalidBytes);
+        }
+        Ok(array.map(Option::unwrap))
+    }
+}
+
+impl<T: IntoLeIter, const N: usize> IntoLeIter for [T; N] {
+    type Item = T::Item;
+    type IntoIter =
+        core::iter::FlatMap<core::array::IntoIter<T, N>, T::IntoIter, fn(T) -> T::IntoIter>;
+    fn into_leiter(self) -> Self::IntoIter {
+        self.into_iter()
+            .flat_map(T::into_leiter as fn(T) -> T::IntoIter)
+    }
+}
+
+impl<T: IntoBeIter, const N: usize> IntoBeIter for [T; N] {
+    type Item = T::Item;
+    type IntoIter =
+        core::iter::FlatMap<core::array::IntoIter<T, N>, T::IntoIter, fn(T) -> T::IntoIter>;
+    fn into_beiter(self) -> Self::IntoIter {
+        self.into_iter()
+            .flat_map(T::into_beiter as fn(T) -> T::IntoIter)
+    }
+}
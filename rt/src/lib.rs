@@ -0,0 +1,173 @@
+//! `bytemap`、`bitmap`、`restrict` 生成代码所依赖的运行时。
+//!
+//! 使用者只需依赖本 crate：
+//!
+//! ```
+//! use binary_proc_rt::{bytemap, endian::Le};
+//!
+//! #[bytemap]
+//! #[derive(Debug)]
+//! struct A {
+//!     #[pos(0..=1)]
+//!     a: u16,
+//! }
+//!
+//! let a = A::try_from(Le(&[1u8, 0][..])).unwrap();
+//! assert_eq!(a.a, 1);
+//! ```
+//!
+//! 若通过其他 crate 重新导出本 crate，需以 `crate = path` 指定生成代码引用运行时的路径：
+//!
+//! ```
+//! mod facade {
+//!     pub(crate) use ::binary_proc_rt as rt;
+//! }
+//!
+//! #[binary_proc_rt::bitmap(u8, crate = facade::rt)]
+//! #[derive(Debug)]
+//! struct Flags {
+//!     #[pos(0)]
+//!     a: bool,
+//! }
+//! ```
+
+pub mod bits;
+#[cfg(feature = "bytes")]
+pub mod buf;
+pub mod endian;
+pub mod mmio;
+pub mod raw;
+pub mod tlv;
+pub mod varint;
+pub mod wireshark;
+
+#[cfg(feature = "arbitrary")]
+pub use arbitrary;
+#[cfg(feature = "bytemuck")]
+pub use bytemuck;
+#[cfg(feature = "bytes")]
+pub use bytes;
+#[cfg(feature = "nom")]
+pub use nom;
+#[cfg(feature = "proptest")]
+pub use proptest;
+
+pub use binary_proc::{bitmap, bytemap, bytemap_from_c, restrict};
+
+/// 类型编码后占用的字节数，`bytemap(packed)` 以此计算未指定 pos 的字段位置
+pub trait BinarySize {
+    const SIZE: usize;
+}
+
+/// 值是否满足布局约束，如 restrict 的 white_list、bitmap 字段的位宽
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `Validate`",
+    label = "fields of a `#[bytemap(builder)]` struct must implement `Validate`",
+    note = "use `#[bitmap]`, `#[restrict]` or `#[bytemap(builder)]` on the type, or add `impl Validate for {Self} {{}}`"
+)]
+pub trait Validate {
+    fn validate(&self) -> bool {
+        true
+    }
+}
+
+/// 解码失败的字段及其范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub range: core::ops::RangeInclusive<usize>,
+}
+
+/// `#[bytemap(accumulate)]` 生成，解码所有字段并返回全部失败的字段
+pub trait DecodeAll<V>: Sized {
+    fn decode_all(value: V) -> Result<Self, Vec<FieldError>>;
+}
+
+/// `try_decode` 的结果，适用于从环形缓冲区或非阻塞套接字中逐步解码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decode<T, E = core::ops::RangeInclusive<usize>> {
+    /// 解码成功，以及消耗的字节数
+    Complete(T, usize),
+    /// 输入不完整，至少还需要 `needed` 字节才能继续
+    Incomplete { needed: usize },
+    /// 输入有误，与字节数无关
+    Error(E),
+}
+
+/// bytemap 生成，区分输入不完整与输入有误
+pub trait TryDecode<V>: Sized {
+    fn try_decode(value: V) -> Decode<Self>;
+}
+
+/// `{Name}Builder::build` 的错误，携带字段名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// 字段未设置
+    Missing(&'static str),
+    /// 字段的值不满足约束
+    Invalid(&'static str),
+}
+
+/// `encode_le_into` 等编码方法的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// 缓冲区不足，至少需要 `needed` 字节
+    BufferTooSmall { needed: usize },
+    /// 字段的值无法按布局编码，解码后不能得到原值，携带字段名
+    Invalid(&'static str),
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty),*) => {
+        $(
+            impl BinarySize for $ty {
+                const SIZE: usize = ::core::mem::size_of::<$ty>();
+            }
+            impl Validate for $ty {}
+        )*
+    };
+}
+
+impl_primitive!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64, bool);
+
+impl<T: BinarySize, const N: usize> BinarySize for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+}
+
+impl<T: BinarySize, const N: usize> BinarySize for &[T; N] {
+    const SIZE: usize = T::SIZE * N;
+}
+
+impl<T: Validate, const N: usize> Validate for [T; N] {
+    fn validate(&self) -> bool {
+        self.iter().all(T::validate)
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> bool {
+        self.as_ref().is_none_or(T::validate)
+    }
+}
+
+impl<T: Validate + ?Sized> Validate for &T {
+    fn validate(&self) -> bool {
+        T::validate(self)
+    }
+}
+
+impl<T: Validate> Validate for [T] {
+    fn validate(&self) -> bool {
+        self.iter().all(T::validate)
+    }
+}
+
+impl Validate for str {}
+
+impl Validate for String {}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> bool {
+        self.iter().all(T::validate)
+    }
+}
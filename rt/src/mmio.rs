@@ -0,0 +1,50 @@
+//! 内存映射寄存器块。
+//!
+//! `#[bytemap(mmio)]` 生成 `{Name}Mmio`，以基地址指针访问各字段，每次读写均为
+//! 对应宽度、本机字节序的 volatile 访问。指定 `mmio` 参数的 `bitmap` 与 `restrict`
+//! 实现 [`Register`]，可作为寄存器类型，`modify_*` 只改写 bitmap 字段覆盖的位。
+//! 此时 bitmap 的字段只能是 bool 或整数，restrict 的数据须能无损转换为容器类型：
+//!
+//! ```
+//! use binary_proc_rt::{bitmap, bytemap};
+//!
+//! #[bitmap(u32, mmio)]
+//! #[derive(Debug, PartialEq)]
+//! struct Ctrl {
+//!     #[pos(0)]
+//!     enable: bool,
+//!     #[pos(4..=7)]
+//!     mode: u8,
+//! }
+//!
+//! #[bytemap(mmio)]
+//! #[derive(Debug)]
+//! struct Uart {
+//!     #[pos(0..=3)]
+//!     ctrl: Ctrl,
+//!     #[pos(4..=7)]
+//!     data: u32,
+//! }
+//!
+//! // 在主机上以普通内存代替设备地址
+//! let mut memory = [0xff00_0000u32, 0];
+//! let uart = unsafe { UartMmio::new(memory.as_mut_ptr().cast()) };
+//! uart.modify_ctrl(|ctrl| ctrl.mode = 3).unwrap();
+//! uart.write_data(0x41);
+//! assert_eq!(uart.read_ctrl(), Ok(Ctrl { enable: false, mode: 3 }));
+//! assert_eq!(memory, [0xff00_0030, 0x41]);
+//! ```
+
+/// 可映射到寄存器的类型
+pub trait Register: Sized {
+    /// 寄存器的整数表示
+    type Raw: Copy;
+    type Error;
+    fn from_raw(raw: Self::Raw) -> Result<Self, Self::Error>;
+    fn into_raw(self) -> Self::Raw;
+    /// 将 `self` 写入 `raw`，不属于任何字段的位保持不变
+    fn merge_into(self, raw: Self::Raw) -> Self::Raw {
+        let _ = raw;
+        self.into_raw()
+    }
+}
@@ -0,0 +1,103 @@
+//! `#[bytemap(zerocopy)]` 镜像结构使用的定长字节序数值。
+//!
+//! 所有类型均以字节数组存储，对齐为 1，任意位模式均合法，因此镜像结构可由 `&[u8]` 直接转换得到。
+
+use core::fmt::{self, Debug};
+
+/// 对齐为 1、没有填充且任意位模式均合法的类型
+///
+/// # Safety
+///
+/// 实现者必须满足上述条件，`ref_from_bytes` 等方法依赖这些条件进行指针转换。
+pub unsafe trait RawBytes: Sized {
+    /// 将 `bytes` 开头的 `size_of::<Self>()` 个字节视为 `Self`，长度不足时返回 None
+    fn ref_from_bytes(bytes: &[u8]) -> Option<&Self> {
+        let bytes = bytes.get(..core::mem::size_of::<Self>())?;
+        // SAFETY: 长度已检查，对齐为 1 且任意位模式均合法
+        Some(unsafe { &*(bytes.as_ptr() as *const Self) })
+    }
+
+    fn mut_from_bytes(bytes: &mut [u8]) -> Option<&mut Self> {
+        let bytes = bytes.get_mut(..core::mem::size_of::<Self>())?;
+        // SAFETY: 同 ref_from_bytes
+        Some(unsafe { &mut *(bytes.as_mut_ptr() as *mut Self) })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: 没有填充，所有字节均已初始化
+        unsafe {
+            core::slice::from_raw_parts(
+                self as *const Self as *const u8,
+                core::mem::size_of::<Self>(),
+            )
+        }
+    }
+}
+
+unsafe impl RawBytes for u8 {}
+unsafe impl RawBytes for i8 {}
+unsafe impl<T: RawBytes, const N: usize> RawBytes for [T; N] {}
+
+macro_rules! raw_number {
+    ($($le:ident, $be:ident => $ty:ty;)*) => {
+        $(
+            raw_number!(@one $le, $ty, to_le_bytes, from_le_bytes);
+            raw_number!(@one $be, $ty, to_be_bytes, from_be_bytes);
+        )*
+    };
+    (@one $name:ident, $ty:ty, $to:ident, $from:ident) => {
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $name([u8; core::mem::size_of::<$ty>()]);
+
+        impl $name {
+            pub fn new(value: $ty) -> Self {
+                $name(value.$to())
+            }
+            pub fn get(self) -> $ty {
+                <$ty>::$from(self.0)
+            }
+            pub fn set(&mut self, value: $ty) {
+                self.0 = value.$to();
+            }
+        }
+
+        impl From<$ty> for $name {
+            fn from(value: $ty) -> Self {
+                $name::new(value)
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(value: $name) -> Self {
+                value.get()
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&self.get(), f)
+            }
+        }
+
+        unsafe impl RawBytes for $name {}
+
+        #[cfg(feature = "bytemuck")]
+        unsafe impl bytemuck::Zeroable for $name {}
+        #[cfg(feature = "bytemuck")]
+        unsafe impl bytemuck::Pod for $name {}
+    };
+}
+
+raw_number! {
+    U16Le, U16Be => u16;
+    U32Le, U32Be => u32;
+    U64Le, U64Be => u64;
+    U128Le, U128Be => u128;
+    I16Le, I16Be => i16;
+    I32Le, I32Be => i32;
+    I64Le, I64Be => i64;
+    I128Le, I128Be => i128;
+    F32Le, F32Be => f32;
+    F64Le, F64Be => f64;
+}
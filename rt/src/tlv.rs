@@ -0,0 +1,154 @@
+//! 类型-长度-值（TLV）序列。
+//!
+//! `#[restrict(u8, tlv(len = u8))]` 以 restrict 枚举描述各类型码：携带数据的变体的数据
+//! 即该类型的值，按 bytemap 字段的方式从值的字节中解码；无数据的变体只占类型码本身，
+//! 没有长度与值，如 DHCP 的 Pad/End、TCP 的 NOP/EOL。不在 white_list 中的类型码
+//! 解码为 [`Tlv::Unknown`]，保留其原始字节：
+//!
+//! ```
+//! use binary_proc_rt::{bytemap, endian::Be, restrict, tlv::Tlv};
+//!
+//! #[bytemap]
+//! #[derive(Debug, PartialEq)]
+//! struct Lease {
+//!     #[pos(0..=3)]
+//!     seconds: u32,
+//! }
+//!
+//! #[restrict(u8, tlv(len = u8))]
+//! #[derive(Debug, PartialEq)]
+//! enum DhcpOption<'a> {
+//!     #[white_list(0)]
+//!     Pad,
+//!     #[white_list(12)]
+//!     HostName(&'a str),
+//!     #[white_list(51)]
+//!     LeaseTime(Lease),
+//!     #[white_list(53)]
+//!     MessageType(u8),
+//!     #[white_list(255)]
+//!     End,
+//! }
+//!
+//! let input = [53, 1, 5, 0, 12, 2, b'p', b'c', 99, 1, 7, 51, 4, 0, 0, 0x0e, 0x10, 255];
+//! let options = DhcpOption::tlv_iter(Be(&input[..]))
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(
+//!     options,
+//!     [
+//!         Tlv::Known(DhcpOption::MessageType(5)),
+//!         Tlv::Known(DhcpOption::Pad),
+//!         Tlv::Known(DhcpOption::HostName("pc")),
+//!         Tlv::Unknown { code: 99, value: &[7][..] },
+//!         Tlv::Known(DhcpOption::LeaseTime(Lease { seconds: 3600 })),
+//!         Tlv::Known(DhcpOption::End),
+//!     ]
+//! );
+//! // 跳过未知类型
+//! assert_eq!(DhcpOption::tlv_iter(Be(&input[..])).known().count(), 5);
+//! // 值超出输入时返回该项的范围，之后不再产生任何项
+//! let mut iter = DhcpOption::tlv_iter(Be(&input[..14]));
+//! assert_eq!(iter.nth(4), Some(Err(13..=16)));
+//! assert_eq!(iter.next(), None);
+//! ```
+//!
+//! `tlv(..)` 的参数：
+//!
+//! - `len = u8`：长度的整数类型，必须指定
+//! - `len_first`：长度在类型码之前，如 BLE AD 结构
+//! - `len_offset = N`：长度中不属于值的字节数，如 TCP 选项的长度包含类型码与长度本身，为 2
+
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
+
+use crate::endian::{Be, Le};
+
+/// TLV 序列中的一项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tlv<'a, T, C> {
+    /// 类型码在 white_list 中
+    Known(T),
+    /// 未知的类型码，以及值的原始字节
+    Unknown { code: C, value: &'a [u8] },
+}
+
+impl<'a, T, C> Tlv<'a, T, C> {
+    pub fn known(self) -> Option<T> {
+        match self {
+            Tlv::Known(value) => Some(value),
+            Tlv::Unknown { .. } => None,
+        }
+    }
+}
+
+/// 解码出的一项及其字节数，错误为该项中有误或超出输入的范围
+pub type TlvResult<'a, T, C> = Result<(Tlv<'a, T, C>, usize), RangeInclusive<usize>>;
+
+/// `#[restrict(.., tlv(..))]` 生成，解码 TLV 序列中的一项
+pub trait DecodeTlv<'a, V>: Sized {
+    /// 类型码的整数类型
+    type Code;
+    /// 解码 `value` 开头的一项
+    fn decode_tlv(value: V) -> TlvResult<'a, Self, Self::Code>;
+}
+
+/// 依次解码 TLV 序列中的各项，遇到错误后结束
+#[derive(Debug, Clone)]
+pub struct TlvIter<V, T> {
+    value: V,
+    offset: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<V, T> TlvIter<V, T> {
+    pub fn new(value: V) -> Self {
+        Self {
+            value,
+            offset: 0,
+            _marker: PhantomData,
+        }
+    }
+    /// 已解码的字节数，出错后为输入的长度
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+macro_rules! impl_tlv_iter {
+    ($($wrapper:ident),*) => {
+        $(
+            impl<'a, T: DecodeTlv<'a, $wrapper<&'a [u8]>>> TlvIter<$wrapper<&'a [u8]>, T> {
+                /// 跳过未知类型码的项
+                pub fn known(self) -> impl Iterator<Item = Result<T, RangeInclusive<usize>>> + 'a
+                where
+                    T: 'a,
+                    T::Code: 'a,
+                {
+                    self.filter_map(|item| item.map(Tlv::known).transpose())
+                }
+            }
+
+            impl<'a, T: DecodeTlv<'a, $wrapper<&'a [u8]>>> Iterator for TlvIter<$wrapper<&'a [u8]>, T> {
+                type Item = Result<Tlv<'a, T, T::Code>, RangeInclusive<usize>>;
+                fn next(&mut self) -> Option<Self::Item> {
+                    let input = self.value.0;
+                    let rest = input.get(self.offset..).filter(|x| !x.is_empty())?;
+                    let offset = self.offset;
+                    match T::decode_tlv(self.value.with(rest)) {
+                        Ok((item, len)) => {
+                            self.offset += len;
+                            Some(Ok(item))
+                        }
+                        Err(range) => {
+                            self.offset = input.len();
+                            Some(Err(range.start() + offset..=range.end() + offset))
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_tlv_iter!(Le, Be);
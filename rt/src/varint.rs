@@ -0,0 +1,210 @@
+//! 变长整数编码，供 `#[varint(..)]` 字段使用。
+//!
+//! - `uleb128`：DWARF、WebAssembly 中的无符号 LEB128
+//! - `sleb128`：有符号 LEB128
+//! - `protobuf`：protobuf 的 varint，有符号整数按 64 位补码编码，固定占用 10 字节
+//!
+//! 解码只接受最短的编码，且值必须能放入目标类型：
+//!
+//! ```
+//! use binary_proc_rt::{bytemap, endian::Le};
+//!
+//! #[bytemap(packed)]
+//! #[derive(Debug, PartialEq)]
+//! struct Entry {
+//!     #[varint(uleb128)]
+//!     offset: u32,
+//!     #[varint(sleb128)]
+//!     delta: i64,
+//!     tag: u8,
+//! }
+//!
+//! let entry = Entry::try_from(Le(&[0xe5, 0x8e, 0x26, 0x7f, 9][..])).unwrap();
+//! assert_eq!(entry, Entry { offset: 624485, delta: -1, tag: 9 });
+//! assert_eq!(entry.encode_le(), [0xe5, 0x8e, 0x26, 0x7f, 9]);
+//! // 多余的 0x80 0x00 不是最短编码
+//! assert!(Entry::try_from(Le(&[0x80, 0x00, 0x7f, 9][..])).is_err());
+//! ```
+
+/// `bytes` 开头的 varint 的字节数，至多 `max` 字节；未结束时为 `bytes.len() + 1`
+pub fn scan(bytes: &[u8], max: usize) -> usize {
+    match bytes.iter().take(max).position(|x| x & 0x80 == 0) {
+        Some(i) => i + 1,
+        None if bytes.len() >= max => max,
+        None => bytes.len() + 1,
+    }
+}
+
+/// 编码结果，最长 19 字节（128 位）
+#[derive(Debug, Clone)]
+pub struct VarintBytes {
+    bytes: [u8; 19],
+    pos: usize,
+    len: usize,
+}
+
+impl Iterator for VarintBytes {
+    type Item = u8;
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.bytes[..self.len].get(self.pos).copied();
+        self.pos += 1;
+        byte
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len.saturating_sub(self.pos);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for VarintBytes {}
+
+fn encode_unsigned(mut value: u128) -> VarintBytes {
+    let mut bytes = [0u8; 19];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    VarintBytes { bytes, pos: 0, len }
+}
+
+fn encode_signed(mut value: i128) -> VarintBytes {
+    let mut bytes = [0u8; 19];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        // 剩余的位均为符号位，且符号与当前字节的第 6 位一致
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    VarintBytes { bytes, pos: 0, len }
+}
+
+/// 忽略超出 128 位的部分，溢出由重新编码后的比较发现
+fn decode_unsigned(raw: &[u8]) -> u128 {
+    raw.iter().enumerate().fold(0, |value, (i, byte)| {
+        value
+            | ((*byte as u128 & 0x7f)
+                .checked_shl(7 * i as u32)
+                .unwrap_or(0))
+    })
+}
+
+fn decode_signed(raw: &[u8]) -> i128 {
+    let value = decode_unsigned(raw) as i128;
+    let bits = 7 * raw.len() as u32;
+    match raw.last() {
+        Some(last) if last & 0x40 != 0 && bits < 128 => value | (-1i128 << bits),
+        _ => value,
+    }
+}
+
+/// 重新编码与输入一致时，编码是最短的且没有溢出
+fn canonical(raw: &[u8], bytes: VarintBytes) -> bool {
+    bytes.len == raw.len() && bytes.bytes[..bytes.len] == *raw
+}
+
+pub trait Uleb128: Sized + Copy {
+    /// 编码的最大字节数
+    const MAX_LEN: usize;
+    fn encoded_len(self) -> usize;
+    fn encode(self) -> VarintBytes;
+    /// `raw` 必须恰好是一个 varint
+    fn decode(raw: &[u8]) -> Option<Self>;
+}
+
+pub trait Sleb128: Sized + Copy {
+    /// 编码的最大字节数
+    const MAX_LEN: usize;
+    fn encoded_len(self) -> usize;
+    fn encode(self) -> VarintBytes;
+    /// `raw` 必须恰好是一个 varint
+    fn decode(raw: &[u8]) -> Option<Self>;
+}
+
+pub trait Protobuf: Sized + Copy {
+    /// 编码的最大字节数
+    const MAX_LEN: usize;
+    fn encoded_len(self) -> usize;
+    fn encode(self) -> VarintBytes;
+    /// `raw` 必须恰好是一个 varint
+    fn decode(raw: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_uleb128 {
+    ($($ty:ty),*) => {
+        $(
+            impl Uleb128 for $ty {
+                const MAX_LEN: usize = (<$ty>::BITS as usize).div_ceil(7);
+                fn encoded_len(self) -> usize {
+                    encode_unsigned(self as u128).len
+                }
+                fn encode(self) -> VarintBytes {
+                    encode_unsigned(self as u128)
+                }
+                fn decode(raw: &[u8]) -> Option<Self> {
+                    let value = <$ty>::try_from(decode_unsigned(raw)).ok()?;
+                    canonical(raw, Uleb128::encode(value)).then_some(value)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_sleb128 {
+    ($($ty:ty),*) => {
+        $(
+            impl Sleb128 for $ty {
+                const MAX_LEN: usize = (<$ty>::BITS as usize).div_ceil(7);
+                fn encoded_len(self) -> usize {
+                    encode_signed(self as i128).len
+                }
+                fn encode(self) -> VarintBytes {
+                    encode_signed(self as i128)
+                }
+                fn decode(raw: &[u8]) -> Option<Self> {
+                    let value = <$ty>::try_from(decode_signed(raw)).ok()?;
+                    canonical(raw, Sleb128::encode(value)).then_some(value)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_protobuf {
+    ($($ty:ty => $wire:ty),*) => {
+        $(
+            impl Protobuf for $ty {
+                const MAX_LEN: usize = 10;
+                fn encoded_len(self) -> usize {
+                    Protobuf::encode(self).len
+                }
+                fn encode(self) -> VarintBytes {
+                    encode_unsigned(self as $wire as u64 as u128)
+                }
+                fn decode(raw: &[u8]) -> Option<Self> {
+                    let wire = u64::try_from(decode_unsigned(raw)).ok()?;
+                    let value = <$ty>::try_from(wire as $wire).ok()?;
+                    canonical(raw, Protobuf::encode(value)).then_some(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_uleb128!(u8, u16, u32, u64, u128);
+impl_sleb128!(i8, i16, i32, i64, i128);
+impl_protobuf!(u8 => u64, u16 => u64, u32 => u64, u64 => u64, i8 => i64, i16 => i64, i32 => i64, i64 => i64);
@@ -0,0 +1,314 @@
+//! 生成 Wireshark Lua dissector。
+//!
+//! `bytemap`、`bitmap`、`restrict` 加上 `dissector` 参数后实现 [`Dissect`]，
+//! 嵌套的 bitmap 字段展开为带掩码的子字段，restrict 的 white_list 作为取值表：
+//!
+//! ```
+//! use binary_proc_rt::{bitmap, bytemap, wireshark::{lua_dissector, ByteOrder}};
+//!
+//! #[bitmap(u8, dissector)]
+//! #[derive(Debug)]
+//! struct Flags {
+//!     #[pos(0)]
+//!     ack: bool,
+//! }
+//!
+//! #[bytemap(dissector)]
+//! #[derive(Debug)]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//!     #[pos(2)]
+//!     flags: Flags,
+//! }
+//!
+//! let lua = lua_dissector::<Header>("demo", "Demo Protocol", ByteOrder::Little);
+//! assert!(lua.contains("ProtoField.bool(\"demo.flags.ack\""));
+//! // std::fs::write("demo.lua", lua)?;
+//! ```
+
+use std::fmt::Write;
+
+/// 多字节字段的字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+/// 字段在 Wireshark 中的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// 仅作为子树，如嵌套的 bytemap
+    None,
+    /// 无符号整数，参数为位数
+    Uint(u32),
+    /// 有符号整数，参数为位数
+    Int(u32),
+    Float,
+    Double,
+    Bool,
+    Bytes,
+    /// UTF-8 字符串
+    String,
+    /// UTF-16 字符串
+    Utf16,
+}
+
+/// dissector 中的一个字段及其子字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub kind: FieldKind,
+    /// 相对报文开头的偏移
+    pub offset: usize,
+    pub len: usize,
+    /// bitmap 字段在容器中的掩码
+    pub mask: Option<u64>,
+    /// restrict 的取值表，`(下界, 上界, 名称)`
+    pub values: Vec<(i128, i128, String)>,
+    pub children: Vec<Field>,
+}
+
+impl Field {
+    pub fn new(name: &str, kind: FieldKind, offset: usize, len: usize) -> Self {
+        Field {
+            name: name.to_owned(),
+            kind,
+            offset,
+            len,
+            mask: None,
+            values: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// 描述类型在报文中的布局，由 `dissector` 参数生成
+pub trait Dissect {
+    /// 位于 `offset` 处、名为 `name` 的字段
+    fn dissect(name: &str, offset: usize) -> Field;
+}
+
+fn lua_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// `ProtoField` 的构造表达式
+fn proto_field(field: &Field, abbrev: &str) -> String {
+    let (abbrev, name) = (lua_string(abbrev), lua_string(&field.name));
+    let mask = field.mask.map(|x| format!("{:#x}", x));
+    let tail = |base: &str| {
+        let values = match field.values.iter().all(|(lo, hi, _)| lo == hi) {
+            _ if field.values.is_empty() => "nil".to_owned(),
+            true => {
+                let entries = field
+                    .values
+                    .iter()
+                    .map(|(value, _, name)| format!("[{}] = {}", value, lua_string(name)))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", entries.join(", "))
+            }
+            false => {
+                let entries = field
+                    .values
+                    .iter()
+                    .map(|(lo, hi, name)| format!("{{ {}, {}, {} }}", lo, hi, lua_string(name)))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", entries.join(", "))
+            }
+        };
+        let base = match field.values.iter().any(|(lo, hi, _)| lo != hi) {
+            true => "base.RANGE_STRING",
+            false => base,
+        };
+        match &mask {
+            Some(mask) => format!("{}, {}, {}", base, values, mask),
+            None if values == "nil" => base.to_owned(),
+            None => format!("{}, {}", base, values),
+        }
+    };
+    match field.kind {
+        FieldKind::None => format!("ProtoField.none({}, {})", abbrev, name),
+        FieldKind::Uint(bits) => {
+            // bitmap 的容器以十六进制显示
+            let base = match field.children.is_empty() {
+                true => "base.DEC",
+                false => "base.HEX",
+            };
+            format!(
+                "ProtoField.uint{}({}, {}, {})",
+                bits,
+                abbrev,
+                name,
+                tail(base)
+            )
+        }
+        FieldKind::Int(bits) => format!(
+            "ProtoField.int{}({}, {}, {})",
+            bits,
+            abbrev,
+            name,
+            tail("base.DEC")
+        ),
+        FieldKind::Float => format!("ProtoField.float({}, {})", abbrev, name),
+        FieldKind::Double => format!("ProtoField.double({}, {})", abbrev, name),
+        FieldKind::Bool => match &mask {
+            Some(mask) => format!(
+                "ProtoField.bool({}, {}, {}, nil, {})",
+                abbrev,
+                name,
+                field.len * 8,
+                mask
+            ),
+            None => format!("ProtoField.bool({}, {})", abbrev, name),
+        },
+        FieldKind::Bytes => format!("ProtoField.bytes({}, {})", abbrev, name),
+        FieldKind::String | FieldKind::Utf16 => {
+            format!("ProtoField.string({}, {})", abbrev, name)
+        }
+    }
+}
+
+struct Generator {
+    order: ByteOrder,
+    declarations: String,
+    registrations: Vec<String>,
+    body: String,
+    trees: usize,
+}
+
+impl Generator {
+    /// 声明 `field` 及其子字段，并在 `tree` 下添加；报文长度至少为 `checked` 时已在外层检查
+    fn field(&mut self, field: &Field, prefix: &str, tree: &str, indent: usize, checked: usize) {
+        let key = format!("{}.{}", prefix, field.name);
+        writeln!(
+            self.declarations,
+            "f[{}] = {}",
+            lua_string(&key),
+            proto_field(field, &key)
+        )
+        .unwrap();
+        self.registrations.push(format!("f[{}]", lua_string(&key)));
+
+        let end = field.offset + field.len;
+        // 截断的报文只显示完整出现的字段
+        let guarded = end > checked;
+        let pad = "    ".repeat(indent);
+        let inner = match guarded {
+            true => "    ".repeat(indent + 1),
+            false => pad.to_owned(),
+        };
+        let range = format!("buffer({}, {})", field.offset, field.len);
+        if guarded {
+            writeln!(self.body, "{}if buffer:len() >= {} then", pad, end).unwrap();
+        }
+        let add = match (self.order, field.kind) {
+            (_, FieldKind::Utf16) => {
+                let ustring = match self.order {
+                    ByteOrder::Little => "le_ustringz",
+                    ByteOrder::Big => "ustringz",
+                };
+                format!(
+                    "add(f[{}], {}, {}:{}())",
+                    lua_string(&key),
+                    range,
+                    range,
+                    ustring
+                )
+            }
+            (
+                ByteOrder::Little,
+                FieldKind::Uint(_)
+                | FieldKind::Int(_)
+                | FieldKind::Float
+                | FieldKind::Double
+                | FieldKind::Bool,
+            ) => format!("add_le(f[{}], {})", lua_string(&key), range),
+            _ => format!("add(f[{}], {})", lua_string(&key), range),
+        };
+        if field.children.is_empty() {
+            writeln!(self.body, "{}{}:{}", inner, tree, add).unwrap();
+        } else {
+            self.trees += 1;
+            let subtree = format!("t{}", self.trees);
+            writeln!(self.body, "{}local {} = {}:{}", inner, subtree, tree, add).unwrap();
+            let indent = if guarded { indent + 1 } else { indent };
+            for child in field.children.iter() {
+                self.field(child, &key, &subtree, indent, checked.max(end));
+            }
+        }
+        if guarded {
+            writeln!(self.body, "{}end", pad).unwrap();
+        }
+    }
+}
+
+/// `T` 作为协议 `proto` 的 Lua dissector 脚本，需自行注册到端口等 dissector table
+pub fn lua_dissector<T: Dissect>(proto: &str, description: &str, order: ByteOrder) -> String {
+    let root = T::dissect(proto, 0);
+    // bytemap 的字段直接位于协议树下，bitmap 与 restrict 本身作为唯一的字段
+    let fields = match root.kind {
+        FieldKind::None => root.children,
+        _ => vec![Field {
+            name: "value".to_owned(),
+            ..root
+        }],
+    };
+    let mut generator = Generator {
+        order,
+        declarations: String::new(),
+        registrations: Vec::new(),
+        body: String::new(),
+        trees: 0,
+    };
+    for field in fields.iter() {
+        generator.field(field, proto, "tree", 1, 0);
+    }
+
+    let mut lua = String::new();
+    writeln!(lua, "-- generated by binary-proc, do not edit").unwrap();
+    writeln!(
+        lua,
+        "local proto = Proto({}, {})",
+        lua_string(proto),
+        lua_string(description)
+    )
+    .unwrap();
+    writeln!(lua, "local f = {{}}").unwrap();
+    lua.push_str(&generator.declarations);
+    writeln!(lua, "proto.fields = {{").unwrap();
+    for registration in generator.registrations.iter() {
+        writeln!(lua, "    {},", registration).unwrap();
+    }
+    writeln!(lua, "}}").unwrap();
+    writeln!(lua).unwrap();
+    writeln!(lua, "function proto.dissector(buffer, pinfo, root)").unwrap();
+    writeln!(
+        lua,
+        "    pinfo.cols.protocol = {}",
+        lua_string(&proto.to_uppercase())
+    )
+    .unwrap();
+    writeln!(lua, "    local tree = root:add(proto, buffer())").unwrap();
+    lua.push_str(&generator.body);
+    writeln!(lua, "end").unwrap();
+    writeln!(lua).unwrap();
+    writeln!(
+        lua,
+        "-- DissectorTable.get(\"udp.port\"):add(<port>, proto)"
+    )
+    .unwrap();
+    writeln!(lua, "return proto").unwrap();
+    lua
+}
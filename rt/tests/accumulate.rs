@@ -0,0 +1,82 @@
+//! `bytemap(accumulate)`：解码所有字段并返回全部失败的字段
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, restrict, DecodeAll, FieldError};
+
+#[restrict(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Class {
+    #[white_list(1)]
+    Elf32,
+    #[white_list(2)]
+    Elf64,
+}
+
+#[bytemap(accumulate)]
+#[derive(Debug, PartialEq)]
+struct Ident<'a> {
+    #[pos(0..=3)]
+    #[magic = *b"\x7fELF"]
+    magic: [u8; 4],
+    #[pos(4)]
+    class: Class,
+    #[pos(5)]
+    data: Class,
+    #[pos(6..=9)]
+    #[cstr]
+    abi: &'a str,
+    #[pos(10..=11)]
+    version: u16,
+}
+
+const INPUT: [u8; 12] = [0x7f, b'E', b'L', b'F', 2, 1, b's', b'y', b's', 0, 0, 1];
+
+#[test]
+fn valid_input_decodes() {
+    let ident = Ident::decode_all(Be(&INPUT[..])).unwrap();
+    assert_eq!(ident, Ident::try_from(Be(&INPUT[..])).unwrap());
+    assert_eq!(ident.abi, "sys");
+    assert_eq!(ident.version, 1);
+    assert_eq!(
+        <Ident as DecodeAll<Le<&[u8]>>>::decode_all(Le(&INPUT[..])).map(|x| x.version),
+        Ok(0x100)
+    );
+}
+
+#[test]
+fn reports_every_invalid_field() {
+    let mut input = INPUT;
+    input[0] = 0;
+    input[5] = 7;
+    input[6] = 0xff;
+    let errors = Ident::decode_all(Be(&input[..])).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            FieldError {
+                field: "magic",
+                range: 0..=3,
+            },
+            FieldError {
+                field: "data",
+                range: 5..=5,
+            },
+            FieldError {
+                field: "abi",
+                range: 6..=9,
+            },
+        ]
+    );
+    // try_from 只报告第一个
+    assert_eq!(Ident::try_from(Be(&input[..])), Err(0..=3));
+}
+
+#[test]
+fn truncated_fields_are_reported() {
+    let mut input = INPUT;
+    input[4] = 0;
+    let errors = Ident::decode_all(Be(&input[..8])).unwrap_err();
+    let fields = errors.iter().map(|x| x.field).collect::<Vec<_>>();
+    assert_eq!(fields, ["class", "abi", "version"]);
+    assert_eq!(errors[2].range, 10..=11);
+}
@@ -0,0 +1,48 @@
+//! 占满容器位宽的位段：schema、c_header 与 dissector 的掩码计算不溢出
+
+use binary_proc_rt::bitmap;
+use binary_proc_rt::wireshark::Dissect;
+
+#[bitmap(u128, schema)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Wide {
+    #[pos(0..=127)]
+    all: u128,
+}
+
+#[bitmap(u64, schema, c_header, dissector)]
+#[derive(Debug, PartialEq)]
+struct Full {
+    #[pos(0..=63)]
+    all: u64,
+}
+
+#[bitmap(u64, c_header, dissector)]
+#[derive(Debug, PartialEq)]
+struct High {
+    #[pos(0)]
+    low: bool,
+    #[pos(1..=63)]
+    rest: u64,
+}
+
+#[test]
+fn wide_schema() {
+    let mask = format!("{:#x}", u128::MAX);
+    assert!(Wide::KSY_LE.contains(&mask), "{}", Wide::KSY_LE);
+    assert!(Wide::SCHEMA_JSON.contains("\"bits\":[0,127]"));
+    let value = Wide { all: u128::MAX - 1 };
+    assert_eq!(u128::from(value), u128::MAX - 1);
+    assert_eq!(Wide::try_from(u128::MAX - 1), Ok(value));
+}
+
+#[test]
+fn full_width_masks() {
+    let mask = format!("{:#x}", u64::MAX);
+    assert!(Full::KSY_LE.contains(&mask), "{}", Full::KSY_LE);
+    assert!(Full::C_HEADER.contains(&format!("FULL_ALL_MASK ((uint64_t){})", mask)));
+    assert_eq!(Full::dissect("full", 0).children[0].mask, Some(u64::MAX));
+    let children = High::dissect("high", 0).children;
+    assert_eq!(children[0].mask, Some(1));
+    assert_eq!(children[1].mask, Some(u64::MAX - 1));
+}
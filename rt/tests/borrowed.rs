@@ -0,0 +1,195 @@
+//! 借用字段与字符串编码：`&[u8]`、`&[u8; N]`、`&str`、`cstr`、`padded`、`utf16`
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, EncodeError};
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+struct Header<'a> {
+    #[pos(0..=3)]
+    magic: &'a [u8; 4],
+    #[pos(4..=11)]
+    #[cstr]
+    name: &'a str,
+    #[pos(12..=17)]
+    #[padded(b' ')]
+    vendor: &'a str,
+    #[pos(18..=25)]
+    #[utf16(le)]
+    label: String,
+    #[pos(26..=29)]
+    payload: &'a [u8],
+}
+
+const INPUT: [u8; 30] = [
+    b'B', b'I', b'N', b'0', // magic
+    b'e', b't', b'h', b'0', 0, 0, 0, 0, // name
+    b'A', b'C', b'M', b'E', b' ', b' ', // vendor
+    b'h', 0, b'i', 0, 0, 0, 0, 0, // label
+    1, 2, 3, 4, // payload
+];
+
+fn header() -> Header<'static> {
+    Header {
+        magic: b"BIN0",
+        name: "eth0",
+        vendor: "ACME",
+        label: "hi".to_owned(),
+        payload: &[1, 2, 3, 4],
+    }
+}
+
+#[test]
+fn decode_borrows_input() {
+    let decoded = Header::try_from(Le(&INPUT[..])).unwrap();
+    assert_eq!(decoded, header());
+    assert_eq!(decoded.payload.as_ptr(), INPUT[26..].as_ptr());
+    assert_eq!(decoded.name.as_ptr(), INPUT[4..].as_ptr());
+}
+
+#[test]
+fn round_trip() {
+    assert_eq!(header().encode_le(), INPUT);
+    // utf16(le) 固定字节序，其余字段与字节序无关
+    assert_eq!(header().encode_be(), INPUT);
+    assert_eq!(Header::try_from(Be(&INPUT[..])).unwrap(), header());
+}
+
+/// cstr 与 strncpy 一致：可以占满整个 pos 范围，此时没有 NUL，解码时取整个范围
+#[test]
+fn cstr_fills_range() {
+    let value = Header {
+        name: "12345678",
+        ..header()
+    };
+    let bytes = value.encode_le();
+    assert_eq!(&bytes[4..12], b"12345678");
+    assert_eq!(Header::try_from(Le(&bytes[..])).unwrap().name, "12345678");
+
+    // 少一个字节时其后为 NUL
+    let value = Header {
+        name: "1234567",
+        ..header()
+    };
+    let bytes = value.encode_le();
+    assert_eq!(&bytes[4..12], b"1234567\0");
+    assert_eq!(Header::try_from(Le(&bytes[..])).unwrap().name, "1234567");
+
+    // 多一个字节则无法放入
+    let value = Header {
+        name: "123456789",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("name"));
+}
+
+/// 解码时 cstr 截止到第一个 NUL，padded 去掉末尾的填充字节
+#[test]
+fn terminators_trim_on_decode() {
+    let mut input = INPUT;
+    input[6] = 0;
+    input[15] = b' ';
+    let decoded = Header::try_from(Le(&input[..])).unwrap();
+    assert_eq!(decoded.name, "et");
+    assert_eq!(decoded.vendor, "ACM");
+}
+
+#[test]
+fn decode_errors() {
+    let mut input = INPUT;
+    // padded 字符串必须是 ASCII
+    input[12] = 0xc3;
+    assert_eq!(Header::try_from(Le(&input[..])), Err(12..=17));
+    let mut input = INPUT;
+    input[4] = 0xff;
+    assert_eq!(Header::try_from(Le(&input[..])), Err(4..=11));
+    // 不成对的代理项
+    let mut input = INPUT;
+    input[18..20].copy_from_slice(&0xd800u16.to_le_bytes());
+    assert_eq!(Header::try_from(Le(&input[..])), Err(18..=25));
+    assert_eq!(Header::try_from(Le(&INPUT[..29])), Err(26..=29));
+}
+
+fn encode_err(value: Header) -> EncodeError {
+    let mut buf = [0xaau8; 30];
+    let err = value.encode_le_into(&mut buf).unwrap_err();
+    assert_eq!(buf, [0xaa; 30], "buf must be left untouched");
+    err
+}
+
+#[test]
+fn encode_rejects_length_mismatch() {
+    // 无终止符的借用字段必须恰好占满 pos 范围
+    let long = [0u8; 5];
+    let value = Header {
+        payload: &long,
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("payload"));
+    let value = Header {
+        payload: &[1, 2],
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("payload"));
+    let value = Header {
+        name: "123456789",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("name"));
+    let value = Header {
+        vendor: "1234567",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("vendor"));
+    let value = Header {
+        label: "abcde".to_owned(),
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("label"));
+}
+
+/// 解码后会被截去的内容不能编码
+#[test]
+fn encode_rejects_lossy_values() {
+    let value = Header {
+        name: "a\0b",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("name"));
+    let value = Header {
+        vendor: "AB ",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("vendor"));
+    let value = Header {
+        vendor: "é",
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("vendor"));
+    let value = Header {
+        label: "a\0".to_owned(),
+        ..header()
+    };
+    assert_eq!(encode_err(value), EncodeError::Invalid("label"));
+}
+
+#[test]
+fn encode_buffer_too_small() {
+    let mut buf = [0u8; 29];
+    assert_eq!(
+        header().encode_be_into(&mut buf),
+        Err(EncodeError::BufferTooSmall { needed: 30 })
+    );
+    let mut buf = [0u8; 32];
+    assert_eq!(header().encode_be_into(&mut buf), Ok(30));
+}
+
+#[test]
+#[should_panic(expected = "Invalid(\"payload\")")]
+fn encode_le_panics_on_invalid() {
+    Header {
+        payload: &[1, 2],
+        ..header()
+    }
+    .encode_le();
+}
@@ -0,0 +1,203 @@
+//! `bytes` feature：从跨越多个 chunk 的 `Buf` 解码，成功时推进 `buf`
+#![cfg(feature = "bytes")]
+
+use std::collections::VecDeque;
+
+use binary_proc_rt::bytes::Buf;
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, Decode, EncodeError};
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+struct Z {
+    #[pos(0..=1)]
+    kind: u16,
+    #[pos(2..=5)]
+    len: u32,
+    #[pos(6..=11)]
+    arr: [u16; 3],
+}
+
+const INPUT: [u8; 15] = [1, 0, 0, 0, 9, 9, 5, 0, 0, 0, 1, 0, 2, 0, 7];
+
+fn expected() -> Z {
+    Z {
+        kind: 1,
+        len: 0x0909_0000,
+        arr: [5, 0, 1],
+    }
+}
+
+#[bytemap(packed)]
+#[derive(Debug, Clone, PartialEq)]
+struct M {
+    kind: u8,
+    #[prefix(u8)]
+    body: Vec<u8>,
+}
+
+/// 每个字节一个 chunk，`chunks_vectored` 使用只给出 `chunk()` 的默认实现
+#[derive(Clone)]
+struct Fragments(VecDeque<u8>);
+
+impl Buf for Fragments {
+    fn remaining(&self) -> usize {
+        self.0.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        let (front, _) = self.0.as_slices();
+        &front[..front.len().min(1)]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.0.drain(..cnt);
+    }
+}
+
+/// 首尾相接的 `VecDeque` 由两个 chunk 组成
+#[test]
+fn wrapped_deque() {
+    let mut buf = VecDeque::with_capacity(INPUT.len());
+    buf.extend([0xff; 8]);
+    while buf.pop_front() == Some(0xff) {}
+    buf.extend(INPUT);
+    assert!(!buf.as_slices().1.is_empty());
+    assert_eq!(
+        Z::decode_from_buf(Le(&mut buf)),
+        Decode::Complete(expected(), 12)
+    );
+    assert_eq!(buf.remaining(), 3);
+    assert_eq!(buf.chunk(), [2, 0, 7]);
+}
+
+/// `chunks_vectored` 只给出首个 chunk 时，从副本中取出其余的字节
+#[test]
+fn partial_vectored_view() {
+    let mut buf = Fragments(INPUT.iter().copied().collect());
+    assert_eq!(
+        Z::decode_from_buf(Le(&mut buf)),
+        Decode::Complete(expected(), 12)
+    );
+    assert_eq!(buf.remaining(), 3);
+    assert_eq!(buf.get_u8(), 2);
+}
+
+#[test]
+fn incomplete_keeps_buf() {
+    let mut buf = &INPUT[..10];
+    assert_eq!(
+        Z::decode_from_buf(Be(&mut buf)),
+        Decode::Incomplete { needed: 2 }
+    );
+    assert_eq!(buf.remaining(), 10);
+    let mut buf = VecDeque::from(INPUT[..5].to_vec());
+    assert_eq!(
+        Z::decode_from_buf(Le(&mut buf)),
+        Decode::Incomplete { needed: 7 }
+    );
+    assert_eq!(buf.remaining(), 5);
+}
+
+/// 不能查看全部字节时从副本中复制，解码失败不推进 `buf`
+#[test]
+fn fragments_failure_keeps_buf() {
+    let mut buf = Fragments([7, 5, 0xaa, 0xbb].into_iter().collect());
+    assert_eq!(
+        M::decode_from_buf(Le(&mut buf)),
+        Decode::Incomplete { needed: 3 }
+    );
+    assert_eq!(buf.remaining(), 4);
+    assert_eq!(buf.chunk(), [7]);
+
+    let mut buf = Fragments(INPUT[..5].iter().copied().collect());
+    assert_eq!(
+        Z::decode_from_buf(Le(&mut buf)),
+        Decode::Incomplete { needed: 7 }
+    );
+    assert_eq!(buf.remaining(), 5);
+}
+
+#[test]
+fn fragments_variable_length() {
+    let input = [7, 3, 0xaa, 0xbb, 0xcc, 1, 0, 9];
+    let mut buf = Fragments(input.into_iter().collect());
+    let expected = M {
+        kind: 7,
+        body: vec![0xaa, 0xbb, 0xcc],
+    };
+    assert_eq!(
+        M::decode_from_buf(Be(&mut buf)),
+        Decode::Complete(expected, 5)
+    );
+    assert_eq!(buf.remaining(), 3);
+    assert_eq!(
+        M::decode_from_buf(Be(&mut buf)),
+        Decode::Complete(
+            M {
+                kind: 1,
+                body: vec![]
+            },
+            2
+        )
+    );
+    assert_eq!(buf.remaining(), 1);
+    assert_eq!(
+        M::decode_from_buf(Be(&mut buf)),
+        Decode::Incomplete { needed: 1 }
+    );
+    assert_eq!(buf.remaining(), 1);
+}
+
+#[test]
+fn encode_into_chunk() {
+    let mut out = [0xffu8; 8];
+    let mut buf = &mut out[..];
+    assert_eq!(expected_m().encode_to_buf_le(&mut buf), Ok(4));
+    assert_eq!(buf.len(), 4);
+    assert_eq!(out, [7, 2, 1, 2, 0xff, 0xff, 0xff, 0xff]);
+
+    let mut buf = &mut out[..3];
+    assert_eq!(
+        expected_m().encode_to_buf_le(&mut buf),
+        Err(EncodeError::BufferTooSmall { needed: 4 })
+    );
+    assert_eq!(buf.len(), 3);
+}
+
+/// 长度超出前缀类型时编码失败，`buf` 保持不变
+#[test]
+fn encode_failure_keeps_buf() {
+    let value = M {
+        kind: 7,
+        body: vec![0; 300],
+    };
+    let mut out = Vec::new();
+    assert_eq!(
+        value.clone().encode_to_buf_be(&mut out),
+        Err(EncodeError::Invalid("body"))
+    );
+    assert!(out.is_empty());
+    let mut out = bytes::BytesMut::with_capacity(512);
+    assert_eq!(
+        value.encode_to_buf_be(&mut out),
+        Err(EncodeError::Invalid("body"))
+    );
+    assert!(out.is_empty());
+
+    // 超出当前 chunk 时经临时缓冲区写入
+    let value = M {
+        kind: 7,
+        body: vec![3; 200],
+    };
+    let mut out = Vec::new();
+    assert_eq!(value.clone().encode_to_buf_be(&mut out), Ok(202));
+    assert_eq!(out, value.encode_be());
+}
+
+fn expected_m() -> M {
+    M {
+        kind: 7,
+        body: vec![1, 2],
+    }
+}
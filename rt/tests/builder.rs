@@ -0,0 +1,134 @@
+//! `bytemap(builder, default)`：必填字段、magic、default 与 Validate
+
+use binary_proc_rt::endian::Le;
+use binary_proc_rt::{bitmap, bytemap, BuildError, Validate};
+
+#[bitmap(u8)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+struct Flags {
+    #[pos(0..=2)]
+    level: u8,
+    #[pos(3)]
+    urgent: bool,
+}
+
+/// 不带 builder 的 bytemap 需手动实现 Validate 才能作为字段
+#[bytemap]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+struct Extra {
+    #[pos(0)]
+    value: u8,
+}
+
+impl Validate for Extra {}
+
+#[bytemap(builder, default)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Packet {
+    #[pos(0..=1)]
+    #[magic = *b"PK"]
+    magic: [u8; 2],
+    #[pos(2)]
+    #[default = 1]
+    version: u8,
+    #[pos(3)]
+    flags: Flags,
+    #[pos(4)]
+    extra: Extra,
+}
+
+fn flags(level: u8) -> Flags {
+    Flags {
+        level,
+        urgent: true,
+    }
+}
+
+#[test]
+fn build_fills_magic_and_default() {
+    let packet = Packet::builder()
+        .flags(flags(5))
+        .extra(Extra { value: 9 })
+        .build()
+        .unwrap();
+    assert_eq!(packet.magic, *b"PK");
+    assert_eq!(packet.version, 1);
+    let bytes = packet.encode_le();
+    assert_eq!(bytes, [b'P', b'K', 1, 0b1101, 9]);
+    assert_eq!(Packet::try_from(Le(&bytes[..])), Ok(packet));
+}
+
+#[test]
+fn build_reports_missing_field() {
+    assert_eq!(
+        Packet::builder().flags(flags(0)).build(),
+        Err(BuildError::Missing("extra"))
+    );
+}
+
+/// bitmap 字段的值超出位宽
+#[test]
+fn build_reports_invalid_field() {
+    let packet = Packet::builder()
+        .version(2)
+        .flags(flags(8))
+        .extra(Extra { value: 0 })
+        .build();
+    assert_eq!(packet, Err(BuildError::Invalid("flags")));
+}
+
+#[test]
+fn validate_matches_build() {
+    let mut packet = Packet::builder()
+        .flags(flags(7))
+        .extra(Extra { value: 0 })
+        .build()
+        .unwrap();
+    assert!(packet.validate());
+    packet.flags.level = 8;
+    assert!(!packet.validate());
+}
+
+#[test]
+fn default_uses_magic_and_default() {
+    let packet = Packet::default();
+    assert_eq!(packet.magic, *b"PK");
+    assert_eq!(packet.version, 1);
+    assert_eq!(packet.extra, Extra { value: 0 });
+}
+
+#[bytemap(packed, builder)]
+#[derive(Debug, PartialEq)]
+struct Versioned {
+    version: u8,
+    #[when(version >= 2)]
+    ext: Option<u16>,
+}
+
+/// 条件字段必须与条件一致
+#[test]
+fn build_checks_condition() {
+    let value = Versioned::builder().version(2).ext(7).build();
+    assert_eq!(
+        value,
+        Ok(Versioned {
+            version: 2,
+            ext: Some(7),
+        })
+    );
+    assert_eq!(
+        Versioned::builder().version(1).build(),
+        Ok(Versioned {
+            version: 1,
+            ext: None,
+        })
+    );
+    assert_eq!(
+        Versioned::builder().version(1).ext(7).build(),
+        Err(BuildError::Invalid("ext"))
+    );
+    assert_eq!(
+        Versioned::builder().version(2).build(),
+        Err(BuildError::Invalid("ext"))
+    );
+}
@@ -0,0 +1,100 @@
+//! `c_header` 生成的头文件与 tests/golden 中的文件比对，有 C 编译器时还会编译校验
+//! `_Static_assert`；设置 `UPDATE_GOLDEN=1` 重新生成
+
+use std::process::Command;
+
+use binary_proc_rt::{bitmap, bytemap, restrict};
+
+#[restrict(u8, c_header)]
+#[derive(Debug)]
+enum Kind {
+    #[white_list(1)]
+    Ping,
+    #[white_list(2..=4, 8..10)]
+    Data,
+    #[white_list(0x80..)]
+    Vendor(u8),
+}
+
+#[bitmap(u16, c_header)]
+#[derive(Debug)]
+struct Flags {
+    #[pos(0)]
+    ack: bool,
+    #[pos(1..=3)]
+    level: u8,
+    #[pos(8..=15)]
+    window: u8,
+}
+
+#[bytemap(c_header)]
+#[derive(Debug)]
+#[reserved(2..=3)]
+struct Packet {
+    #[pos(0)]
+    kind: Kind,
+    #[pos(1)]
+    version: u8,
+    #[pos(4..=5)]
+    flags: Flags,
+    #[pos(6..=7)]
+    len: u16,
+    #[pos(8..=15)]
+    id: [u16; 4],
+    #[pos(16..=19)]
+    checksum: u32,
+}
+
+/// 按依赖顺序拼接的完整头文件
+fn header() -> String {
+    format!(
+        "#include <stdint.h>\n\n{}\n{}\n{}",
+        Kind::C_HEADER,
+        Flags::C_HEADER,
+        Packet::C_HEADER
+    )
+}
+
+fn check_golden(name: &str, actual: &str) {
+    let path = format!("{}/tests/golden/{}", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(actual, expected, "{} is out of date", name);
+}
+
+#[test]
+fn golden() {
+    check_golden("packet.h", &header());
+}
+
+/// 没有 C 编译器时跳过
+#[test]
+fn compiles() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_header");
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("packet.c");
+    std::fs::write(
+        &source,
+        format!("{}\nint main(void) {{ return 0; }}\n", header()),
+    )
+    .unwrap();
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let output = match Command::new(&cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-fsyntax-only"])
+        .arg(&source)
+        .output()
+    {
+        Ok(output) => output,
+        Err(err) => {
+            eprintln!("skip: can not run {}: {}", cc, err);
+            return;
+        }
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,64 @@
+//! `bytemap_from_c!`：按 C 的对齐规则由结构体声明计算 pos
+
+use binary_proc_rt::bytemap_from_c;
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::BinarySize;
+
+bytemap_from_c! {
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct version {
+        uint8_t major, minor;
+    };
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct vendor_header {
+        uint16_t kind;
+        uint32_t len;
+        uint8_t mac[6];
+        struct version version;
+        int16_t grid[2][2];
+    };
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    typedef struct __attribute__((packed)) {
+        uint8_t flags;
+        uint32_t magic;
+        struct {
+            uint8_t a;
+            uint16_t b;
+        } inner;
+    } packed_t;
+}
+
+/// 自然对齐：len 位于 4..=7，2..=3 与末尾的空隙为 reserved
+#[test]
+fn natural_alignment() {
+    assert_eq!(<VendorHeader as BinarySize>::SIZE, 24);
+    let header = VendorHeader {
+        kind: 0x0102,
+        len: 0x0304_0506,
+        mac: [1, 2, 3, 4, 5, 6],
+        version: Version { major: 1, minor: 2 },
+        grid: [[-1, 2], [3, -4]],
+    };
+    let le = header.encode_le();
+    assert_eq!(le.len(), 24);
+    assert_eq!(le[..8], [2, 1, 0, 0, 6, 5, 4, 3]);
+    assert_eq!(le[8..16], [1, 2, 3, 4, 5, 6, 1, 2]);
+    assert_eq!(le[16..], [0xff, 0xff, 2, 0, 3, 0, 0xfc, 0xff]);
+    assert_eq!(VendorHeader::try_from(Le(&le[..])), Ok(header));
+}
+
+/// packed 时字段紧密排列，但与 GCC 一致，内联结构体自身的空隙保留；typedef 名称去掉 `_t` 后缀
+#[test]
+fn packed() {
+    assert_eq!(<Packed as BinarySize>::SIZE, 9);
+    let value = Packed {
+        flags: 0x80,
+        magic: 0xcafe_babe,
+        inner: PackedInner { a: 1, b: 0x0203 },
+    };
+    let be = value.encode_be();
+    assert_eq!(be, [0x80, 0xca, 0xfe, 0xba, 0xbe, 1, 0, 2, 3]);
+    assert_eq!(Packed::try_from(Be(&be[..])), Ok(value));
+}
@@ -0,0 +1,66 @@
+//! 条件字段：`#[when(..)]` 引用之前解码的字段，布局长度随之变化
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{Be, Le};
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq)]
+struct Record {
+    version: u8,
+    #[when(version >= 2)]
+    ext: Option<u16>,
+    flags: u8,
+}
+
+#[test]
+fn absent_when_condition_fails() {
+    let value = Record {
+        version: 1,
+        ext: None,
+        flags: 0xaa,
+    };
+    assert_eq!(Record::try_from(Be(&[1u8, 0xaa][..])), Ok(value));
+    let value = Record {
+        version: 1,
+        ext: None,
+        flags: 0xaa,
+    };
+    assert_eq!(value.encoded_len(), 2);
+    assert_eq!(value.encode_be(), [1, 0xaa]);
+}
+
+#[test]
+fn present_when_condition_holds() {
+    let input = [2u8, 0x12, 0x34, 0xaa];
+    let value = Record::try_from(Be(&input[..])).unwrap();
+    assert_eq!(
+        value,
+        Record {
+            version: 2,
+            ext: Some(0x1234),
+            flags: 0xaa,
+        }
+    );
+    assert_eq!(value.encoded_len(), 4);
+    assert_eq!(value.encode_be(), input);
+    let le = Record::try_from(Le(&input[..])).unwrap();
+    assert_eq!(le.ext, Some(0x3412));
+    assert_eq!(le.encode_le(), input);
+}
+
+/// 后续字段的位置随条件字段移动
+#[test]
+fn later_fields_shift() {
+    let v1 = Record::try_from(Be(&[1u8, 0xaa, 0xbb, 0xcc][..])).unwrap();
+    assert_eq!(v1.flags, 0xaa);
+    let v2 = Record::try_from(Be(&[2u8, 0xaa, 0xbb, 0xcc][..])).unwrap();
+    assert_eq!(v2.ext, Some(0xaabb));
+    assert_eq!(v2.flags, 0xcc);
+}
+
+#[test]
+fn short_input_reports_shifted_range() {
+    assert_eq!(Record::try_from(Be(&[2u8, 0x12][..])), Err(1..=2));
+    assert_eq!(Record::try_from(Be(&[2u8, 0x12, 0x34][..])), Err(3..=3));
+    assert_eq!(Record::try_from(Be(&[1u8][..])), Err(1..=1));
+}
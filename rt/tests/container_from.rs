@@ -0,0 +1,107 @@
+//! restrict 与 bitmap 只在数据能无损转换为容器类型时才生成 `From` 与编码实现
+
+use binary_proc_rt::endian::{IntoLeIter, Le};
+use binary_proc_rt::{bitmap, restrict};
+
+/// 只能从 `u8` 转换而来的自定义类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Level(u8);
+
+impl TryFrom<u8> for Level {
+    type Error = u8;
+    fn try_from(value: u8) -> Result<Self, u8> {
+        if value >= 100 {
+            Ok(Level(value))
+        } else {
+            Err(value)
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> binary_proc_rt::arbitrary::Arbitrary<'a> for Level {
+    fn arbitrary(
+        u: &mut binary_proc_rt::arbitrary::Unstructured<'a>,
+    ) -> binary_proc_rt::arbitrary::Result<Self> {
+        Ok(Level(u.int_in_range(100..=127)?))
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl binary_proc_rt::proptest::arbitrary::Arbitrary for Level {
+    type Parameters = ();
+    type Strategy =
+        binary_proc_rt::proptest::strategy::Map<::core::ops::RangeInclusive<u8>, fn(u8) -> Level>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use binary_proc_rt::proptest::strategy::Strategy;
+        (100..=127u8).prop_map(Level)
+    }
+}
+
+#[restrict(u8)]
+#[derive(Debug, PartialEq)]
+enum Custom {
+    #[white_list(0)]
+    Off,
+    #[white_list(100..=200)]
+    Level(Level),
+}
+
+#[restrict(u8, u16)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Narrow {
+    #[white_list(0)]
+    Off,
+    #[white_list(1..=255)]
+    Value(u8),
+}
+
+#[restrict(u8, u16)]
+#[derive(Debug, PartialEq)]
+enum Wide {
+    #[white_list(0)]
+    Off,
+    #[white_list(1..=255)]
+    Value(u16),
+}
+
+#[bitmap(u8)]
+#[derive(Debug, PartialEq)]
+struct Flags {
+    #[pos(0)]
+    enable: bool,
+    #[pos(1..=7)]
+    level: Level,
+}
+
+#[test]
+fn custom_payload_decodes_without_from() {
+    assert_eq!(Custom::try_from(0u8), Ok(Custom::Off));
+    assert_eq!(Custom::try_from(150u8), Ok(Custom::Level(Level(150))));
+    assert!(Custom::try_from(50u8).is_err());
+    assert_eq!(
+        Flags::try_from(0b1110_0101u8),
+        Ok(Flags {
+            enable: true,
+            level: Level(0b111_0010)
+        })
+    );
+    assert!(Flags::try_from(0b0000_0001u8).is_err());
+}
+
+#[test]
+fn integer_payload_encodes_to_each_wider_container() {
+    let value = Narrow::Value(7);
+    assert_eq!(u8::from(value), 7);
+    assert_eq!(u16::from(value), 7);
+    assert_eq!(u8::from(Narrow::Off), 0);
+    assert_eq!(value.into_leiter().collect::<Vec<_>>(), [7]);
+    assert_eq!(Narrow::try_from(Le(&[7u8][..])), Ok(value));
+}
+
+#[test]
+fn wider_payload_only_encodes_to_wide_enough_container() {
+    assert_eq!(u16::from(Wide::Value(255)), 255);
+    assert_eq!(u16::from(Wide::Off), 0);
+    assert_eq!(Wide::try_from(255u8), Ok(Wide::Value(255)));
+}
@@ -0,0 +1,84 @@
+//! `crate = path`：生成代码经由重新导出的路径引用运行时
+
+mod facade {
+    pub mod codec {
+        pub use binary_proc_rt as rt;
+    }
+}
+
+use facade::codec::rt::endian::{Be, IntoBeIter, Le};
+
+#[facade::codec::rt::bitmap(u8, crate = facade::codec::rt)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Flags {
+    #[pos(0)]
+    ack: bool,
+    #[pos(1..=3)]
+    level: u8,
+}
+
+#[facade::codec::rt::restrict(u8, crate = facade::codec::rt)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    #[white_list(1)]
+    Request,
+    #[white_list(2..=9)]
+    Other(u8),
+}
+
+#[facade::codec::rt::bytemap(builder, crate = facade::codec::rt)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Header {
+    #[pos(0)]
+    kind: Kind,
+    #[pos(1)]
+    flags: Flags,
+    #[pos(2..=3)]
+    len: u16,
+}
+
+#[facade::codec::rt::bytemap(packed, crate = facade::codec::rt)]
+#[derive(Debug, PartialEq)]
+struct Entry<'a> {
+    #[varint(uleb128)]
+    id: u32,
+    #[prefix(u8)]
+    name: &'a str,
+}
+
+#[test]
+fn all_macros_through_facade() {
+    let header = Header::builder()
+        .kind(Kind::Other(5))
+        .flags(Flags {
+            ack: true,
+            level: 3,
+        })
+        .len(0x0102)
+        .build()
+        .unwrap();
+    let bytes = header.encode_be();
+    assert_eq!(bytes, [5, 0b111, 1, 2]);
+    assert_eq!(Header::try_from(Be(&bytes[..])), Ok(header));
+    assert_eq!(Kind::Request.into_beiter().collect::<Vec<_>>(), [1]);
+    assert_eq!(
+        Flags::try_from(0b1001u8),
+        Ok(Flags {
+            ack: true,
+            level: 4
+        })
+    );
+}
+
+#[test]
+fn encodings_through_facade() {
+    let entry = Entry::try_from(Le(&[0x81, 0x01, 2, b'o', b'k'][..])).unwrap();
+    assert_eq!(
+        entry,
+        Entry {
+            id: 129,
+            name: "ok"
+        }
+    );
+    assert_eq!(entry.encode_le(), [0x81, 0x01, 2, b'o', b'k']);
+}
@@ -0,0 +1,44 @@
+//! 布局诊断不误报：reserved 的空隙、紧密排列的 align/pad 与任意宽度的 bool
+#![deny(deprecated)]
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::Le;
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+#[reserved(2..=3, 8)]
+struct Fixed {
+    #[pos(0..=1)]
+    a: u16,
+    #[pos(4..=7)]
+    b: u32,
+    /// bool 可占用任意字节数
+    #[pos(9..=10)]
+    flag: bool,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq)]
+struct Packed {
+    a: u8,
+    #[align(4)]
+    b: u32,
+    #[pad(1)]
+    c: u8,
+}
+
+#[test]
+fn gaps_marked_reserved_compile_cleanly() {
+    let fixed = Fixed::try_from(Le(&[1u8, 0, 9, 9, 2, 0, 0, 0, 9, 0, 1][..])).unwrap();
+    assert_eq!(
+        fixed,
+        Fixed {
+            a: 1,
+            b: 2,
+            flag: true,
+        }
+    );
+    assert_eq!(fixed.encode_le(), [1, 0, 0, 0, 2, 0, 0, 0, 0, 1, 0]);
+    let packed = Packed::try_from(Le(&[1u8, 0, 0, 0, 2, 0, 0, 0, 0, 3][..])).unwrap();
+    assert_eq!(packed, Packed { a: 1, b: 2, c: 3 });
+}
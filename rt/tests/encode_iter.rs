@@ -0,0 +1,123 @@
+//! `IntoLeIter`/`IntoBeIter`：定长布局的迭代器不分配内存，与 `encode_le`/`encode_be` 一致
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{IntoBeIter, IntoLeIter};
+
+/// 统计当前线程的分配次数
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let value = f();
+    (value, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[bytemap]
+#[derive(Debug, Clone)]
+#[reserved(6..=7)]
+struct Fixed<'a> {
+    #[pos(0..=1)]
+    a: u16,
+    #[pos(2..=3)]
+    tag: &'a [u8],
+    #[pos(4..=5)]
+    b: [u8; 2],
+}
+
+#[bytemap(packed)]
+#[derive(Debug, Clone)]
+struct Nested {
+    head: u8,
+    inner: [u16; 2],
+    tail: u32,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, Clone)]
+struct Variable {
+    #[varint(uleb128)]
+    a: u32,
+    b: u8,
+}
+
+fn fixed() -> Fixed<'static> {
+    Fixed {
+        a: 0x0102,
+        tag: b"ok",
+        b: [7, 8],
+    }
+}
+
+#[test]
+fn fixed_iter_does_not_allocate() {
+    let (sum, count) = allocations(|| fixed().into_leiter().map(u32::from).sum::<u32>());
+    assert_eq!(count, 0);
+    assert_eq!(sum, 2 + 1 + u32::from(b'o') + u32::from(b'k') + 7 + 8);
+    let nested = Nested {
+        head: 1,
+        inner: [2, 3],
+        tail: 4,
+    };
+    let (len, count) = allocations(|| nested.into_beiter().len());
+    assert_eq!((len, count), (9, 0));
+}
+
+#[test]
+fn iter_matches_encode() {
+    let iter = fixed().into_leiter();
+    assert_eq!(iter.len(), 8);
+    assert_eq!(iter.collect::<Vec<_>>(), fixed().encode_le());
+    assert_eq!(
+        fixed().into_beiter().collect::<Vec<_>>(),
+        fixed().encode_be()
+    );
+    assert_eq!(fixed().encode_be(), [1, 2, b'o', b'k', 7, 8, 0, 0]);
+    // 变长布局仍先编码到 Vec
+    let variable = Variable { a: 300, b: 9 };
+    let (_, count) = allocations(|| variable.clone().into_leiter().len());
+    assert!(count > 0);
+    let iter = variable.clone().into_leiter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.collect::<Vec<_>>(), variable.encode_le());
+}
+
+#[test]
+fn iter_drains_in_order() {
+    let mut iter = fixed().into_beiter();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.size_hint(), (7, Some(7)));
+    assert_eq!(iter.nth(5), Some(0));
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+#[should_panic(expected = "Invalid(\"tag\")")]
+fn iter_panics_on_invalid_value() {
+    Fixed {
+        tag: b"too long",
+        ..fixed()
+    }
+    .into_leiter()
+    .count();
+}
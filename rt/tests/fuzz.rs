@@ -0,0 +1,173 @@
+//! `arbitrary` 与 `proptest` feature：生成的值总能编码，并解码为原值
+#![cfg(all(feature = "arbitrary", feature = "proptest"))]
+
+use binary_proc_rt::arbitrary::{Arbitrary, Unstructured};
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::proptest::prelude::*;
+
+/// 生成代码只通过 `crate =` 指定的路径引用运行时
+mod facade {
+    pub use binary_proc_rt as rt;
+}
+
+#[facade::rt::bitmap(u8, crate = facade::rt)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Flags {
+    #[pos(0..=2)]
+    level: u8,
+    #[pos(3..=6)]
+    delta: i8,
+    #[pos(7)]
+    urgent: bool,
+}
+
+#[facade::rt::restrict(u8, crate = facade::rt)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    #[white_list(1..=5)]
+    Small(u8),
+    #[white_list(9)]
+    Nine,
+}
+
+#[facade::rt::bytemap(packed, crate = facade::rt)]
+#[derive(Debug, PartialEq, Clone)]
+struct Owned {
+    version: u8,
+    #[when(version >= 2)]
+    ext: Option<u16>,
+    flags: Flags,
+    kind: Kind,
+    #[varint(uleb128)]
+    offset: u64,
+    #[varint(sleb128)]
+    delta: i32,
+    #[varint(protobuf)]
+    signed: i16,
+    #[prefix(u8, min = 1, max = 4)]
+    name: String,
+    #[prefix(u8, max = 3)]
+    ports: Vec<u16>,
+}
+
+#[facade::rt::bytemap(packed, crate = facade::rt)]
+#[derive(Debug, PartialEq, Clone)]
+struct Borrowed<'a> {
+    #[prefix(u8)]
+    payload: &'a [u8],
+    #[prefix(u16, be, min = 2, max = 300)]
+    label: &'a str,
+}
+
+#[facade::rt::bytemap(crate = facade::rt)]
+#[derive(Debug, PartialEq, Clone)]
+struct Fixed<'a> {
+    #[pos(0..=3)]
+    name: &'a str,
+    #[pos(4..=7)]
+    #[padded(b' ')]
+    label: &'a str,
+    #[pos(8..=11)]
+    #[cstr]
+    tag: &'a str,
+    #[pos(12..=13)]
+    raw: &'a [u8],
+}
+
+fn check_owned(value: &Owned) {
+    assert!(!value.name.is_empty() && value.name.len() <= 4);
+    assert!(value.ports.len() <= 3);
+    let le = value.clone().encode_le();
+    assert_eq!(Owned::try_from(Le(&le[..])).as_ref(), Ok(value));
+    let be = value.clone().encode_be();
+    assert_eq!(Owned::try_from(Be(&be[..])).as_ref(), Ok(value));
+}
+
+/// 以固定的伪随机数据驱动 arbitrary
+fn data(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+#[test]
+fn arbitrary_values_round_trip() {
+    let mut generated = 0;
+    for seed in 0..500 {
+        let data = data(seed, 1024);
+        let mut u = Unstructured::new(&data);
+        if let Ok(value) = Owned::arbitrary(&mut u) {
+            check_owned(&value);
+            generated += 1;
+        }
+        let mut u = Unstructured::new(&data);
+        if let Ok(value) = Borrowed::arbitrary(&mut u) {
+            assert!(value.payload.len() <= 255);
+            assert!((2..=300).contains(&value.label.len()));
+            let bytes = value.clone().encode_le();
+            assert_eq!(Borrowed::try_from(Le(&bytes[..])), Ok(value));
+        }
+    }
+    assert!(generated > 400);
+}
+
+/// 生成的每个值都能编码：无终止符的字符串恰好占满 pos 范围
+#[test]
+fn arbitrary_fixed_width_encodes() {
+    let mut generated = 0;
+    for seed in 0..2000 {
+        let data = data(seed, 64);
+        let mut u = Unstructured::new(&data);
+        if let Ok(value) = Fixed::arbitrary(&mut u) {
+            assert_eq!(value.name.len(), 4);
+            let mut buf = [0u8; 14];
+            assert_eq!(value.clone().encode_le_into(&mut buf), Ok(14));
+            assert_eq!(Fixed::try_from(Le(&buf[..])), Ok(value));
+            generated += 1;
+        }
+        let mut u = Unstructured::new(&data);
+        if let Ok(bytes) = Fixed::arbitrary_le_bytes(&mut u) {
+            assert!(Fixed::try_from(Le(&bytes[..])).is_ok());
+        }
+    }
+    assert!(generated > 0);
+}
+
+#[test]
+fn arbitrary_bytes_decode() {
+    for seed in 0..200 {
+        let data = data(seed, 256);
+        let mut u = Unstructured::new(&data);
+        if let Ok(bytes) = Owned::arbitrary_le_bytes(&mut u) {
+            assert!(Owned::try_from(Le(&bytes[..])).is_ok());
+        }
+        let mut u = Unstructured::new(&data);
+        if let Ok(bytes) = Borrowed::arbitrary_be_bytes(&mut u) {
+            assert!(Borrowed::try_from(Be(&bytes[..])).is_ok());
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest_values_round_trip(value in any::<Owned>()) {
+        check_owned(&value);
+    }
+
+    #[test]
+    fn proptest_bytes_decode(bytes in Owned::be_bytes_strategy()) {
+        prop_assert!(Owned::try_from(Be(&bytes[..])).is_ok());
+    }
+
+    #[test]
+    fn proptest_bitmap_in_range(flags in any::<Flags>(), kind in any::<Kind>()) {
+        prop_assert!(flags.level < 8 && (-8..8).contains(&flags.delta));
+        prop_assert!(matches!(kind, Kind::Small(1..=5) | Kind::Nine));
+    }
+}
@@ -0,0 +1,27 @@
+-- generated by binary-proc, do not edit
+local proto = Proto("flags", "Flags")
+local f = {}
+f["flags.value"] = ProtoField.uint8("flags.value", "value", base.HEX)
+f["flags.value.ack"] = ProtoField.bool("flags.value.ack", "ack", 8, nil, 0x1)
+f["flags.value.priority"] = ProtoField.uint8("flags.value.priority", "priority", base.DEC, nil, 0xe)
+f["flags.value.delta"] = ProtoField.int8("flags.value.delta", "delta", base.DEC, nil, 0xf0)
+proto.fields = {
+    f["flags.value"],
+    f["flags.value.ack"],
+    f["flags.value.priority"],
+    f["flags.value.delta"],
+}
+
+function proto.dissector(buffer, pinfo, root)
+    pinfo.cols.protocol = "FLAGS"
+    local tree = root:add(proto, buffer())
+    if buffer:len() >= 1 then
+        local t1 = tree:add_le(f["flags.value"], buffer(0, 1))
+        t1:add_le(f["flags.value.ack"], buffer(0, 1))
+        t1:add_le(f["flags.value.priority"], buffer(0, 1))
+        t1:add_le(f["flags.value.delta"], buffer(0, 1))
+    end
+end
+
+-- DissectorTable.get("udp.port"):add(<port>, proto)
+return proto
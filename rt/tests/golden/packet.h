@@ -0,0 +1,96 @@
+#include <stdint.h>
+
+#ifndef BINARY_PROC_KIND_H
+#define BINARY_PROC_KIND_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+typedef uint8_t Kind;
+
+enum KindVariant {
+    KIND_PING,
+    KIND_DATA,
+    KIND_VENDOR,
+};
+
+static inline bool kind_is_ping(uint8_t value) {
+    return value == 1;
+}
+
+static inline bool kind_is_data(uint8_t value) {
+    return (value >= 2 && value <= 4) || (value >= 8 && value <= 9);
+}
+
+static inline bool kind_is_vendor(uint8_t value) {
+    return value >= 128;
+}
+
+/* enum KindVariant of value, or -1 if no white_list matches */
+static inline int kind_variant(uint8_t value) {
+    if (kind_is_ping(value)) return KIND_PING;
+    if (kind_is_data(value)) return KIND_DATA;
+    if (kind_is_vendor(value)) return KIND_VENDOR;
+    return -1;
+}
+
+#endif /* BINARY_PROC_KIND_H */
+
+#ifndef BINARY_PROC_FLAGS_H
+#define BINARY_PROC_FLAGS_H
+
+#include <stdint.h>
+
+typedef uint16_t Flags;
+
+#define FLAGS_ACK_SHIFT 0
+#define FLAGS_ACK_MASK ((uint16_t)0x1)
+#define FLAGS_ACK_GET(raw) (((uint16_t)(raw) >> FLAGS_ACK_SHIFT) & FLAGS_ACK_MASK)
+#define FLAGS_ACK_SET(raw, value) ((uint16_t)(((uint16_t)(raw) & ~(FLAGS_ACK_MASK << FLAGS_ACK_SHIFT)) | (((uint16_t)(value) & FLAGS_ACK_MASK) << FLAGS_ACK_SHIFT)))
+
+#define FLAGS_LEVEL_SHIFT 1
+#define FLAGS_LEVEL_MASK ((uint16_t)0x7)
+#define FLAGS_LEVEL_GET(raw) (((uint16_t)(raw) >> FLAGS_LEVEL_SHIFT) & FLAGS_LEVEL_MASK)
+#define FLAGS_LEVEL_SET(raw, value) ((uint16_t)(((uint16_t)(raw) & ~(FLAGS_LEVEL_MASK << FLAGS_LEVEL_SHIFT)) | (((uint16_t)(value) & FLAGS_LEVEL_MASK) << FLAGS_LEVEL_SHIFT)))
+
+#define FLAGS_WINDOW_SHIFT 8
+#define FLAGS_WINDOW_MASK ((uint16_t)0xff)
+#define FLAGS_WINDOW_GET(raw) (((uint16_t)(raw) >> FLAGS_WINDOW_SHIFT) & FLAGS_WINDOW_MASK)
+#define FLAGS_WINDOW_SET(raw, value) ((uint16_t)(((uint16_t)(raw) & ~(FLAGS_WINDOW_MASK << FLAGS_WINDOW_SHIFT)) | (((uint16_t)(value) & FLAGS_WINDOW_MASK) << FLAGS_WINDOW_SHIFT)))
+
+#endif /* BINARY_PROC_FLAGS_H */
+
+/* Packet: multi-byte fields are stored in wire byte order */
+#ifndef BINARY_PROC_PACKET_H
+#define BINARY_PROC_PACKET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#pragma pack(push, 1)
+typedef struct {
+    Kind kind;
+    uint8_t version;
+    uint8_t reserved_2[2];
+    Flags flags;
+    uint16_t len;
+    uint16_t id[4];
+    uint32_t checksum;
+} Packet;
+#pragma pack(pop)
+
+_Static_assert(sizeof(Packet) == 20, "Packet size");
+_Static_assert(offsetof(Packet, kind) == 0, "Packet.kind offset");
+_Static_assert(sizeof(((Packet *)0)->kind) == 1, "Packet.kind size");
+_Static_assert(offsetof(Packet, version) == 1, "Packet.version offset");
+_Static_assert(sizeof(((Packet *)0)->version) == 1, "Packet.version size");
+_Static_assert(offsetof(Packet, flags) == 4, "Packet.flags offset");
+_Static_assert(sizeof(((Packet *)0)->flags) == 2, "Packet.flags size");
+_Static_assert(offsetof(Packet, len) == 6, "Packet.len offset");
+_Static_assert(sizeof(((Packet *)0)->len) == 2, "Packet.len size");
+_Static_assert(offsetof(Packet, id) == 8, "Packet.id offset");
+_Static_assert(sizeof(((Packet *)0)->id) == 8, "Packet.id size");
+_Static_assert(offsetof(Packet, checksum) == 16, "Packet.checksum offset");
+_Static_assert(sizeof(((Packet *)0)->checksum) == 4, "Packet.checksum size");
+
+#endif /* BINARY_PROC_PACKET_H */
@@ -0,0 +1,68 @@
+-- generated by binary-proc, do not edit
+local proto = Proto("demo", "Demo Protocol")
+local f = {}
+f["demo.kind"] = ProtoField.uint8("demo.kind", "kind", base.DEC, { [1] = "Request", [2] = "Response" })
+f["demo.flags"] = ProtoField.uint8("demo.flags", "flags", base.HEX)
+f["demo.flags.ack"] = ProtoField.bool("demo.flags.ack", "ack", 8, nil, 0x1)
+f["demo.flags.priority"] = ProtoField.uint8("demo.flags.priority", "priority", base.DEC, nil, 0xe)
+f["demo.flags.delta"] = ProtoField.int8("demo.flags.delta", "delta", base.DEC, nil, 0xf0)
+f["demo.status"] = ProtoField.uint16("demo.status", "status", base.RANGE_STRING, { { 0, 0, "Ok" }, { 100, 199, "Retry" }, { 255, 255, "Retry" }, { 400, 65535, "Failure" } })
+f["demo.version"] = ProtoField.none("demo.version", "version")
+f["demo.version.major"] = ProtoField.uint8("demo.version.major", "major", base.DEC)
+f["demo.version.minor"] = ProtoField.uint8("demo.version.minor", "minor", base.DEC)
+f["demo.length"] = ProtoField.uint32("demo.length", "length", base.DEC)
+f["demo.ratio"] = ProtoField.float("demo.ratio", "ratio")
+f["demo.name"] = ProtoField.string("demo.name", "name")
+f["demo.mac"] = ProtoField.bytes("demo.mac", "mac")
+proto.fields = {
+    f["demo.kind"],
+    f["demo.flags"],
+    f["demo.flags.ack"],
+    f["demo.flags.priority"],
+    f["demo.flags.delta"],
+    f["demo.status"],
+    f["demo.version"],
+    f["demo.version.major"],
+    f["demo.version.minor"],
+    f["demo.length"],
+    f["demo.ratio"],
+    f["demo.name"],
+    f["demo.mac"],
+}
+
+function proto.dissector(buffer, pinfo, root)
+    pinfo.cols.protocol = "DEMO"
+    local tree = root:add(proto, buffer())
+    if buffer:len() >= 1 then
+        tree:add(f["demo.kind"], buffer(0, 1))
+    end
+    if buffer:len() >= 2 then
+        local t1 = tree:add(f["demo.flags"], buffer(1, 1))
+        t1:add(f["demo.flags.ack"], buffer(1, 1))
+        t1:add(f["demo.flags.priority"], buffer(1, 1))
+        t1:add(f["demo.flags.delta"], buffer(1, 1))
+    end
+    if buffer:len() >= 4 then
+        tree:add(f["demo.status"], buffer(2, 2))
+    end
+    if buffer:len() >= 8 then
+        local t2 = tree:add(f["demo.version"], buffer(6, 2))
+        t2:add(f["demo.version.major"], buffer(6, 1))
+        t2:add(f["demo.version.minor"], buffer(7, 1))
+    end
+    if buffer:len() >= 12 then
+        tree:add(f["demo.length"], buffer(8, 4))
+    end
+    if buffer:len() >= 16 then
+        tree:add(f["demo.ratio"], buffer(12, 4))
+    end
+    if buffer:len() >= 24 then
+        tree:add(f["demo.name"], buffer(16, 8))
+    end
+    if buffer:len() >= 30 then
+        tree:add(f["demo.mac"], buffer(24, 6))
+    end
+end
+
+-- DissectorTable.get("udp.port"):add(<port>, proto)
+return proto
@@ -0,0 +1,134 @@
+//! 生成类型的布局文档：以 rustdoc 生成测试 crate 的文档，检查其中的偏移表与取值表
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LIB: &str = r#"
+use binary_proc_rt::{bitmap, bytemap, restrict};
+
+#[bitmap(u8)]
+#[derive(Debug)]
+pub struct Flags {
+    #[pos(0)]
+    pub ack: bool,
+    #[pos(4..=7)]
+    pub level: u8,
+}
+
+#[restrict(u8)]
+#[derive(Debug)]
+pub enum Kind {
+    #[white_list(1)]
+    Request,
+    #[white_list(2..=9, 0xff)]
+    Other(u8),
+}
+
+/// 报文头
+#[bytemap]
+#[derive(Debug)]
+#[reserved(2..=3)]
+pub struct Header {
+    #[pos(4..=7)]
+    pub len: u32,
+    #[pos(0)]
+    pub kind: Kind,
+    #[pos(1)]
+    pub flags: Flags,
+}
+
+#[bytemap(packed)]
+#[derive(Debug)]
+pub struct Entry {
+    pub version: u8,
+    #[when(version >= 2)]
+    #[varint(uleb128)]
+    pub ext: Option<u64>,
+    pub flags: u8,
+}
+"#;
+
+/// 在 `CARGO_TARGET_TMPDIR` 下生成依赖本 crate 的测试 crate 并运行 `cargo doc`
+fn document() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("layout_doc");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = 'layout-doc'\nversion = '0.0.0'\nedition = '2021'\n\n\
+         [workspace]\n\n[dependencies.binary-proc-rt]\npath = {:?}\n",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    std::fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), LIB).unwrap();
+    // 沿用工作区已解析的依赖版本，无需访问网络
+    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.lock");
+    if lock.exists() && !dir.join("Cargo.lock").exists() {
+        std::fs::copy(lock, dir.join("Cargo.lock")).unwrap();
+    }
+    let status = Command::new(option_env!("CARGO").unwrap_or("cargo"))
+        .args(["doc", "--no-deps", "--offline", "--quiet"])
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    dir.join("target/doc/layout_doc")
+}
+
+fn page(doc: &Path, name: &str) -> String {
+    let html = std::fs::read_to_string(doc.join(name)).unwrap();
+    // 只保留文本，便于比较表格的内容
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn layout_tables() {
+    let doc = document();
+
+    let header = page(&doc, "struct.Header.html");
+    assert!(header.contains("报文头"), "{}", header);
+    // 按位置排序，reserved 同样列出
+    assert!(
+        header.contains("Bytes Field Type 0 kind Kind 1 flags Flags 2..=3 reserved 4..=7 len u32"),
+        "{}",
+        header
+    );
+
+    // 变长字段及其后字段的位置在运行时求出
+    let entry = page(&doc, "struct.Entry.html");
+    assert!(
+        entry.contains(
+            "Bytes Field Type Present when 0 version u8 variable ext u64 (uleb128 varint) \
+             version &gt;= 2 variable flags u8"
+        ),
+        "{}",
+        entry
+    );
+
+    let flags = page(&doc, "struct.Flags.html");
+    assert!(flags.contains("Container: u8"), "{}", flags);
+    assert!(
+        flags.contains("Bits Field Type 0 ack bool 4..=7 level u8"),
+        "{}",
+        flags
+    );
+
+    let kind = page(&doc, "enum.Kind.html");
+    assert!(
+        kind.contains("Variant Values Request 1 Other(u8) 2..=9 , 0xff"),
+        "{}",
+        kind
+    );
+}
@@ -0,0 +1,132 @@
+//! `bytemap(mmio)`：以普通内存代替设备地址，检查 volatile 读写的位置与 bitmap 的读改写
+
+use binary_proc_rt::{bitmap, bytemap, restrict};
+
+#[bitmap(u32, mmio)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Ctrl {
+    #[pos(0)]
+    enable: bool,
+    #[pos(4..=7)]
+    mode: u8,
+}
+
+#[restrict(u16, mmio)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Speed {
+    #[white_list(1)]
+    Low,
+    #[white_list(2)]
+    High,
+}
+
+#[bytemap(mmio)]
+#[derive(Debug)]
+#[reserved(16..=19)]
+struct Device {
+    #[pos(0)]
+    id: u8,
+    #[pos(1)]
+    rev: i8,
+    #[pos(4..=7)]
+    ctrl: Ctrl,
+    #[pos(8..=15)]
+    counter: u64,
+    #[pos(2..=3)]
+    speed: Speed,
+}
+
+fn bytes(memory: &[u64; 3]) -> Vec<u8> {
+    memory.iter().flat_map(|x| x.to_ne_bytes()).collect()
+}
+
+#[test]
+fn layout_constants() {
+    // 末尾的 reserved 同样属于寄存器块
+    assert_eq!(DeviceMmio::SIZE, 20);
+    assert_eq!(DeviceMmio::ALIGN, 8);
+}
+
+#[test]
+fn integer_registers() {
+    let mut memory = [0u64; 3];
+    let device = unsafe { DeviceMmio::new(memory.as_mut_ptr().cast()) };
+    device.write_id(0xa5);
+    device.write_rev(-2);
+    device.write_counter(0x0102_0304_0506_0708);
+    assert_eq!(device.read_id(), 0xa5);
+    assert_eq!(device.read_rev(), -2);
+    assert_eq!(device.read_counter(), 0x0102_0304_0506_0708);
+    assert_eq!(bytes(&memory)[..2], [0xa5, 0xfe]);
+    assert_eq!(memory[1], 0x0102_0304_0506_0708);
+}
+
+/// write 覆盖整个寄存器，modify 只改写字段覆盖的位
+#[test]
+fn bitmap_register() {
+    let mut memory = [0u64; 3];
+    let base: *mut u8 = memory.as_mut_ptr().cast();
+    let device = unsafe { DeviceMmio::new(base) };
+    unsafe { base.add(4).cast::<u32>().write(0xff00_0000) };
+    device.modify_ctrl(|ctrl| ctrl.mode = 3).unwrap();
+    assert_eq!(device.read_ctrl_raw(), 0xff00_0030);
+    assert_eq!(
+        device.read_ctrl(),
+        Ok(Ctrl {
+            enable: false,
+            mode: 3
+        })
+    );
+    device.write_ctrl(Ctrl {
+        enable: true,
+        mode: 1,
+    });
+    assert_eq!(device.read_ctrl_raw(), 0x11);
+}
+
+/// 寄存器中的值不在 white_list 中时读取失败，modify 不写回
+#[test]
+fn restrict_register() {
+    let mut memory = [0u64; 3];
+    let device = unsafe { DeviceMmio::new(memory.as_mut_ptr().cast()) };
+    assert_eq!(device.read_speed(), Err(0));
+    assert_eq!(device.modify_speed(|speed| *speed = Speed::High), Err(0));
+    device.write_speed(Speed::Low);
+    assert_eq!(device.read_speed_raw(), 1);
+    device.modify_speed(|speed| *speed = Speed::High).unwrap();
+    assert_eq!(device.read_speed(), Ok(Speed::High));
+}
+
+/// 寄存器块与本机字节序的编码使用相同的布局
+#[test]
+fn matches_encoding() {
+    let value = Device {
+        id: 7,
+        rev: -1,
+        ctrl: Ctrl {
+            enable: true,
+            mode: 9,
+        },
+        counter: u64::MAX - 1,
+        speed: Speed::High,
+    };
+    let encoded = match cfg!(target_endian = "little") {
+        true => value.encode_le(),
+        false => value.encode_be(),
+    };
+    let mut memory = [0u64; 3];
+    let base: *mut u8 = memory.as_mut_ptr().cast();
+    unsafe { base.copy_from_nonoverlapping(encoded.as_ptr(), encoded.len()) };
+    let device = unsafe { DeviceMmio::new(base) };
+    assert_eq!(device.read_id(), 7);
+    assert_eq!(device.read_rev(), -1);
+    assert_eq!(
+        device.read_ctrl(),
+        Ok(Ctrl {
+            enable: true,
+            mode: 9
+        })
+    );
+    assert_eq!(device.read_counter(), u64::MAX - 1);
+    assert_eq!(device.read_speed(), Ok(Speed::High));
+}
@@ -0,0 +1,74 @@
+//! `nom` feature：`parse_le`/`parse_be` 与 nom 组合子混用，失败时带字段名 context 且不 panic
+#![cfg(feature = "nom")]
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+use binary_proc_rt::nom::multi::many1;
+use binary_proc_rt::nom::Err;
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+#[reserved(4..=7)]
+struct Padded {
+    #[pos(0..=3)]
+    a: u32,
+}
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+struct Pair {
+    #[pos(0)]
+    a: u8,
+    #[pos(1..=2)]
+    b: u16,
+}
+
+fn kinds(err: Err<VerboseError<&[u8]>>) -> Vec<VerboseErrorKind> {
+    match err {
+        Err::Error(err) => err.errors.into_iter().map(|(_, kind)| kind).collect(),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn rest_after_value() {
+    let input = [1u8, 0x12, 0x34, 9];
+    let (rest, pair) = Pair::parse_be::<VerboseError<_>>(&input).unwrap();
+    assert_eq!(pair, Pair { a: 1, b: 0x1234 });
+    assert_eq!(rest, [9]);
+    let input = [1u8, 0x34, 0x12, 2, 0x78, 0x56];
+    let (rest, pairs) = many1(Pair::parse_le::<VerboseError<_>>)(&input[..]).unwrap();
+    assert_eq!(pairs, [Pair { a: 1, b: 0x1234 }, Pair { a: 2, b: 0x5678 }]);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn truncated_field() {
+    let err = Pair::parse_le::<VerboseError<_>>(&[1u8, 2]).unwrap_err();
+    assert_eq!(
+        kinds(err),
+        [
+            VerboseErrorKind::Nom(ErrorKind::Eof),
+            VerboseErrorKind::Context("b"),
+            VerboseErrorKind::Context("Pair"),
+        ]
+    );
+}
+
+/// 字段都已读取但末尾的 reserved 字节不足时返回 Eof 而不是 panic
+#[test]
+fn trailing_reserved() {
+    let input = [1u8, 0, 0, 0];
+    let err = Padded::parse_le::<VerboseError<_>>(&input).unwrap_err();
+    assert_eq!(
+        kinds(err),
+        [
+            VerboseErrorKind::Nom(ErrorKind::Eof),
+            VerboseErrorKind::Context("Padded"),
+        ]
+    );
+    let input = [1u8, 0, 0, 0, 0, 0, 0, 0, 5];
+    let (rest, value) = Padded::parse_le::<VerboseError<_>>(&input).unwrap();
+    assert_eq!(value, Padded { a: 1 });
+    assert_eq!(rest, [5]);
+}
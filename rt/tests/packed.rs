@@ -0,0 +1,82 @@
+//! 紧密排列的布局：`bytemap(packed)`、`align`、`pad` 与 `len`
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, BinarySize};
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Inner {
+    a: u8,
+    b: u16,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq)]
+struct Outer<'a> {
+    kind: u8, // 0..=0
+    #[align(4)]
+    id: u32, // 4..=7
+    #[pad(2)]
+    port: u16, // 10..=11
+    #[len = 4]
+    tag: &'a [u8], // 12..=15
+    inner: Inner, // 16..=18
+    tail: [u8; 2], // 19..=20
+}
+
+const INPUT: [u8; 21] = [
+    7, 0, 0, 0, // kind, align
+    1, 2, 3, 4, // id
+    0, 0, // pad
+    0x12, 0x34, // port
+    b't', b'a', b'g', b'!', // tag
+    9, 0xab, 0xcd, // inner
+    5, 6, // tail
+];
+
+fn outer() -> Outer<'static> {
+    Outer {
+        kind: 7,
+        id: 0x01020304,
+        port: 0x1234,
+        tag: b"tag!",
+        inner: Inner { a: 9, b: 0xabcd },
+        tail: [5, 6],
+    }
+}
+
+#[test]
+fn sizes_follow_fields() {
+    assert_eq!(Inner::SIZE, 3);
+    assert_eq!(Outer::SIZE, 21);
+    assert_eq!(outer().encoded_len(), 21);
+}
+
+#[test]
+fn round_trip() {
+    assert_eq!(Outer::try_from(Be(&INPUT[..])), Ok(outer()));
+    assert_eq!(outer().encode_be(), INPUT);
+    let le = outer().encode_le();
+    assert_eq!(&le[4..8], &[4, 3, 2, 1]);
+    assert_eq!(&le[16..19], &[9, 0xcd, 0xab]);
+    assert_eq!(Outer::try_from(Le(&le[..])), Ok(outer()));
+}
+
+/// align 与 pad 产生的空隙编码为 0，解码时忽略
+#[test]
+fn gaps_are_zeroed_and_ignored() {
+    let mut buf = [0xffu8; 21];
+    assert_eq!(outer().encode_be_into(&mut buf), Ok(21));
+    assert_eq!(buf, INPUT);
+    let mut input = INPUT;
+    input[1..4].fill(0xee);
+    input[8..10].fill(0xee);
+    assert_eq!(Outer::try_from(Be(&input[..])), Ok(outer()));
+}
+
+#[test]
+fn short_input_reports_field_range() {
+    assert_eq!(Outer::try_from(Be(&INPUT[..20])), Err(19..=20));
+    assert_eq!(Outer::try_from(Be(&INPUT[..14])), Err(12..=15));
+    assert_eq!(Outer::try_from(Be(&INPUT[..5])), Err(4..=7));
+}
@@ -0,0 +1,102 @@
+//! `bytemap(partial)`：截断或非法的字段为 None，其余字段照常解码
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, restrict};
+
+#[restrict(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    #[white_list(1)]
+    Data,
+    #[white_list(2)]
+    Ack,
+}
+
+#[bytemap(partial)]
+#[derive(Debug, PartialEq, Clone)]
+struct Record {
+    #[pos(0..=1)]
+    #[magic = 0xcafe]
+    magic: u16,
+    #[pos(2)]
+    kind: Kind,
+    #[pos(3..=6)]
+    len: u32,
+    #[pos(7..=8)]
+    crc: u16,
+}
+
+const INPUT: [u8; 9] = [0xca, 0xfe, 2, 0, 0, 1, 0, 0xab, 0xcd];
+
+#[test]
+fn complete_input_matches_try_from() {
+    let partial = Record::decode_partial(Be(&INPUT[..]));
+    let full = Record::try_from(Be(&INPUT[..])).unwrap();
+    assert_eq!(partial.magic, Some(full.magic));
+    assert_eq!(partial.kind, Some(Kind::Ack));
+    assert_eq!(partial.len, Some(256));
+    assert_eq!(partial.crc, Some(0xabcd));
+}
+
+#[test]
+fn truncated_input_keeps_present_fields() {
+    let partial = Record::decode_partial(Be(&INPUT[..5]));
+    assert_eq!(partial.magic, Some(0xcafe));
+    assert_eq!(partial.kind, Some(Kind::Ack));
+    assert_eq!(partial.len, None);
+    assert_eq!(partial.crc, None);
+    let partial = Record::decode_partial(Be(&[][..]));
+    assert_eq!(
+        partial,
+        RecordPartial {
+            magic: None,
+            kind: None,
+            len: None,
+            crc: None,
+        }
+    );
+}
+
+/// 非法字段不影响之后的字段
+#[test]
+fn invalid_fields_are_skipped() {
+    let mut input = INPUT;
+    input[1] = 0;
+    input[2] = 9;
+    let partial = Record::decode_partial(Be(&input[..]));
+    assert_eq!(partial.magic, None);
+    assert_eq!(partial.kind, None);
+    assert_eq!(partial.len, Some(256));
+    assert_eq!(partial.crc, Some(0xabcd));
+    let partial = RecordPartial::from(Le(&input[..8]));
+    assert_eq!(partial.len, Some(0x0001_0000));
+    assert_eq!(partial.crc, None);
+}
+
+#[bytemap(packed, partial)]
+#[derive(Debug, PartialEq)]
+struct Versioned {
+    kind: u8,
+    #[when(kind == 1)]
+    ext: Option<u16>,
+    len: u32,
+}
+
+#[test]
+fn conditional_fields() {
+    let partial = Versioned::decode_partial(Le(&[1u8, 2, 0][..]));
+    assert_eq!(partial.kind, Some(1));
+    assert_eq!(partial.ext, Some(Some(2)));
+    assert_eq!(partial.len, None);
+    let partial = Versioned::decode_partial(Le(&[0u8, 2, 0, 0, 0][..]));
+    assert_eq!(partial.ext, Some(None));
+    assert_eq!(partial.len, Some(2));
+    // 条件字段截断后无法确定之后字段的位置
+    let partial = Versioned::decode_partial(Le(&[1u8, 2][..]));
+    assert_eq!(partial.kind, Some(1));
+    assert_eq!(partial.ext, None);
+    assert_eq!(partial.len, None);
+    // 条件依赖的字段缺失时，条件字段与之后的字段均为 None
+    let partial = Versioned::decode_partial(Le(&[][..]));
+    assert_eq!((partial.kind, partial.ext, partial.len), (None, None, None));
+}
@@ -0,0 +1,132 @@
+//! 长度前缀字段：编码时写入前缀，超出前缀类型或 min、max 的长度拒绝编码而不是截断
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{Be, IntoLeIter, Le};
+use binary_proc_rt::EncodeError;
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq, Clone)]
+struct Entry<'a> {
+    #[prefix(u16, be, min = 1, max = 255)]
+    name: &'a str,
+    #[prefix(u8)]
+    ports: Vec<u16>,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq, Clone)]
+struct Payload<'a> {
+    #[prefix(u8)]
+    data: &'a [u8],
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq, Clone)]
+struct Ports {
+    #[prefix(u8, max = 3)]
+    ports: Vec<u16>,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq, Clone)]
+struct Record<'a> {
+    kind: u8,
+    #[prefix(u32, be, max = 16)]
+    body: &'a [u8],
+}
+
+#[test]
+fn round_trip() {
+    let entry = Entry {
+        name: "eth0",
+        ports: vec![80, 443],
+    };
+    let le = entry.clone().encode_le();
+    assert_eq!(le, [0, 4, b'e', b't', b'h', b'0', 2, 80, 0, 0xbb, 1]);
+    assert_eq!(Entry::try_from(Le(&le[..])), Ok(entry.clone()));
+    let be = entry.clone().encode_be();
+    assert_eq!(be, [0, 4, b'e', b't', b'h', b'0', 2, 0, 80, 1, 0xbb]);
+    assert_eq!(Entry::try_from(Be(&be[..])), Ok(entry));
+}
+
+/// 前缀类型能表示的最大长度可以编码，再多一个字节即拒绝
+#[test]
+fn prefix_type_bound() {
+    let data = [7u8; 300];
+    let mut buf = [0u8; 301];
+    let value = Payload { data: &data[..255] };
+    assert_eq!(value.clone().encode_le_into(&mut buf), Ok(256));
+    assert_eq!(Payload::try_from(Le(&buf[..256])), Ok(value));
+    let value = Payload { data: &data[..256] };
+    assert_eq!(
+        value.encode_le_into(&mut buf),
+        Err(EncodeError::Invalid("data"))
+    );
+    let value = Payload { data: &data[..] };
+    assert_eq!(
+        value.encode_be_into(&mut buf),
+        Err(EncodeError::Invalid("data"))
+    );
+}
+
+#[test]
+#[should_panic]
+fn prefix_type_bound_iter() {
+    let data = [7u8; 300];
+    Payload { data: &data[..] }.into_leiter().for_each(drop);
+}
+
+#[test]
+fn max_bound() {
+    let mut buf = [0u8; 16];
+    let value = Ports {
+        ports: vec![1, 2, 3],
+    };
+    assert_eq!(value.clone().encode_le_into(&mut buf), Ok(7));
+    assert_eq!(Ports::try_from(Le(&buf[..7])), Ok(value));
+    let value = Ports {
+        ports: vec![1, 2, 3, 4, 5],
+    };
+    assert_eq!(
+        value.encode_le_into(&mut buf),
+        Err(EncodeError::Invalid("ports"))
+    );
+    // 解码同样拒绝超出 max 的前缀
+    let input = [5u8, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0];
+    assert!(Ports::try_from(Le(&input[..])).is_err());
+}
+
+#[test]
+fn min_bound() {
+    let mut buf = [0u8; 16];
+    let value = Entry {
+        name: "",
+        ports: Vec::new(),
+    };
+    assert_eq!(
+        value.encode_le_into(&mut buf),
+        Err(EncodeError::Invalid("name"))
+    );
+}
+
+/// 读出前缀后立即检查 min、max，错误只指向前缀本身
+#[test]
+fn decode_bounds_before_data() {
+    let input = [1u8, 0xff, 0xff, 0xff, 0xf0, 1, 2, 3];
+    assert_eq!(Record::try_from(Le(&input[..])), Err(1..=4));
+    assert_eq!(Record::try_from(Be(&input[..])), Err(1..=4));
+    let input = [1u8, 0, 0, 0, 17, 1, 2, 3];
+    assert_eq!(Record::try_from(Le(&input[..])), Err(1..=4));
+    let input = [1u8, 0, 0, 0, 3, 1, 2, 3];
+    assert_eq!(
+        Record::try_from(Le(&input[..])),
+        Ok(Record {
+            kind: 1,
+            body: &[1, 2, 3]
+        })
+    );
+
+    // 前缀为 0 小于 min = 1
+    let input = [0u8, 0, 0, 1, 0, 0];
+    assert_eq!(Entry::try_from(Le(&input[..])), Err(0..=1));
+}
@@ -0,0 +1,117 @@
+//! 运行时的原生类型实现：整数、浮点数、bool 与数组的 `Le`/`Be` 编解码及位操作
+
+use binary_proc_rt::bits::Bits;
+use binary_proc_rt::endian::{Be, IntoBeIter, IntoLeIter, InvalidBytes, Le};
+use binary_proc_rt::{bitmap, BinarySize};
+
+macro_rules! round_trip {
+    ($($ty:ty = $value:expr),* $(,)?) => {
+        $({
+            let value: $ty = $value;
+            let le = value.into_leiter().collect::<Vec<_>>();
+            let be = value.into_beiter().collect::<Vec<_>>();
+            assert_eq!(le, value.to_le_bytes(), stringify!($ty));
+            assert_eq!(be, value.to_be_bytes(), stringify!($ty));
+            assert_eq!(<$ty>::try_from(Le(&le[..])), Ok(value));
+            assert_eq!(<$ty>::try_from(Be(&be[..])), Ok(value));
+            assert_eq!(<$ty as BinarySize>::SIZE, le.len());
+            // 长度必须与类型一致
+            assert_eq!(<$ty>::try_from(Le(&le[1..])), Err(InvalidBytes));
+            let mut long = be.clone();
+            long.push(0);
+            assert_eq!(<$ty>::try_from(Be(&long[..])), Err(InvalidBytes));
+        })*
+    };
+}
+
+#[test]
+fn numbers() {
+    round_trip!(
+        u8 = 0xa5,
+        u16 = 0x1234,
+        u32 = 0x1234_5678,
+        u64 = 0x0102_0304_0506_0708,
+        u128 = 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10,
+        i8 = -2,
+        i16 = -0x1234,
+        i32 = -0x1234_5678,
+        i64 = i64::MIN + 1,
+        i128 = -7,
+        f32 = 1.5,
+        f64 = -0.25,
+    );
+}
+
+#[test]
+fn bool_is_any_nonzero() {
+    assert_eq!(bool::try_from(Le(&[0u8, 0][..])), Ok(false));
+    assert_eq!(bool::try_from(Be(&[0u8, 2][..])), Ok(true));
+    assert_eq!(true.into_leiter().collect::<Vec<_>>(), [1]);
+    assert_eq!(false.into_beiter().collect::<Vec<_>>(), [0]);
+    assert_eq!(bool::SIZE, 1);
+}
+
+#[test]
+fn arrays_decode_per_element() {
+    let input = [1u8, 2, 3, 4, 5, 6];
+    assert_eq!(
+        <[u16; 3]>::try_from(Le(&input[..])),
+        Ok([0x201, 0x403, 0x605])
+    );
+    assert_eq!(
+        <[u16; 3]>::try_from(Be(&input[..])),
+        Ok([0x102, 0x304, 0x506])
+    );
+    assert_eq!(<[u16; 3]>::try_from(Le(&input[..5])), Err(InvalidBytes));
+    assert_eq!(
+        <[[u8; 2]; 3]>::try_from(Le(&input[..])),
+        Ok([[1, 2], [3, 4], [5, 6]])
+    );
+    assert_eq!(
+        [0x201u16, 0x403, 0x605].into_leiter().collect::<Vec<_>>(),
+        input
+    );
+    assert_eq!(
+        [0x102u16, 0x304, 0x506].into_beiter().collect::<Vec<_>>(),
+        input
+    );
+    assert_eq!(<[u32; 4]>::SIZE, 16);
+    assert_eq!(<&[u16; 4]>::SIZE, 8);
+}
+
+#[test]
+fn bits_read_and_write() {
+    assert_eq!(0b1011_0110u8.bits(2..=4).read(), 0b101);
+    assert_eq!(0xffu8.bits(0..=7).read(), 0xff);
+    assert_eq!(u128::MAX.bits(127..=127).read(), 1);
+    assert_eq!(0u8.set_bits(2..=4, 0b111), 0b1_1100);
+    // 超出位宽的部分被截断
+    assert_eq!(0u16.set_bits(4..=7, 0x1f), 0xf0);
+    assert_eq!(0xffffu16.set_bits(0..=15, 0x1234), 0x1234);
+}
+
+#[bitmap(u16)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Status {
+    #[pos(0)]
+    ready: bool,
+    #[pos(1..=4)]
+    offset: i8,
+    #[pos(5..=15)]
+    count: u16,
+}
+
+/// bitmap 通过运行时的位操作读写，有符号字段符号扩展
+#[test]
+fn bitmap_uses_runtime_bits() {
+    let status = Status {
+        ready: true,
+        offset: -3,
+        count: 0x7ff,
+    };
+    let raw = u16::from(status);
+    assert_eq!(raw, 1 | (0b1101 << 1) | (0x7ff << 5));
+    assert_eq!(Status::try_from(raw), Ok(status));
+    assert_eq!(status.into_leiter().collect::<Vec<_>>(), raw.to_le_bytes());
+    assert_eq!(Status::try_from(Be(&raw.to_be_bytes()[..])), Ok(status));
+}
@@ -0,0 +1,139 @@
+//! `schema` 生成的 JSON 描述必须是合法 JSON，非字面量的界以表达式字符串表示；
+//! KSY 输出与 tests/golden 中的文件比对，设置 `UPDATE_GOLDEN=1` 重新生成
+
+use binary_proc_rt::{bitmap, bytemap, restrict};
+use serde_json::{json, Value};
+
+const LOW: u16 = 10;
+const HIGH: u16 = 20;
+const BUSY: u16 = 21;
+
+#[restrict(u16, schema)]
+#[derive(Debug)]
+enum Status {
+    #[white_list(0)]
+    Ok,
+    #[white_list(LOW..=HIGH, 255)]
+    Retry,
+    #[white_list(BUSY..300)]
+    Busy,
+    #[white_list(400..)]
+    Failure(u16),
+}
+
+#[bitmap(u8, schema)]
+#[derive(Debug)]
+struct Flags {
+    #[pos(0)]
+    ack: bool,
+    #[pos(1..=7)]
+    level: u8,
+}
+
+#[bytemap(schema)]
+#[derive(Debug)]
+struct Header {
+    #[pos(0)]
+    kind: u8,
+    #[pos(1..=2)]
+    len: u16,
+}
+
+#[restrict(u8, schema)]
+#[derive(Debug)]
+enum Kind {
+    #[white_list(1)]
+    Ping,
+    #[white_list(2..=4, 8..10)]
+    Data,
+}
+
+#[bytemap(packed, schema)]
+#[derive(Debug)]
+struct Record<'a> {
+    version: u8,
+    kind: Kind,
+    flags: Flags,
+    #[magic(0xa5)]
+    tag: u8,
+    #[len(4)]
+    #[cstr]
+    name: &'a str,
+    #[len(6)]
+    #[padded(b' ')]
+    label: &'a str,
+    id: [u8; 16],
+    #[varint(uleb128)]
+    offset: u64,
+    #[prefix(u16, be, max = 32)]
+    body: &'a [u8],
+    #[when(version >= 2)]
+    #[prefix(u8)]
+    ports: Option<Vec<u16>>,
+    checksum: u32,
+}
+
+fn check_golden(name: &str, actual: &str) {
+    let path = format!("{}/tests/golden/{}", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(actual, expected, "{} is out of date", name);
+}
+
+fn parse(json: &str) -> Value {
+    serde_json::from_str(json).unwrap_or_else(|e| panic!("{e}: {json}"))
+}
+
+#[test]
+fn restrict_bounds() {
+    let schema = parse(Status::SCHEMA_JSON);
+    assert_eq!(schema["kind"], "restrict");
+    let values = schema["variants"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| (x["name"].as_str().unwrap(), x["values"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [
+            ("Ok", json!([[0, 0]])),
+            ("Retry", json!([["LOW", "HIGH"], [255, 255]])),
+            ("Busy", json!([["BUSY", 299]])),
+            ("Failure", json!([[400, null]])),
+        ]
+    );
+}
+
+#[test]
+fn bitmap_and_bytemap() {
+    let flags = parse(Flags::SCHEMA_JSON);
+    assert_eq!(flags["fields"][1]["bits"], json!([1, 7]));
+    let header = parse(Header::SCHEMA_JSON);
+    assert_eq!(header["fields"][1]["offset"], 1);
+    assert_eq!(header["fields"][1]["size"], 2);
+}
+
+#[test]
+fn packed_ksy() {
+    check_golden("record_le.ksy", Record::KSY_LE);
+    check_golden("record_be.ksy", Record::KSY_BE);
+    // 编译期已知的长度不带类型后缀
+    assert!(!Record::KSY_LE.contains("usize"));
+}
+
+#[test]
+fn restrict_ksy() {
+    check_golden("kind.ksy", Kind::KSY_LE);
+    check_golden("flags.ksy", Flags::KSY_LE);
+    // 含常量界的变体不生成 Kaitai 实例
+    check_golden("status.ksy", Status::KSY_BE);
+    assert!(!Status::KSY_BE.contains("LOW"));
+}
+
+#[test]
+fn fixed_ksy() {
+    check_golden("header.ksy", Header::KSY_BE);
+}
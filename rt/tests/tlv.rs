@@ -0,0 +1,153 @@
+//! `restrict(.., tlv(..))`：以 restrict 枚举分派类型码，解码 TCP 选项与 BLE AD 结构等 TLV 序列
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::tlv::{DecodeTlv, Tlv};
+use binary_proc_rt::{bytemap, restrict};
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+struct Timestamps {
+    #[pos(0..=3)]
+    value: u32,
+    #[pos(4..=7)]
+    echo: u32,
+}
+
+/// TCP 选项的长度包含类型码与长度本身
+#[restrict(u8, tlv(len = u8, len_offset = 2))]
+#[derive(Debug, PartialEq)]
+enum TcpOption {
+    #[white_list(0)]
+    Eol,
+    #[white_list(1)]
+    Nop,
+    #[white_list(2)]
+    Mss(u16),
+    #[white_list(3)]
+    WindowScale(u8),
+    #[white_list(8)]
+    Timestamps(Timestamps),
+}
+
+/// BLE AD 结构的长度在类型码之前，并包含类型码
+#[restrict(u8, tlv(len = u8, len_first, len_offset = 1))]
+#[derive(Debug, PartialEq)]
+enum Ad<'a> {
+    #[white_list(0x01)]
+    Flags(u8),
+    #[white_list(0x08, 0x09)]
+    Name(&'a str),
+    #[white_list(0x0a)]
+    TxPower(i8),
+}
+
+#[restrict(u8, tlv(len = u16))]
+#[derive(Debug, PartialEq, Clone)]
+enum Record<'a> {
+    #[white_list(1)]
+    Data(&'a [u8]),
+}
+
+#[test]
+fn tcp_options() {
+    let input = [
+        2, 4, 0x05, 0xb4, 1, 3, 3, 7, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 2, 0,
+    ];
+    let options = TcpOption::tlv_iter(Be(&input[..]))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        options,
+        [
+            Tlv::Known(TcpOption::Mss(1460)),
+            Tlv::Known(TcpOption::Nop),
+            Tlv::Known(TcpOption::WindowScale(7)),
+            // SACK permitted 未声明，值为空
+            Tlv::Unknown {
+                code: 4,
+                value: &[][..]
+            },
+            Tlv::Known(TcpOption::Timestamps(Timestamps { value: 1, echo: 2 })),
+            Tlv::Known(TcpOption::Eol),
+        ]
+    );
+    assert_eq!(TcpOption::tlv_iter(Be(&input[..])).known().count(), 5);
+    // 单独解码一项时返回其字节数，无数据的变体只占类型码
+    assert_eq!(
+        TcpOption::decode_tlv(Be(&input[..])),
+        Ok((Tlv::Known(TcpOption::Mss(1460)), 4))
+    );
+    assert_eq!(
+        TcpOption::decode_tlv(Be(&input[4..])),
+        Ok((Tlv::Known(TcpOption::Nop), 1))
+    );
+}
+
+#[test]
+fn ble_advertising() {
+    let input = [
+        2u8, 0x01, 0x06, 5, 0x09, b'b', b'e', b'a', b'c', 2, 0x0a, 0xf4, 3, 0xff, 0x4c, 0,
+    ];
+    let items = Ad::tlv_iter(Le(&input[..]))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        items,
+        [
+            Tlv::Known(Ad::Flags(6)),
+            Tlv::Known(Ad::Name("beac")),
+            Tlv::Known(Ad::TxPower(-12)),
+            Tlv::Unknown {
+                code: 0xff,
+                value: &[0x4c, 0][..]
+            },
+        ]
+    );
+}
+
+#[test]
+fn length_endianness() {
+    let le = [1u8, 2, 0, 0xaa, 0xbb];
+    let be = [1u8, 0, 2, 0xaa, 0xbb];
+    let expected = [Tlv::Known(Record::Data(&[0xaa, 0xbb][..]))];
+    assert_eq!(
+        Record::tlv_iter(Le(&le[..])).collect::<Result<Vec<_>, _>>(),
+        Ok(expected.to_vec())
+    );
+    assert_eq!(
+        Record::tlv_iter(Be(&be[..])).collect::<Result<Vec<_>, _>>(),
+        Ok(expected.to_vec())
+    );
+}
+
+/// 出错时返回该项中出错的范围（相对整个输入），之后不再产生任何项
+#[test]
+fn errors() {
+    // 长度小于 len_offset
+    let input = [1u8, 1, 3, 1];
+    let mut iter = TcpOption::tlv_iter(Be(&input[..]));
+    assert_eq!(iter.next(), Some(Ok(Tlv::Known(TcpOption::Nop))));
+    assert_eq!(iter.next(), Some(Ok(Tlv::Known(TcpOption::Nop))));
+    assert_eq!(iter.next(), Some(Err(3..=3)));
+    assert_eq!(iter.offset(), input.len());
+    assert_eq!(iter.next(), None);
+
+    // 值的长度与类型不符
+    let input = [1u8, 2, 3, 0x05];
+    let mut iter = TcpOption::tlv_iter(Be(&input[..]));
+    iter.next();
+    assert_eq!(iter.next(), Some(Err(3..=3)));
+
+    // 值超出输入
+    let input = [2u8, 4, 0x05];
+    let mut iter = TcpOption::tlv_iter(Be(&input[..]));
+    assert_eq!(iter.next(), Some(Err(2..=3)));
+    assert_eq!(iter.next(), None);
+
+    // 名称不是 UTF-8
+    let input = [3u8, 0x09, 0xff, 0xfe];
+    assert_eq!(
+        Ad::tlv_iter(Le(&input[..])).collect::<Result<Vec<_>, _>>(),
+        Err(2..=3)
+    );
+}
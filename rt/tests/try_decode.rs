@@ -0,0 +1,97 @@
+//! `try_decode`：输入不完整时返回还需要的字节数，完整时返回实际消耗的字节数
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::{bytemap, Decode};
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+#[reserved(3)]
+struct Fixed {
+    #[pos(0..=1)]
+    kind: u16,
+    #[pos(2)]
+    flags: u8,
+}
+
+#[bytemap]
+#[derive(Debug, PartialEq)]
+#[reserved(4..=5)]
+struct Tail {
+    #[pos(0)]
+    version: u8,
+    #[when(version >= 2)]
+    #[pos(1..=2)]
+    ext: Option<u16>,
+    #[pos(3)]
+    flags: u8,
+}
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq)]
+struct Record<'a> {
+    kind: u8,
+    #[prefix(u32, be, max = 16)]
+    body: &'a [u8],
+}
+
+#[test]
+fn fixed_layout() {
+    let input = [0x12u8, 0x34, 0xaa, 0, 0xff];
+    for len in 0..4 {
+        assert_eq!(
+            Fixed::try_decode(Be(&input[..len])),
+            Decode::Incomplete { needed: 4 - len }
+        );
+    }
+    let value = Fixed {
+        kind: 0x1234,
+        flags: 0xaa,
+    };
+    assert_eq!(
+        Fixed::try_decode(Be(&input[..])),
+        Decode::Complete(value, 4)
+    );
+}
+
+/// 字段都已到达但末尾的 reserved 字节尚未到达时，仍需继续等待
+#[test]
+fn trailing_reserved() {
+    let input = [2u8, 0x34, 0x12, 0xaa, 0, 0, 0xff];
+    for len in 4..6 {
+        assert_eq!(
+            Tail::try_decode(Le(&input[..len])),
+            Decode::Incomplete { needed: 6 - len }
+        );
+    }
+    let value = Tail {
+        version: 2,
+        ext: Some(0x1234),
+        flags: 0xaa,
+    };
+    assert_eq!(Tail::try_decode(Le(&input[..])), Decode::Complete(value, 6));
+}
+
+#[test]
+fn missing_field() {
+    assert_eq!(
+        Tail::try_decode(Le(&[2u8, 0x34][..])),
+        Decode::Incomplete { needed: 1 }
+    );
+}
+
+/// 前缀超出 max 时立即报错，而不是等待前缀声明的全部数据
+#[test]
+fn prefix_out_of_bounds() {
+    let input = [1u8, 0xff, 0xff, 0xff, 0xf0, 1, 2, 3];
+    assert_eq!(Record::try_decode(Le(&input[..])), Decode::Error(1..=4));
+    assert_eq!(Record::try_decode(Le(&input[..5])), Decode::Error(1..=4));
+    assert_eq!(
+        Record::try_decode(Le(&input[..3])),
+        Decode::Incomplete { needed: 2 }
+    );
+    let input = [1u8, 0, 0, 0, 16, 1, 2, 3];
+    assert_eq!(
+        Record::try_decode(Le(&input[..])),
+        Decode::Incomplete { needed: 13 }
+    );
+}
@@ -0,0 +1,6 @@
+//! 宏在编译期报告的错误
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use binary_proc_rt::bitmap;
+
+#[bitmap(u8)]
+struct A {
+    #[pos(4..=8)]
+    a: u8,
+}
+
+fn main() {}
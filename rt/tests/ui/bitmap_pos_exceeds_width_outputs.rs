@@ -0,0 +1,10 @@
+use binary_proc_rt::bitmap;
+
+#[bitmap(u8, schema, c_header, dissector)]
+#[derive(Debug)]
+struct A {
+    #[pos(0..=100)]
+    a: u8,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+#[derive(Debug)]
+struct Inner {
+    #[pos(0)]
+    a: u8,
+}
+
+#[bytemap(builder)]
+#[derive(Debug)]
+struct Outer {
+    #[pos(0)]
+    inner: Inner,
+}
+
+fn main() {}
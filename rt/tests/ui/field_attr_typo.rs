@@ -0,0 +1,9 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+struct A {
+    #[poss(0..=1)]
+    a: u16,
+}
+
+fn main() {}
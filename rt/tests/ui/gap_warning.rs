@@ -0,0 +1,14 @@
+#![deny(deprecated)]
+
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+#[derive(Debug)]
+struct A {
+    #[pos(0..=1)]
+    a: u16,
+    #[pos(4..=7)]
+    b: u32,
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use binary_proc_rt::{bitmap, restrict};
+
+#[derive(Debug)]
+struct Level(u8);
+
+impl TryFrom<u32> for Level {
+    type Error = u32;
+    fn try_from(value: u32) -> Result<Self, u32> {
+        Ok(Level(value as u8))
+    }
+}
+
+#[bitmap(u32, mmio)]
+#[derive(Debug)]
+struct Ctrl {
+    #[pos(0)]
+    enable: bool,
+    #[pos(1..=7)]
+    level: Level,
+}
+
+#[restrict(u8, mmio)]
+#[derive(Debug)]
+enum Speed {
+    #[white_list(1..=255)]
+    Value(u16),
+}
+
+fn main() {}
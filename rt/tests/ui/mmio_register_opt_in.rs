@@ -0,0 +1,17 @@
+use binary_proc_rt::{bitmap, bytemap};
+
+#[bitmap(u32)]
+#[derive(Debug)]
+struct Ctrl {
+    #[pos(0)]
+    enable: bool,
+}
+
+#[bytemap(mmio)]
+#[derive(Debug)]
+struct Uart {
+    #[pos(0..=3)]
+    ctrl: Ctrl,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap(mmio)]
+#[derive(Debug)]
+struct A {
+    #[pos(0)]
+    a: u8,
+    #[pos(1..=4)]
+    b: u32,
+}
+
+fn main() {}
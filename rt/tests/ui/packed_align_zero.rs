@@ -0,0 +1,10 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap(packed)]
+struct A {
+    a: u8,
+    #[align(0)]
+    b: u16,
+}
+
+fn main() {}
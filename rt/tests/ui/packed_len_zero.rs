@@ -0,0 +1,10 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap(packed)]
+struct A<'a> {
+    a: u8,
+    #[len = 0]
+    b: &'a [u8],
+}
+
+fn main() {}
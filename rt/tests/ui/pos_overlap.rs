@@ -0,0 +1,11 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+struct A {
+    #[pos(0..=1)]
+    a: u16,
+    #[pos(1..=2)]
+    b: u16,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+struct A {
+    #[pos(0..=2)]
+    a: u32,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use binary_proc_rt::restrict;
+
+#[restrict(u8, u16)]
+#[derive(Debug)]
+enum A {
+    #[white_list(0)]
+    Off,
+    #[white_list(1..=255)]
+    Value(u16),
+}
+
+fn main() {
+    let _ = u8::from(A::Value(300));
+}
@@ -0,0 +1,10 @@
+use binary_proc_rt::bytemap;
+
+#[bytemap]
+#[reserve(2..=3)]
+struct A {
+    #[pos(0..=1)]
+    a: u16,
+}
+
+fn main() {}
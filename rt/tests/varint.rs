@@ -0,0 +1,180 @@
+//! `#[varint(..)]`：拒绝非最短与溢出的编码，有符号值在边界处正确扩展符号位
+
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::varint::{Protobuf, Sleb128, Uleb128};
+use binary_proc_rt::{bytemap, Decode};
+
+#[bytemap(packed)]
+#[derive(Debug, PartialEq)]
+struct Entry {
+    #[varint(uleb128)]
+    offset: u32,
+    #[varint(sleb128)]
+    delta: i8,
+    tag: u8,
+}
+
+fn uleb<T: Uleb128>(value: T) -> Vec<u8> {
+    Uleb128::encode(value).collect()
+}
+
+fn sleb<T: Sleb128>(value: T) -> Vec<u8> {
+    Sleb128::encode(value).collect()
+}
+
+fn protobuf<T: Protobuf>(value: T) -> Vec<u8> {
+    Protobuf::encode(value).collect()
+}
+
+#[test]
+fn uleb128_round_trip() {
+    assert_eq!(uleb(0u8), [0]);
+    assert_eq!(uleb(127u8), [0x7f]);
+    assert_eq!(uleb(128u8), [0x80, 0x01]);
+    assert_eq!(uleb(u8::MAX), [0xff, 0x01]);
+    assert_eq!(uleb(624485u32), [0xe5, 0x8e, 0x26]);
+    let max = uleb(u64::MAX);
+    assert_eq!(
+        max,
+        [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+    );
+    assert_eq!(max.len(), <u64 as Uleb128>::MAX_LEN);
+    assert_eq!(<u64 as Uleb128>::decode(&max), Some(u64::MAX));
+    let max = uleb(u128::MAX);
+    assert_eq!(max.len(), 19);
+    assert_eq!(<u128 as Uleb128>::decode(&max), Some(u128::MAX));
+}
+
+#[test]
+fn uleb128_rejects_overlong_and_overflow() {
+    assert_eq!(<u32 as Uleb128>::decode(&[0x80, 0x00]), None);
+    assert_eq!(<u32 as Uleb128>::decode(&[0xe5, 0x8e, 0xa6, 0x00]), None);
+    // 256 放不进 u8
+    assert_eq!(<u8 as Uleb128>::decode(&[0x80, 0x02]), None);
+    // 最后一个字节超出 64 位
+    let overflow = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03];
+    assert_eq!(<u64 as Uleb128>::decode(&overflow), None);
+    let overflow = [0xff, 0xff, 0xff, 0xff, 0x1f];
+    assert_eq!(<u32 as Uleb128>::decode(&overflow), None);
+}
+
+#[test]
+fn sleb128_sign_extension() {
+    assert_eq!(sleb(0i8), [0]);
+    assert_eq!(sleb(-1i8), [0x7f]);
+    assert_eq!(sleb(63i8), [0x3f]);
+    // 第 6 位是符号位，64 与 -65 需要第二个字节
+    assert_eq!(sleb(64i8), [0xc0, 0x00]);
+    assert_eq!(sleb(-64i8), [0x40]);
+    assert_eq!(sleb(-65i8), [0xbf, 0x7f]);
+    assert_eq!(sleb(i8::MAX), [0xff, 0x00]);
+    assert_eq!(sleb(i8::MIN), [0x80, 0x7f]);
+    for value in i8::MIN..=i8::MAX {
+        assert_eq!(<i8 as Sleb128>::decode(&sleb(value)), Some(value));
+    }
+    for value in [i64::MIN, i64::MIN + 1, -1, i64::MAX] {
+        let bytes = sleb(value);
+        assert!(bytes.len() <= <i64 as Sleb128>::MAX_LEN);
+        assert_eq!(<i64 as Sleb128>::decode(&bytes), Some(value));
+    }
+    for value in [i128::MIN, i128::MAX] {
+        assert_eq!(<i128 as Sleb128>::decode(&sleb(value)), Some(value));
+    }
+}
+
+#[test]
+fn sleb128_rejects_overlong_and_overflow() {
+    // -1 多余的符号扩展字节
+    assert_eq!(<i8 as Sleb128>::decode(&[0xff, 0x7f]), None);
+    assert_eq!(<i32 as Sleb128>::decode(&[0x80, 0x00]), None);
+    // 128 与 -129 放不进 i8
+    assert_eq!(<i8 as Sleb128>::decode(&[0x80, 0x01]), None);
+    assert_eq!(<i8 as Sleb128>::decode(&[0xff, 0x7e]), None);
+}
+
+/// protobuf 的负数按 64 位补码编码，int32 与 int64 都占 10 字节
+#[test]
+fn protobuf_negative() {
+    let minus_one = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    assert_eq!(protobuf(-1i32), minus_one);
+    assert_eq!(protobuf(-1i64), minus_one);
+    assert_eq!(protobuf(-1i8), minus_one);
+    assert_eq!(<i32 as Protobuf>::decode(&minus_one), Some(-1));
+    assert_eq!(<i64 as Protobuf>::decode(&minus_one), Some(-1));
+    let min = [0x80, 0x80, 0x80, 0x80, 0xf8, 0xff, 0xff, 0xff, 0xff, 0x01];
+    assert_eq!(protobuf(i32::MIN), min);
+    assert_eq!(<i32 as Protobuf>::decode(&min), Some(i32::MIN));
+    let min = protobuf(i64::MIN);
+    assert_eq!(min.len(), 10);
+    assert_eq!(<i64 as Protobuf>::decode(&min), Some(i64::MIN));
+    // i64::MIN 放不进 int32
+    assert_eq!(<i32 as Protobuf>::decode(&min), None);
+    // 只编码低 32 位的 -1 不是合法的 int32
+    assert_eq!(
+        <i32 as Protobuf>::decode(&[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        None
+    );
+}
+
+#[test]
+fn protobuf_max_round_trip() {
+    assert_eq!(protobuf(u32::MAX), [0xff, 0xff, 0xff, 0xff, 0x0f]);
+    assert_eq!(
+        <u32 as Protobuf>::decode(&protobuf(u32::MAX)),
+        Some(u32::MAX)
+    );
+    assert_eq!(
+        <u64 as Protobuf>::decode(&protobuf(u64::MAX)),
+        Some(u64::MAX)
+    );
+    assert_eq!(
+        <i32 as Protobuf>::decode(&protobuf(i32::MAX)),
+        Some(i32::MAX)
+    );
+    assert_eq!(<u8 as Protobuf>::decode(&[0x80, 0x02]), None);
+    assert_eq!(<u64 as Protobuf>::decode(&[0x80, 0x00]), None);
+}
+
+#[test]
+fn try_decode_truncated() {
+    let input = [0xe5, 0x8e, 0x26, 0x7f, 9];
+    let entry = Entry {
+        offset: 624485,
+        delta: -1,
+        tag: 9,
+    };
+    assert_eq!(
+        Entry::try_decode(Le(&input[..])),
+        Decode::Complete(entry, 5)
+    );
+    // varint 未结束时至少还需要一个字节
+    for len in 0..3 {
+        assert_eq!(
+            Entry::try_decode(Le(&input[..len])),
+            Decode::Incomplete { needed: 1 }
+        );
+    }
+    assert_eq!(
+        Entry::try_decode(Be(&input[..4])),
+        Decode::Incomplete { needed: 1 }
+    );
+}
+
+#[test]
+fn try_decode_invalid() {
+    // 非最短编码
+    assert_eq!(
+        Entry::try_decode(Le(&[0x80, 0x00, 0x7f, 9][..])),
+        Decode::Error(0..=1)
+    );
+    // u32 的 varint 至多 5 字节
+    assert_eq!(
+        Entry::try_decode(Le(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01, 0x7f, 9][..])),
+        Decode::Error(0..=4)
+    );
+    // i8 溢出
+    assert_eq!(
+        Entry::try_decode(Le(&[1, 0x80, 0x01, 9][..])),
+        Decode::Error(1..=2)
+    );
+}
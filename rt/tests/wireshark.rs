@@ -0,0 +1,93 @@
+//! Lua dissector 的 golden 文件测试，`UPDATE_GOLDEN=1 cargo test` 重新生成 golden 文件
+
+use binary_proc_rt::wireshark::{lua_dissector, ByteOrder};
+use binary_proc_rt::{bitmap, bytemap, restrict};
+
+#[bitmap(u8, dissector)]
+#[derive(Debug)]
+struct Flags {
+    #[pos(0)]
+    ack: bool,
+    #[pos(1..=3)]
+    priority: u8,
+    #[pos(4..=7)]
+    delta: i8,
+}
+
+#[restrict(u8, dissector)]
+#[derive(Debug)]
+enum Kind {
+    #[white_list(1)]
+    Request,
+    #[white_list(2)]
+    Response,
+}
+
+#[restrict(u16, dissector)]
+#[derive(Debug)]
+enum Status {
+    #[white_list(0)]
+    Ok,
+    #[white_list(100..=199, 255)]
+    Retry(u16),
+    #[white_list(400..)]
+    Failure(u16),
+}
+
+#[bytemap(dissector)]
+#[derive(Debug)]
+struct Version {
+    #[pos(0)]
+    major: u8,
+    #[pos(1)]
+    minor: u8,
+}
+
+#[bytemap(dissector)]
+#[reserved(4..=5)]
+struct Packet<'a> {
+    #[pos(0)]
+    kind: Kind,
+    #[pos(1)]
+    flags: Flags,
+    #[pos(2..=3)]
+    status: Status,
+    #[pos(6..=7)]
+    version: Version,
+    #[pos(8..=11)]
+    length: u32,
+    #[pos(12..=15)]
+    ratio: f32,
+    #[pos(16..=23)]
+    #[padded(0)]
+    name: &'a str,
+    #[pos(24..=29)]
+    mac: [u8; 6],
+}
+
+fn check_golden(name: &str, actual: &str) {
+    let path = format!("{}/tests/golden/{}", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(actual, expected, "{} is out of date", name);
+}
+
+#[test]
+fn packet_le() {
+    let lua = lua_dissector::<Packet>("demo", "Demo Protocol", ByteOrder::Little);
+    check_golden("packet_le.lua", &lua);
+}
+
+#[test]
+fn packet_be() {
+    let lua = lua_dissector::<Packet>("demo", "Demo Protocol", ByteOrder::Big);
+    check_golden("packet_be.lua", &lua);
+}
+
+#[test]
+fn standalone_bitmap() {
+    let lua = lua_dissector::<Flags>("flags", "Flags", ByteOrder::Little);
+    check_golden("flags.lua", &lua);
+}
@@ -0,0 +1,55 @@
+//! `unit` 参数：pos 按字计数，`Le`/`Be` 同时决定字的顺序与字内的字节序
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{Be, Le};
+
+#[bytemap(unit = u16)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[reserved(4)]
+struct Block {
+    #[pos(0)]
+    id: u16,
+    #[pos(1..=2)]
+    addr: u32,
+    #[pos(3)]
+    tag: [u8; 2],
+}
+
+const BLOCK: Block = Block {
+    id: 7,
+    addr: 0x1234_5678,
+    tag: [0xaa, 0xbb],
+};
+
+#[test]
+fn words_le() {
+    let words = BLOCK.encode_words_le();
+    assert_eq!(words, [7, 0x5678, 0x1234, 0xbbaa, 0]);
+    assert_eq!(Block::try_from(Le(&words[..])), Ok(BLOCK));
+    // 字节形式的 pos 为字的两倍
+    assert_eq!(Block::try_from(Le(&BLOCK.encode_le()[..])), Ok(BLOCK));
+    assert_eq!(BLOCK.encode_le().len(), 10);
+}
+
+#[test]
+fn words_be() {
+    let words = BLOCK.encode_words_be();
+    assert_eq!(words, [7, 0x1234, 0x5678, 0xaabb, 0]);
+    assert_eq!(Block::try_from(Be(&words[..])), Ok(BLOCK));
+}
+
+/// 以字节序解读另一字节序编码的字，字的顺序随之交换
+#[test]
+fn mismatched_order() {
+    let words = BLOCK.encode_words_le();
+    let value = Block::try_from(Be(&words[..])).unwrap();
+    assert_eq!(value.addr, 0x5678_1234);
+    assert_eq!(value.tag, [0xbb, 0xaa]);
+}
+
+/// 错误范围以字计
+#[test]
+fn truncated_words() {
+    let words = BLOCK.encode_words_le();
+    assert_eq!(Block::try_from(Le(&words[..2])), Err(1..=2));
+}
@@ -0,0 +1,113 @@
+//! `bytemap(zerocopy)`：`{Name}Raw` 镜像结构与字节切片之间零拷贝转换
+
+use binary_proc_rt::bytemap;
+use binary_proc_rt::endian::{Be, Le};
+use binary_proc_rt::raw::{RawBytes, U16Le, U32Be};
+
+#[bytemap(zerocopy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[reserved(3, 10..=11)]
+struct Header {
+    #[pos(0..=1)]
+    kind: u16,
+    #[pos(2)]
+    flags: i8,
+    #[pos(4..=7)]
+    len: u32,
+    #[pos(8..=9)]
+    pair: [u8; 2],
+}
+
+#[bytemap(zerocopy(be))]
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Sample {
+    #[pos(0..=7)]
+    values: [u16; 4],
+    #[pos(8..=11)]
+    scale: f32,
+}
+
+const INPUT: [u8; 12] = [0x34, 0x12, 0xfe, 0xaa, 4, 3, 2, 1, 7, 8, 0xbb, 0xbb];
+
+fn header() -> Header {
+    Header {
+        kind: 0x1234,
+        flags: -2,
+        len: 0x0102_0304,
+        pair: [7, 8],
+    }
+}
+
+#[test]
+fn layout_matches_pos() {
+    assert_eq!(core::mem::size_of::<HeaderRaw>(), 12);
+    assert_eq!(core::mem::align_of::<HeaderRaw>(), 1);
+    assert_eq!(core::mem::offset_of!(HeaderRaw, len), 4);
+    assert_eq!(core::mem::size_of::<SampleRaw>(), 12);
+}
+
+#[test]
+fn ref_from_bytes_borrows_input() {
+    let raw = HeaderRaw::ref_from_bytes(&INPUT).unwrap();
+    assert_eq!(raw as *const HeaderRaw as *const u8, INPUT.as_ptr());
+    assert_eq!(raw.kind.get(), 0x1234);
+    assert_eq!(raw.len.get(), 0x0102_0304);
+    assert_eq!(Header::from(raw), header());
+    // 与逐字段解码一致
+    assert_eq!(Header::try_from(Le(&INPUT[..])), Ok(header()));
+    assert!(HeaderRaw::ref_from_bytes(&INPUT[..11]).is_none());
+}
+
+/// 不要求输入对齐
+#[test]
+fn unaligned_input() {
+    let mut buf = [0u8; 13];
+    buf[1..].copy_from_slice(&INPUT);
+    let raw = HeaderRaw::ref_from_bytes(&buf[1..]).unwrap();
+    assert_eq!(Header::from(raw), header());
+}
+
+#[test]
+fn mut_from_bytes_writes_through() {
+    let mut buf = INPUT;
+    let raw = HeaderRaw::mut_from_bytes(&mut buf).unwrap();
+    raw.kind = U16Le::new(0xbeef);
+    raw.len.set(9);
+    assert_eq!(&buf[..8], &[0xef, 0xbe, 0xfe, 0xaa, 9, 0, 0, 0]);
+}
+
+/// 转换为镜像结构时空隙与 reserved 字节为 0
+#[test]
+fn round_trip_through_raw() {
+    let raw = HeaderRaw::from(&header());
+    assert_eq!(
+        raw.as_bytes(),
+        &[0x34, 0x12, 0xfe, 0, 4, 3, 2, 1, 7, 8, 0, 0]
+    );
+    assert_eq!(raw.as_bytes(), &header().encode_le()[..]);
+    assert_eq!(Header::from(&raw), header());
+}
+
+#[test]
+fn big_endian_mirror() {
+    let sample = Sample {
+        values: [1, 2, 0x0304, 0xffff],
+        scale: 0.5,
+    };
+    let raw = SampleRaw::from(&sample);
+    assert_eq!(raw.as_bytes(), &sample.encode_be()[..]);
+    let bytes = raw.as_bytes().to_vec();
+    let raw = SampleRaw::ref_from_bytes(&bytes).unwrap();
+    assert_eq!(Sample::from(raw), sample);
+    assert_eq!(Sample::try_from(Be(&bytes[..])), Ok(sample));
+    assert_eq!(U32Be::new(0x0102_0304).get(), 0x0102_0304);
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn bytemuck_pod() {
+    let raw: &HeaderRaw = binary_proc_rt::bytemuck::from_bytes(&INPUT);
+    assert_eq!(Header::from(raw), header());
+    let zeroed: HeaderRaw = binary_proc_rt::bytemuck::Zeroable::zeroed();
+    assert_eq!(zeroed.as_bytes(), &[0; 12]);
+}
@@ -1,4 +1,4 @@
-//! ```
+//! ```ignore
 //! #[bitmap(u8)]
 //! struct A {
 //!     #[pos(0..=1)]
@@ -9,6 +9,8 @@
 use quote::ToTokens;
 use syn::{parse::Parse, Data, DeriveInput, Result};
 
+use crate::diagnostics::check_attr_typos;
+
 pub(crate) struct BitField {
     pub(crate) pos: syn::Expr,
     pub(crate) ident: syn::Ident,
@@ -18,6 +20,7 @@ pub(crate) struct BitField {
 impl Parse for BitField {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let field = syn::Field::parse_named(input)?;
+        check_attr_typos(&field.attrs, &["pos"])?;
         let attr = field
             .attrs
             .iter()
@@ -26,7 +29,7 @@ impl Parse for BitField {
                 field.to_token_stream(),
                 "pos attr must be used",
             ))?;
-        return Ok(BitField {
+        Ok(BitField {
             pos: attr.parse_args::<syn::Expr>()?,
             ident: field
                 .to_owned()
@@ -36,7 +39,7 @@ impl Parse for BitField {
                     "only named field is supported",
                 ))?,
             target_type: field.ty,
-        });
+        })
     }
 }
 
@@ -0,0 +1,117 @@
+use syn::{parenthesized, parse::Parse, Error, Ident, Path, Token};
+
+use crate::crate_path::{default_crate, parse_crate_arg};
+use crate::field_encoding::Endian;
+
+/// `#[bytemap(...)]` 的参数
+pub(crate) struct BytemapArgs {
+    /// 未指定 pos 的字段依次紧密排列
+    pub(crate) packed: bool,
+    /// 生成 `{Name}Builder`
+    pub(crate) builder: bool,
+    /// 生成 `Default` 实现
+    pub(crate) default: bool,
+    /// 生成 `{Name}Partial` 与 `decode_partial`
+    pub(crate) partial: bool,
+    /// 生成 `decode_all`，返回所有失败的字段，隐含 partial
+    pub(crate) accumulate: bool,
+    /// 导出 Kaitai Struct 与 JSON 描述
+    pub(crate) schema: bool,
+    /// 生成 C 头文件片段 `C_HEADER`
+    pub(crate) c_header: bool,
+    /// 实现 `wireshark::Dissect`
+    pub(crate) dissector: bool,
+    /// 生成 `{Name}Raw` 镜像结构，`zerocopy(be)` 表示大端，默认小端
+    pub(crate) zerocopy: Option<Endian>,
+    /// 生成以基地址 volatile 访问的 `{Name}Mmio`
+    pub(crate) mmio: bool,
+    /// pos 与 reserved 的寻址单位，`unit = u16` 时按 16 位字计数
+    pub(crate) unit: Option<Ident>,
+    /// 运行时 crate 的路径
+    pub(crate) krate: Path,
+}
+
+impl Default for BytemapArgs {
+    fn default() -> Self {
+        BytemapArgs {
+            packed: false,
+            builder: false,
+            default: false,
+            partial: false,
+            accumulate: false,
+            schema: false,
+            c_header: false,
+            dissector: false,
+            zerocopy: None,
+            mmio: false,
+            unit: None,
+            krate: default_crate(),
+        }
+    }
+}
+
+impl Parse for BytemapArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = BytemapArgs::default();
+        while !input.is_empty() {
+            if input.peek(Token![crate]) {
+                args.krate = parse_crate_arg(input)?;
+            } else {
+                let ident = input.parse::<Ident>()?;
+                match ident.to_string().as_str() {
+                    "packed" => args.packed = true,
+                    "builder" => args.builder = true,
+                    "default" => args.default = true,
+                    "partial" => args.partial = true,
+                    "accumulate" => args.accumulate = true,
+                    "schema" => args.schema = true,
+                    "c_header" => args.c_header = true,
+                    "dissector" => args.dissector = true,
+                    "mmio" => args.mmio = true,
+                    "zerocopy" if input.peek(syn::token::Paren) => {
+                        let content;
+                        parenthesized!(content in input);
+                        let endian = content.parse::<Ident>()?;
+                        args.zerocopy = Some(match endian.to_string().as_str() {
+                            "le" => Endian::Le,
+                            "be" => Endian::Be,
+                            _ => return Err(Error::new_spanned(endian, "expected `le` or `be`")),
+                        });
+                    }
+                    "zerocopy" => args.zerocopy = Some(Endian::Le),
+                    "unit" => {
+                        input.parse::<Token![=]>()?;
+                        let unit = input.parse::<Ident>()?;
+                        if !["u8", "u16", "u32", "u64"].contains(&unit.to_string().as_str()) {
+                            return Err(Error::new_spanned(
+                                unit,
+                                "expected `u8`, `u16`, `u32` or `u64`",
+                            ));
+                        }
+                        args.unit = Some(unit);
+                    }
+                    _ => return Err(Error::new_spanned(ident, "unknown bytemap argument")),
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if let (true, Some(unit)) = (args.packed, &args.unit) {
+            return Err(Error::new_spanned(unit, "unit requires explicit pos"));
+        }
+        Ok(args)
+    }
+}
+
+impl BytemapArgs {
+    /// 每个寻址单位的字节数
+    pub(crate) fn unit_size(&self) -> usize {
+        match self.unit.as_ref().map(|x| x.to_string()).as_deref() {
+            Some("u16") => 2,
+            Some("u32") => 4,
+            Some("u64") => 8,
+            _ => 1,
+        }
+    }
+}
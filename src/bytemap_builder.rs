@@ -0,0 +1,172 @@
+//! ```ignore
+//! #[bytemap(builder, default)]
+//! struct Ehdr {
+//!     #[pos(0..=3)]
+//!     #[magic = *b"\x7fELF"]
+//!     magic: [u8; 4],
+//!     #[pos(4)]
+//!     #[default = 2]
+//!     class: u8,
+//!     #[pos(5)]
+//!     data: Data, // restrict enum
+//! }
+//! let ehdr = Ehdr::builder().data(Data::Le).build()?;
+//! ```
+//!
+//! `build()` 以 `Validate` 检查每个字段，因此字段类型都必须实现 `Validate`。
+//! 原生类型、数组、字符串与切片已经实现；bitmap、restrict 枚举与带 builder 的 bytemap
+//! 会生成实现；其他类型（如不带 builder 的 bytemap）需手动添加 `impl Validate for T {}`，
+//! 否则在该字段的类型处报错。
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::FieldEncoding;
+
+/// `{Name}Builder` 以及 bytemap 自身的 `Validate` 实现
+pub(crate) fn builder(bytemap: &BytemapStruct) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let ident = &clean.ident;
+    let vis = &clean.vis;
+    let generics = &clean.generics;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let builder_ident = format_ident!("{}Builder", ident);
+    let krate = &bytemap.krate;
+
+    let mut builder_fields = TokenStream::new();
+    let mut builder_init = TokenStream::new();
+    let mut setters = TokenStream::new();
+    let mut build_fields = TokenStream::new();
+    let mut validate_fields = TokenStream::new();
+    let mut check_conditions = TokenStream::new();
+    let mut check_fields = TokenStream::new();
+    let mut field_idents = Vec::new();
+    for field in bytemap.fields.iter() {
+        let field_ident = &field.ident;
+        let target_type = &field.target_type;
+        let field_name = field_ident.to_string();
+        builder_fields.extend(quote!(#field_ident: Option<#target_type>,));
+        let init = match (&field.magic, &field.default) {
+            (Some(value), _) | (None, Some(value)) => quote!(Some(#value)),
+            (None, None) => quote!(None),
+        };
+        builder_init.extend(quote!(#field_ident: #init,));
+        if field.magic.is_none() {
+            setters.extend(quote! {
+                pub fn #field_ident(mut self, #field_ident: #target_type) -> Self {
+                    self.#field_ident = Some(#field_ident);
+                    self
+                }
+            });
+        }
+        match &field.condition {
+            // 条件字段未设置即为 None，但必须与条件一致
+            Some(condition) => {
+                build_fields.extend(quote!(let #field_ident = self.#field_ident;));
+                check_conditions.extend(quote! {
+                    if (#condition) != #field_ident.is_some() {
+                        return Err(#krate::BuildError::Invalid(#field_name));
+                    }
+                });
+            }
+            None => {
+                build_fields.extend(quote! {
+                    let #field_ident = self
+                        .#field_ident
+                        .ok_or(#krate::BuildError::Missing(#field_name))?;
+                });
+            }
+        }
+        // 长度前缀字段的长度还需在前缀的范围与 min/max 之内
+        let in_bounds = |value: TokenStream| match &field.encoding {
+            FieldEncoding::Prefixed(prefix) => {
+                let out_of_bounds = prefix.out_of_bounds(quote!(x.len()));
+                match field.condition {
+                    Some(_) => quote!(&& #value.as_ref().is_none_or(|x| !(#out_of_bounds))),
+                    None => quote!(&& { let x = &#value; !(#out_of_bounds) }),
+                }
+            }
+            _ => quote!(),
+        };
+        let in_bounds_self = in_bounds(quote!(self.#field_ident));
+        let in_bounds_value = in_bounds(quote!(value.#field_ident));
+        // 字段类型未实现 Validate 时，错误指向该类型
+        let field_ty = match field.condition {
+            Some(_) => quote!(Option<#target_type>),
+            None => quote!(#target_type),
+        };
+        let validate = |value: TokenStream| quote_spanned!(target_type.span()=> <#field_ty as #krate::Validate>::validate(&#value));
+        let validate_self = validate(quote!(self.#field_ident));
+        let validate_value = validate(quote!(value.#field_ident));
+        validate_fields.extend(quote! {
+            if !(#validate_self #in_bounds_self) {
+                return false;
+            }
+        });
+        check_fields.extend(quote! {
+            if !(#validate_value #in_bounds_value) {
+                return Err(#krate::BuildError::Invalid(#field_name));
+            }
+        });
+        field_idents.push(field_ident);
+    }
+
+    quote! {
+        #vis struct #builder_ident #generics #where_clause {
+            #builder_fields
+        }
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub fn builder() -> #builder_ident #ty_generics {
+                #builder_ident {
+                    #builder_init
+                }
+            }
+        }
+        impl #impl_generics #builder_ident #ty_generics #where_clause {
+            #setters
+            /// 检查所有字段均已设置、条件字段与条件一致，且每个字段的值合法
+            pub fn build(self) -> Result<#ident #ty_generics, #krate::BuildError> {
+                #build_fields
+                #check_conditions
+                let value = #ident {
+                    #(#field_idents),*
+                };
+                #check_fields
+                Ok(value)
+            }
+        }
+        impl #impl_generics #krate::Validate for #ident #ty_generics #where_clause {
+            fn validate(&self) -> bool {
+                #validate_fields
+                true
+            }
+        }
+    }
+}
+
+/// 未指定 `#[default]` 的字段使用 `Default::default()`，条件字段默认为 None
+pub(crate) fn default(bytemap: &BytemapStruct) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let fields = bytemap.fields.iter().map(|field| {
+        let field_ident = &field.ident;
+        let value = match (&field.magic, &field.default, &field.condition) {
+            (Some(value), _, None) | (None, Some(value), None) => quote!(#value),
+            (_, Some(value), Some(_)) => quote!(Some(#value)),
+            _ => quote!(::core::default::Default::default()),
+        };
+        quote!(#field_ident: #value,)
+    });
+    quote! {
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#fields)*
+                }
+            }
+        }
+    }
+}
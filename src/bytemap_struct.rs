@@ -1,4 +1,4 @@
-//! ```
+//! ```ignore
 //! // bytemap(len)
 //! #[bytemap(64)]
 //! struct A {
@@ -9,39 +9,128 @@
 //!     field2: u8, // error pos must be specified
 //! }
 //! // bytemap(packed)
-//! struct A {
-//!     field1: u8, // 不支持 u8
-//!     field2: u32,
-//!     field3: u16
+//! // 未指定 pos 的字段紧跟在上一个字段之后，长度来自 BinarySize 或 len
+//! #[bytemap(packed)]
+//! struct B<'a> {
+//!     field1: u8,    // 0..=0
+//!     #[align(4)]
+//!     field2: u32,   // 4..=7
+//!     #[pad(2)]
+//!     field3: u16,   // 10..=11
+//!     #[len = 4]
+//!     field4: &'a [u8], // 12..=15
+//! }
+//! // 条件字段：仅当 when 中的表达式（可引用之前的字段）成立时才解码，
+//! // 编码时仅编码 Some，之后紧密排列的字段位置随之变化
+//! #[bytemap(packed)]
+//! struct C {
+//!     version: u8,
+//!     #[when(version >= 2)]
+//!     ext: Option<u16>,
+//!     flags: u8,
 //! }
 //! ```
 
 use std::ops::RangeInclusive;
 
-use quote::ToTokens;
-use syn::{parse::Parse, parse2, Data, DeriveInput, Error, Result};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse2, Data, DeriveInput, Error, Expr, GenericArgument, Ident, Path, PathArguments, Result,
+    Token, Type,
+};
 
+use crate::bytemap_args::BytemapArgs;
+use crate::diagnostics::{check_attr_typos, gaps, warning};
+use crate::field_encoding::{FieldEncoding, ENCODING_ATTRS};
 use crate::literal_pos::range_from_expr;
+use crate::type_size::{known_size, literal_usize};
+
+const LAYOUT_ATTRS: [&str; 7] = ["pos", "len", "align", "pad", "when", "default", "magic"];
+/// 结构体上的辅助属性
+const STRUCT_ATTRS: [&str; 2] = ["bytemap", "reserved"];
+
+/// 下一个紧密排列字段的起始偏移
+pub(crate) struct Cursor {
+    tokens: TokenStream,
+    value: Option<usize>,
+}
+
+impl Cursor {
+    fn new() -> Self {
+        Cursor {
+            tokens: quote!(0usize),
+            value: Some(0),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct ByteField {
     pub(crate) pos: syn::ExprRange,
+    /// 字段占用的字节数，不依赖之前的条件字段
+    pub(crate) len: TokenStream,
     pub(crate) ident: syn::Ident,
     pub(crate) target_type: syn::Type,
-    pub(crate) pos_value: RangeInclusive<usize>,
+    /// 宏展开时无法计算（如依赖 BinarySize）的位置为 None
+    pub(crate) pos_value: Option<RangeInclusive<usize>>,
+    pub(crate) encoding: FieldEncoding,
+    /// `#[when(expr)]`，字段类型为 `Option<target_type>`
+    pub(crate) condition: Option<Expr>,
+    /// 紧密排列的条件字段缺失时，后续字段的起始偏移
+    pub(crate) cursor_before: Option<TokenStream>,
+    /// `#[default = expr]`，Builder 与 Default 的初始值
+    pub(crate) default: Option<Expr>,
+    /// `#[magic = expr]`，固定值，解码时校验
+    pub(crate) magic: Option<Expr>,
 }
 
-impl Parse for ByteField {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let field = syn::Field::parse_named(input)?;
-        let attr = field
-            .attrs
-            .iter()
-            .find(|attr| attr.path.to_token_stream().to_string() == "pos")
-            .ok_or(syn::parse::Error::new_spanned(
-                field.to_token_stream(),
-                "pos attr must be used",
-            ))?;
-        let pos = attr.parse_args::<syn::Expr>()?;
+/// `Option<T>` 中的 `T`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 以 `unit` 字节为单位的范围对应的字节范围
+fn unit_bytes(range: &RangeInclusive<usize>, unit: usize) -> RangeInclusive<usize> {
+    range.start() * unit..=range.end() * unit + unit - 1
+}
+
+fn find_attr<'a>(field: &'a syn::Field, name: &str) -> Option<&'a syn::Attribute> {
+    field
+        .attrs
+        .iter()
+        .find(|attr| attr.path.to_token_stream().to_string() == name)
+}
+
+/// `#[len = 4]` 或 `#[len(4)]`
+fn attr_value(attr: &syn::Attribute) -> Result<Expr> {
+    let parser = |input: ParseStream| {
+        input.parse::<Token!(=)>()?;
+        input.parse::<Expr>()
+    };
+    match parser.parse2(attr.tokens.to_owned()) {
+        Ok(expr) => Ok(expr),
+        Err(_) => attr.parse_args::<Expr>(),
+    }
+}
+
+impl ByteField {
+    pub(crate) fn new(field: syn::Field, cursor: &mut Cursor, args: &BytemapArgs) -> Result<Self> {
         let ident = field
             .to_owned()
             .ident
@@ -49,77 +138,497 @@ impl Parse for ByteField {
                 field.to_token_stream(),
                 "only named field is supported",
             ))?;
-        let range = if let syn::Expr::Lit(ref lit) = pos {
-            parse2(quote::quote!(#lit ..= #lit))?
-        } else if let syn::Expr::Range(ref range) = pos {
-            range.to_owned()
+        let known = LAYOUT_ATTRS
+            .iter()
+            .chain(ENCODING_ATTRS.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        check_attr_typos(&field.attrs, &known)?;
+        let condition = find_attr(&field, "when")
+            .map(|attr| attr.parse_args::<Expr>())
+            .transpose()?;
+        let target_type = match condition {
+            Some(_) => option_inner(&field.ty)
+                .ok_or(Error::new_spanned(
+                    &field.ty,
+                    "conditional field must be `Option<T>`",
+                ))?
+                .to_owned(),
+            None => field.ty.to_owned(),
+        };
+        let encoding = FieldEncoding::from_field(&field.attrs, &target_type)?;
+        let default = find_attr(&field, "default").map(attr_value).transpose()?;
+        let magic = find_attr(&field, "magic").map(attr_value).transpose()?;
+        if let (Some(_), Some(attr)) = (&magic, find_attr(&field, "default")) {
+            return Err(Error::new_spanned(
+                attr,
+                "magic field can not have a default",
+            ));
+        }
+        if encoding.is_dynamic() {
+            for name in ["pos", "len"] {
+                if let Some(attr) = find_attr(&field, name) {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "length of varint and prefix fields is determined by their value",
+                    ));
+                }
+            }
+            if !args.packed {
+                return Err(Error::new_spanned(
+                    field.to_token_stream(),
+                    "varint and prefix fields require #[bytemap(packed)]",
+                ));
+            }
+        }
+        let cursor_before = cursor.tokens.to_owned();
+        let (range, len, pos_value) = match find_attr(&field, "pos") {
+            Some(attr) => {
+                for name in ["len", "align", "pad"] {
+                    if let Some(attr) = find_attr(&field, name) {
+                        return Err(Error::new_spanned(
+                            attr,
+                            "can not be used together with pos",
+                        ));
+                    }
+                }
+                let pos = attr.parse_args::<syn::Expr>()?;
+                let mut range = if let syn::Expr::Lit(ref lit) = pos {
+                    parse2(quote::quote!(#lit ..= #lit))?
+                } else if let syn::Expr::Range(ref range) = pos {
+                    range.to_owned()
+                } else {
+                    Err(syn::Error::new_spanned(
+                        pos.to_token_stream(),
+                        "Only ExprLit or ExprRange supported",
+                    ))?
+                };
+                let mut pos_value = range_from_expr(&pos)?;
+                // 以字寻址时换算为字节范围，之后的编解码均按字节进行
+                let unit = args.unit_size();
+                if unit > 1 {
+                    pos_value = unit_bytes(&pos_value, unit);
+                    let (start, end) = (
+                        Literal::usize_unsuffixed(*pos_value.start()),
+                        Literal::usize_unsuffixed(*pos_value.end()),
+                    );
+                    range = parse2(quote!(#start..=#end))?;
+                }
+                let width = pos_value.end() + 1 - pos_value.start();
+                // bool 可占用任意字节数
+                if let (FieldEncoding::Native, Some(size)) = (&encoding, known_size(&target_type)) {
+                    if size != width && target_type.to_token_stream().to_string() != "bool" {
+                        return Err(Error::new_spanned(
+                            attr,
+                            format!(
+                                "`{}` occupies {} bytes but pos covers {}",
+                                target_type.to_token_stream(),
+                                size,
+                                width
+                            ),
+                        ));
+                    }
+                }
+                let len = Literal::usize_unsuffixed(pos_value.end() + 1 - pos_value.start());
+                let len = quote!(#len);
+                let end = pos_value.end() + 1;
+                let end_lit = Literal::usize_unsuffixed(end);
+                cursor.tokens = quote!(#end_lit);
+                cursor.value = Some(end);
+                (range, len, Some(pos_value))
+            }
+            None if args.packed => {
+                Self::next_packed(&field, &target_type, &encoding, cursor, &args.krate)?
+            }
+            None => {
+                return Err(syn::parse::Error::new_spanned(
+                    field.to_token_stream(),
+                    "pos attr must be used",
+                ))
+            }
+        };
+        // 紧密排列的条件字段之后，字段位置只能在运行时确定
+        let cursor_before = if condition.is_some() && find_attr(&field, "pos").is_none() {
+            cursor.tokens = format_ident!("__next_{}", ident).to_token_stream();
+            cursor.value = None;
+            Some(cursor_before)
         } else {
-            Err(syn::Error::new_spanned(
-                pos.to_token_stream(),
-                "Only ExprLit or ExprRange supported",
-            ))?
+            None
         };
-        let target_type = field.ty;
-        return Ok(ByteField {
+        Ok(ByteField {
             pos: range,
+            len,
             ident,
             target_type,
-            pos_value: range_from_expr(&pos)?,
-        });
+            pos_value,
+            encoding,
+            condition,
+            cursor_before,
+            default,
+            magic,
+        })
+    }
+
+    pub(crate) fn pos_ident(&self) -> Ident {
+        format_ident!("__pos_{}", self.ident)
+    }
+
+    /// 解码时计算该字段的位置，变长整数与长度前缀字段的长度由输入 `__value` 决定
+    pub(crate) fn layout(&self, krate: &Path) -> TokenStream {
+        let pos = &self.pos;
+        let pos_ident = self.pos_ident();
+        let len = match &self.encoding {
+            FieldEncoding::Varint(kind) => {
+                let len_ident = format_ident!("__len_{}", self.ident);
+                let start = &self.pos.from;
+                let ty = &self.target_type;
+                let varint = kind.trait_path(krate);
+                quote! {
+                    let #len_ident = #krate::varint::scan(
+                        __value.0.get(#start..).unwrap_or(&[]),
+                        <#ty as #varint>::MAX_LEN,
+                    );
+                }
+            }
+            // 前缀不完整或长度超出前缀类型、min、max 时只包含前缀本身，解码时报告错误
+            FieldEncoding::Prefixed(prefix) => {
+                let len_ident = format_ident!("__len_{}", self.ident);
+                let start = &self.pos.from;
+                let (ty, size) = (&prefix.ty, prefix.size());
+                let item_size = prefix.item_size(krate);
+                let out_of_bounds = prefix.out_of_bounds(quote!(__count));
+                let raw = quote!(__value.0.get(__start..__start + #size));
+                let count = match prefix.endian {
+                    Some(endian) => {
+                        let wrapper = endian.wrapper(krate);
+                        quote!(#raw.map(|raw| <#ty>::try_from(#wrapper(raw))))
+                    }
+                    None => quote!(#raw.map(|raw| <#ty>::try_from(__value.with(raw)))),
+                };
+                quote! {
+                    let #len_ident = {
+                        let __start = #start;
+                        match #count {
+                            Some(Ok(count)) => {
+                                let __count = usize::try_from(count).unwrap_or(usize::MAX);
+                                if #out_of_bounds {
+                                    #size
+                                } else {
+                                    __count
+                                        .saturating_mul(#item_size)
+                                        .min(isize::MAX as usize)
+                                        + #size
+                                }
+                            }
+                            _ => #size,
+                        }
+                    };
+                }
+            }
+            _ => quote!(),
+        };
+        quote!(#len let #pos_ident = #pos;)
+    }
+
+    /// 编码时计算该字段的位置，变长整数与长度前缀字段的长度由 `self` 中的值决定
+    pub(crate) fn encode_layout(&self, krate: &Path) -> TokenStream {
+        let pos = &self.pos;
+        let pos_ident = self.pos_ident();
+        let len = match &self.encoding {
+            FieldEncoding::Varint(kind) => {
+                let len_ident = format_ident!("__len_{}", self.ident);
+                let field_ident = &self.ident;
+                let varint = kind.trait_path(krate);
+                // 不存在的条件字段不占用空间，长度仅用于构造位置
+                match self.condition {
+                    Some(_) => quote! {
+                        let #len_ident = self.#field_ident.map_or(1, #varint::encoded_len);
+                    },
+                    None => quote! {
+                        let #len_ident = #varint::encoded_len(self.#field_ident);
+                    },
+                }
+            }
+            FieldEncoding::Prefixed(prefix) => {
+                let len_ident = format_ident!("__len_{}", self.ident);
+                let field_ident = &self.ident;
+                let size = prefix.size();
+                let item_size = prefix.item_size(krate);
+                match self.condition {
+                    Some(_) => quote! {
+                        let #len_ident = self
+                            .#field_ident
+                            .as_ref()
+                            .map_or(#size, |x| #size + x.len() * #item_size);
+                    },
+                    None => quote! {
+                        let #len_ident = #size + self.#field_ident.len() * #item_size;
+                    },
+                }
+            }
+            _ => quote!(),
+        };
+        quote!(#len let #pos_ident = #pos;)
+    }
+
+    /// 推进紧密排列的条件字段之后的偏移，`present` 为该字段是否存在
+    pub(crate) fn advance(&self, present: TokenStream) -> TokenStream {
+        let pos_ident = self.pos_ident();
+        match &self.cursor_before {
+            Some(before) => {
+                let next_ident = format_ident!("__next_{}", self.ident);
+                quote! {
+                    let #next_ident = if #present { *#pos_ident.end() + 1 } else { #before };
+                }
+            }
+            None => quote!(),
+        }
+    }
+
+    /// 根据 cursor 以及 align、pad、len 计算紧密排列字段的位置，并推进 cursor
+    fn next_packed(
+        field: &syn::Field,
+        ty: &Type,
+        encoding: &FieldEncoding,
+        cursor: &mut Cursor,
+        krate: &Path,
+    ) -> Result<(syn::ExprRange, TokenStream, Option<RangeInclusive<usize>>)> {
+        let len = match find_attr(field, "len") {
+            Some(attr) => {
+                let len = attr_value(attr)?;
+                if literal_usize(&len) == Some(0) {
+                    return Err(Error::new_spanned(attr, "len must be greater than 0"));
+                }
+                len
+            }
+            // 由 layout 在运行时求出
+            None if encoding.is_dynamic() => {
+                let ident = field.ident.as_ref().map(|x| format_ident!("__len_{}", x));
+                parse2(quote!(#ident))?
+            }
+            None => match known_size(ty) {
+                Some(size) => {
+                    let size = Literal::usize_unsuffixed(size);
+                    parse2(quote!(#size))?
+                }
+                None if matches!(encoding, FieldEncoding::Native) => {
+                    parse2(quote!(<#ty as #krate::BinarySize>::SIZE))?
+                }
+                None => {
+                    return Err(Error::new_spanned(
+                        field.to_token_stream(),
+                        "len must be specified for variable sized field",
+                    ))
+                }
+            },
+        };
+        let pad = find_attr(field, "pad")
+            .map(|attr| attr.parse_args::<Expr>())
+            .transpose()?;
+        let align = find_attr(field, "align")
+            .map(|attr| {
+                let align = attr.parse_args::<Expr>()?;
+                match literal_usize(&align) {
+                    Some(0) => Err(Error::new_spanned(attr, "align must be greater than 0")),
+                    _ => Ok(align),
+                }
+            })
+            .transpose()?;
+
+        let mut start = cursor.tokens.to_owned();
+        let mut start_value = cursor.value;
+        if let Some(pad) = pad {
+            start = quote!((#start + #pad));
+            start_value = start_value.zip(literal_usize(&pad)).map(|(s, p)| s + p);
+        }
+        if let Some(align) = align {
+            start = quote!(((#start + #align - 1) / #align * #align));
+            start_value = start_value
+                .zip(literal_usize(&align))
+                .map(|(s, a)| s.next_multiple_of(a));
+        }
+        let end = quote!((#start + #len - 1));
+        let end_value = start_value.zip(literal_usize(&len)).map(|(s, l)| s + l - 1);
+
+        match (start_value, end_value) {
+            (Some(start), Some(end)) => {
+                let (start_lit, end_lit) = (
+                    Literal::usize_unsuffixed(start),
+                    Literal::usize_unsuffixed(end + 1),
+                );
+                cursor.tokens = quote!(#end_lit);
+                cursor.value = Some(end + 1);
+                let end_lit = Literal::usize_unsuffixed(end);
+                Ok((
+                    parse2(quote!(#start_lit..=#end_lit))?,
+                    len.to_token_stream(),
+                    Some(start..=end),
+                ))
+            }
+            _ => {
+                cursor.tokens = quote!((#end + 1));
+                cursor.value = None;
+                Ok((parse2(quote!(#start..=#end))?, len.to_token_stream(), None))
+            }
+        }
     }
 }
 
 pub(crate) struct BytemapStruct {
     pub(crate) fields: Vec<ByteField>,
     pub(crate) clean_struct: DeriveInput,
+    /// 运行时 crate 的路径
+    pub(crate) krate: Path,
+    /// 布局警告，随生成代码一同输出
+    pub(crate) warnings: TokenStream,
+    /// `#[reserved(..)]` 标记的范围，属于布局的一部分
+    pub(crate) reserved: Vec<RangeInclusive<usize>>,
+    /// 每个寻址单位的字节数，默认为 1
+    pub(crate) unit: usize,
 }
 
 impl BytemapStruct {
     pub(crate) fn clean(input: proc_macro::TokenStream) -> Result<DeriveInput> {
         let mut derive_input = syn::parse::<DeriveInput>(input)?;
-        derive_input
-            .attrs
-            .retain(|attr| attr.path.to_token_stream().to_string() != "bytemap");
+        derive_input.attrs.retain(|attr| {
+            !STRUCT_ATTRS.contains(&attr.path.to_token_stream().to_string().as_str())
+        });
         if let Data::Struct(ref mut data_struct) = derive_input.data {
             data_struct.fields.iter_mut().for_each(|x| {
-                x.attrs
-                    .retain(|x| x.path.to_token_stream().to_string() != "pos");
+                x.attrs.retain(|x| {
+                    let path = x.path.to_token_stream().to_string();
+                    !LAYOUT_ATTRS.contains(&path.as_str())
+                        && !ENCODING_ATTRS.contains(&path.as_str())
+                });
             });
         }
         Ok(derive_input)
     }
-}
 
-impl Parse for BytemapStruct {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let derive_input = DeriveInput::parse(input)?;
+    /// 存在条件字段、变长整数或长度前缀时布局长度可变
+    pub(crate) fn is_variable(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|x| x.condition.is_some() || x.encoding.is_dynamic())
+    }
+
+    /// reserved 范围覆盖到的长度，编码长度至少为此值
+    pub(crate) fn reserved_len(&self) -> usize {
+        self.reserved.iter().map(|x| x.end() + 1).max().unwrap_or(0)
+    }
+
+    /// 最后一个字节的位置（包含），仅适用于定长布局
+    pub(crate) fn limit(&self) -> TokenStream {
+        let reserved = self
+            .reserved
+            .iter()
+            .map(|x| x.end())
+            .max()
+            .map(|x| quote!(#x));
+        self.fields
+            .iter()
+            .map(|field| {
+                let end = &field.pos.to;
+                quote!(#end)
+            })
+            .chain(reserved)
+            .reduce(|max, end| quote!({ let (a, b) = (#max, #end); if a > b { a } else { b } }))
+            .unwrap_or(quote!(0usize))
+    }
+
+    /// 定长且长度不依赖泛型参数与生命周期时的编码长度，可用作非泛型类型中的数组长度
+    pub(crate) fn const_len(&self) -> Option<TokenStream> {
+        fn mentions(tokens: TokenStream, params: &[Ident]) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Group(group) => mentions(group.stream(), params),
+                proc_macro2::TokenTree::Ident(ident) => params.contains(&ident),
+                proc_macro2::TokenTree::Punct(punct) => punct.as_char() == '\'',
+                proc_macro2::TokenTree::Literal(_) => false,
+            })
+        }
+        if self.is_variable() {
+            return None;
+        }
+        let generics = &self.clean_struct.generics;
+        let params = generics
+            .type_params()
+            .map(|x| x.ident.to_owned())
+            .chain(generics.const_params().map(|x| x.ident.to_owned()))
+            .collect::<Vec<_>>();
+        let limit = self.limit();
+        match mentions(limit.to_owned(), &params) {
+            true => None,
+            false => Some(quote!((#limit) + 1)),
+        }
+    }
+
+    pub(crate) fn parse_with_args(
+        input: syn::parse::ParseStream,
+        args: &BytemapArgs,
+    ) -> syn::Result<Self> {
+        let derive_input = input.parse::<DeriveInput>()?;
         let mut fields = Vec::<ByteField>::new();
+        let mut cursor = Cursor::new();
         if let Data::Struct(data_struct) = derive_input.to_owned().data {
             for field in data_struct.fields {
-                let token = field.to_token_stream();
-                let byte_field = syn::parse2::<ByteField>(token)?;
+                let byte_field = ByteField::new(field, &mut cursor, args)?;
                 fields.push(byte_field);
             }
         }
-        fields.sort_by(|x, y| x.pos_value.to_owned().cmp(y.pos_value.to_owned()));
-        fields
-            .clone()
+        let mut sorted = fields
+            .iter()
+            .filter(|x| x.pos_value.is_some())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|x| x.pos_value.to_owned().map(|x| (*x.start(), *x.end())));
+        sorted
             .into_iter()
-            .try_fold(None, |prev: Option<ByteField>, curr| match prev {
+            .try_fold(None, |prev: Option<&ByteField>, curr| match prev {
                 Some(prev_field) => {
-                    if prev_field.pos_value.contains(curr.pos_value.start()) {
-                        return Err(Error::new_spanned(curr.pos, "position overlapped"));
+                    let curr_start = *curr.pos_value.as_ref().unwrap().start();
+                    if prev_field.pos_value.as_ref().unwrap().contains(&curr_start) {
+                        Err(Error::new_spanned(&curr.pos, "position overlapped"))
                     } else {
-                        return Ok(Some(curr));
+                        Ok(Some(curr))
                     }
                 }
-                None => {
-                    return Ok(Some(curr));
-                }
+                None => Ok(Some(curr)),
             })?;
+        check_attr_typos(&derive_input.attrs, &STRUCT_ATTRS)?;
+        let mut reserved = Vec::new();
+        for attr in derive_input.attrs.iter() {
+            if attr.path.is_ident("reserved") {
+                let parser = Punctuated::<Expr, Token!(,)>::parse_separated_nonempty;
+                for expr in attr.parse_args_with(parser)? {
+                    reserved.push(unit_bytes(&range_from_expr(&expr)?, args.unit_size()));
+                }
+            }
+        }
+        let mut covered = reserved.to_owned();
+        // 紧密排列时的空隙来自 align 与 pad，不视为意外
+        let mut warnings = TokenStream::new();
+        if !args.packed && fields.iter().all(|x| x.pos_value.is_some()) {
+            covered.extend(fields.iter().filter_map(|x| x.pos_value.to_owned()));
+            // 以字寻址时空隙同样按字报告
+            let (unit, name) = match args.unit_size() {
+                1 => (1, "bytes"),
+                unit => (unit, "words"),
+            };
+            for gap in gaps(covered) {
+                let (start, end) = (gap.start() / unit, gap.end() / unit);
+                let message = format!(
+                    "{} {}..={} are not covered by any field, mark them with #[reserved({}..={})] if intended",
+                    name, start, end, start, end
+                );
+                warnings.extend(warning(&derive_input.ident, &message));
+            }
+        }
         Ok(BytemapStruct {
             fields,
             clean_struct: Self::clean(derive_input.to_token_stream().into())?,
+            krate: args.krate.to_owned(),
+            warnings,
+            reserved,
+            unit: args.unit_size(),
         })
     }
 }
@@ -0,0 +1,324 @@
+//! `c_header` 参数：生成供 C 代码使用的头文件片段。
+//!
+//! ```ignore
+//! #[bytemap(c_header)]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//!     #[pos(4..=7)]
+//!     len: u32,
+//! }
+//! // build.rs
+//! std::fs::write(out_dir.join("header.h"), Header::C_HEADER)?;
+//! ```
+//!
+//! bytemap 生成 `#pragma pack(1)` 的结构体，空隙以 `reserved_N` 数组显式填充，
+//! 并以 `_Static_assert` 校验大小与偏移；bitmap 生成各字段的 SHIFT、MASK 与存取宏；
+//! restrict 生成变体枚举与 white_list 检查函数。嵌套类型按 Rust 类型名引用，
+//! 需先包含其自身的头文件。
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, Result, Type, TypePath};
+
+use crate::bitmap_struct::BitmapStruct;
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::FieldEncoding;
+use crate::literal_pos::range_from_expr;
+use crate::restrict_enum::RestrictVariant;
+use crate::schema::{snake_case, tokens_string, white_list_bounds};
+
+/// 原生整数与浮点数对应的 C 类型
+fn c_primitive(ident: &str) -> Option<&'static str> {
+    Some(match ident {
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "f32" => "float",
+        "f64" => "double",
+        _ => return None,
+    })
+}
+
+/// 字段在 C 结构体中的元素类型与数组维度，如 `[u16; 2]` => (`uint16_t`, `[2]`)
+fn c_type(ty: &Type, width: usize) -> Option<(String, String)> {
+    match ty {
+        Type::Array(array) => {
+            let len = tokens_string(&array.len);
+            let elem_width = len.parse::<usize>().ok().map_or(width, |len| width / len);
+            let (elem, dims) = c_type(&array.elem, elem_width)?;
+            Some((elem, format!("[{}]{}", len, dims)))
+        }
+        Type::Paren(paren) => c_type(&paren.elem, width),
+        Type::Path(path) => {
+            let ident = path.path.segments.last()?.ident.to_string();
+            match ident.as_str() {
+                "bool" if width == 1 => Some(("uint8_t".to_owned(), String::new())),
+                // bool 可占用多个字节，C 没有可移植的 128 位整数
+                "bool" | "u128" | "i128" => Some(("uint8_t".to_owned(), format!("[{}]", width))),
+                _ => Some((
+                    c_primitive(&ident).map_or(ident.to_owned(), |x| x.to_owned()),
+                    String::new(),
+                )),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn guard_begin(name: &str, includes: &[&str]) -> String {
+    let guard = format!("BINARY_PROC_{}_H", snake_case(name).to_uppercase());
+    let mut header = format!("#ifndef {}\n#define {}\n\n", guard, guard);
+    for include in includes {
+        header.push_str(&format!("#include <{}>\n", include));
+    }
+    header.push('\n');
+    header
+}
+
+fn guard_end(name: &str) -> String {
+    format!(
+        "#endif /* BINARY_PROC_{}_H */\n",
+        snake_case(name).to_uppercase()
+    )
+}
+
+fn header_impl(clean: &DeriveInput, header: String) -> TokenStream {
+    let ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// C 头文件片段
+            pub const C_HEADER: &'static str = #header;
+        }
+    }
+}
+
+/// C 中的容器类型，不支持 128 位整数
+fn container_c(ty: &TypePath) -> Result<&'static str> {
+    match c_primitive(&tokens_string(ty)) {
+        Some(c) if !matches!(c, "float" | "double") => Ok(c),
+        _ => Err(Error::new_spanned(
+            ty,
+            "c_header requires an integer container of at most 64 bits",
+        )),
+    }
+}
+
+pub(crate) fn bytemap_header(bytemap: &BytemapStruct) -> Result<TokenStream> {
+    let clean = &bytemap.clean_struct;
+    if let Some(param) = clean.generics.type_params().next() {
+        return Err(Error::new_spanned(
+            param,
+            "c_header does not support type parameters",
+        ));
+    }
+    let name = clean.ident.to_string();
+
+    let mut fields = Vec::new();
+    for field in bytemap.fields.iter() {
+        if let Some(condition) = &field.condition {
+            return Err(Error::new_spanned(
+                condition,
+                "c_header does not support conditional fields",
+            ));
+        }
+        let range = field.pos_value.to_owned().ok_or(Error::new_spanned(
+            &field.pos,
+            "c_header requires a literal position",
+        ))?;
+        let width = range.end() + 1 - range.start();
+        let (elem, dims) = match &field.encoding {
+            FieldEncoding::Native => c_type(&field.target_type, width).ok_or(
+                Error::new_spanned(&field.target_type, "unsupported type for c_header"),
+            )?,
+            FieldEncoding::Borrowed { is_str: true, .. } => {
+                ("char".to_owned(), format!("[{}]", width))
+            }
+            FieldEncoding::Borrowed { .. } => ("uint8_t".to_owned(), format!("[{}]", width)),
+            FieldEncoding::Utf16(_) => ("uint16_t".to_owned(), format!("[{}]", width / 2)),
+            FieldEncoding::Varint(_) => {
+                return Err(Error::new_spanned(
+                    &field.target_type,
+                    "c_header does not support varint fields",
+                ))
+            }
+            FieldEncoding::Prefixed(_) => {
+                return Err(Error::new_spanned(
+                    &field.target_type,
+                    "c_header does not support prefix fields",
+                ))
+            }
+        };
+        fields.push((field, range, elem, dims));
+    }
+    fields.sort_by_key(|(_, range, _, _)| *range.start());
+
+    let mut header = format!(
+        "/* {}: multi-byte fields are stored in wire byte order */\n",
+        name
+    );
+    header.push_str(&guard_begin(&name, &["stddef.h", "stdint.h"]));
+    header.push_str("#pragma pack(push, 1)\ntypedef struct {\n");
+    let mut asserts = String::new();
+    let mut offset = 0usize;
+    for (field, range, elem, dims) in fields.iter() {
+        let (start, end) = (*range.start(), *range.end());
+        // pos 之间的空隙显式填充
+        if start > offset {
+            header.push_str(&format!(
+                "    uint8_t reserved_{}[{}];\n",
+                offset,
+                start - offset
+            ));
+        }
+        header.push_str(&format!("    {} {}{};\n", elem, field.ident, dims));
+        asserts.push_str(&format!(
+            "_Static_assert(offsetof({}, {}) == {}, \"{}.{} offset\");\n",
+            name, field.ident, start, name, field.ident
+        ));
+        asserts.push_str(&format!(
+            "_Static_assert(sizeof((({} *)0)->{}) == {}, \"{}.{} size\");\n",
+            name,
+            field.ident,
+            end + 1 - start,
+            name,
+            field.ident
+        ));
+        offset = offset.max(end + 1);
+    }
+    // 末尾的 reserved 字节
+    let reserved_len = bytemap.reserved_len();
+    if reserved_len > offset {
+        header.push_str(&format!(
+            "    uint8_t reserved_{}[{}];\n",
+            offset,
+            reserved_len - offset
+        ));
+        offset = reserved_len;
+    }
+    header.push_str(&format!("}} {};\n#pragma pack(pop)\n\n", name));
+    header.push_str(&format!(
+        "_Static_assert(sizeof({}) == {}, \"{} size\");\n",
+        name, offset, name
+    ));
+    header.push_str(&asserts);
+    header.push('\n');
+    header.push_str(&guard_end(&name));
+    Ok(header_impl(clean, header))
+}
+
+pub(crate) fn bitmap_header(bitmap: &BitmapStruct, types: &[TypePath]) -> Result<TokenStream> {
+    let clean = &bitmap.clean_struct;
+    let name = clean.ident.to_string();
+    let prefix = snake_case(&name).to_uppercase();
+    let container = container_c(&types[0])?;
+
+    let mut header = guard_begin(&name, &["stdint.h"]);
+    header.push_str(&format!("typedef {} {};\n\n", container, name));
+    for field in bitmap.fields.iter() {
+        let range = range_from_expr(&field.pos)?;
+        let (start, end) = (*range.start(), *range.end());
+        let mask = 1u64
+            .checked_shl((end + 1 - start) as u32)
+            .map_or(u64::MAX, |x| x - 1);
+        let field_prefix = format!("{}_{}", prefix, field.ident.to_string().to_uppercase());
+        header.push_str(&format!(
+            "#define {p}_SHIFT {shift}\n\
+             #define {p}_MASK (({c}){mask:#x})\n\
+             #define {p}_GET(raw) ((({c})(raw) >> {p}_SHIFT) & {p}_MASK)\n\
+             #define {p}_SET(raw, value) (({c})((({c})(raw) & ~({p}_MASK << {p}_SHIFT)) | ((({c})(value) & {p}_MASK) << {p}_SHIFT)))\n\n",
+            p = field_prefix,
+            shift = start,
+            c = container,
+            mask = mask,
+        ));
+    }
+    header.push_str(&guard_end(&name));
+    Ok(header_impl(clean, header))
+}
+
+pub(crate) fn restrict_header(
+    clean: &DeriveInput,
+    variants: &[RestrictVariant],
+    types: &[TypePath],
+) -> Result<TokenStream> {
+    let name = clean.ident.to_string();
+    let snake = snake_case(&name);
+    let prefix = snake.to_uppercase();
+    let container = container_c(&types[0])?;
+
+    let mut header = guard_begin(&name, &["stdbool.h", "stdint.h"]);
+    header.push_str(&format!("typedef {} {};\n\n", container, name));
+    header.push_str(&format!("enum {}Variant {{\n", name));
+    for variant in variants.iter() {
+        header.push_str(&format!(
+            "    {}_{},\n",
+            prefix,
+            snake_case(&variant.ident.to_string()).to_uppercase()
+        ));
+    }
+    header.push_str("};\n\n");
+    for variant in variants.iter() {
+        let checks = variant
+            .restrict
+            .white_list
+            .iter()
+            .map(|x| {
+                let (lo, hi) = white_list_bounds(x);
+                // 无符号容器的 `value >= 0` 恒成立，会触发 -Wtype-limits
+                match lo {
+                    Some(lo)
+                        if lo == "0"
+                            && hi.as_deref() != Some("0")
+                            && container.starts_with('u')
+                            && hi.is_some() =>
+                    {
+                        (None, hi)
+                    }
+                    lo => (lo, hi),
+                }
+            })
+            .map(|bounds| match bounds {
+                (Some(lo), Some(hi)) if lo == hi => format!("value == {}", lo),
+                (Some(lo), Some(hi)) => format!("(value >= {} && value <= {})", lo, hi),
+                (Some(lo), None) => format!("value >= {}", lo),
+                (None, Some(hi)) => format!("value <= {}", hi),
+                (None, None) => "true".to_owned(),
+            })
+            .collect::<Vec<_>>();
+        header.push_str(&format!(
+            "static inline bool {}_is_{}({} value) {{\n    return {};\n}}\n\n",
+            snake,
+            snake_case(&variant.ident.to_string()),
+            container,
+            checks.join(" || ")
+        ));
+    }
+    header.push_str(&format!(
+        "/* enum {}Variant of value, or -1 if no white_list matches */\n",
+        name
+    ));
+    header.push_str(&format!(
+        "static inline int {}_variant({} value) {{\n",
+        snake, container
+    ));
+    for variant in variants.iter() {
+        let variant = snake_case(&variant.ident.to_string());
+        header.push_str(&format!(
+            "    if ({}_is_{}(value)) return {}_{};\n",
+            snake,
+            variant,
+            prefix,
+            variant.to_uppercase()
+        ));
+    }
+    header.push_str("    return -1;\n}\n\n");
+    header.push_str(&guard_end(&name));
+    Ok(header_impl(clean, header))
+}
@@ -0,0 +1,456 @@
+//! `bytemap_from_c!`：由 C 结构体声明生成等价的 `#[bytemap]` 结构体。
+//!
+//! ```ignore
+//! bytemap_from_c! {
+//!     #[derive(Debug)]
+//!     pub struct vendor_header {
+//!         uint16_t kind;
+//!         uint32_t len;      /* 按自然对齐位于 4..=7，2..=3 为 reserved */
+//!         uint8_t mac[6];
+//!         struct {
+//!             uint8_t major, minor;
+//!         } version;
+//!     };
+//! }
+//! // 生成 VendorHeader 与 VendorHeaderVersion
+//! ```
+//!
+//! 支持定宽整数、`char`、`float`、`double`、`bool`、多维数组、同一宏内先前声明的结构体、
+//! 内联的匿名结构体、`typedef struct` 以及 `__attribute__((packed))`。
+//! 未指定 packed 时按各类型的自然对齐计算偏移，对齐产生的空隙（包括末尾）标记为 reserved。
+//! 结构体上的 `#[bytemap(...)]` 参数会被保留，`crate = path;` 可置于最前以指定运行时路径。
+
+use std::collections::HashMap;
+
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    braced, bracketed, ext::IdentExt, parenthesized, parse::Parse, parse::ParseStream, Attribute,
+    Error, LitInt, Path, Result, Token, Visibility,
+};
+
+use crate::crate_path::{default_crate, parse_crate_arg};
+
+/// C 字段类型
+#[derive(Clone)]
+enum CType {
+    /// 对应的 Rust 类型与大小（同时也是对齐）
+    Primitive(&'static str, usize),
+    /// `struct tag` 或 typedef 名称，引用同一宏内先前声明的结构体
+    Named(Ident),
+    /// 内联的匿名结构体
+    Inline(Box<CStruct>),
+}
+
+#[derive(Clone)]
+struct CField {
+    ident: Ident,
+    ty: CType,
+    /// `a[2][3]` => `[2, 3]`
+    dims: Vec<usize>,
+}
+
+#[derive(Clone)]
+struct CStruct {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    /// 可被其他字段引用的名称：struct tag 与 typedef 名称
+    names: Vec<String>,
+    /// 生成的 Rust 结构体名称
+    ident: Ident,
+    fields: Vec<CField>,
+    packed: bool,
+}
+
+/// 已完成布局的结构体：Rust 名称、大小、对齐
+#[derive(Clone)]
+struct Layout {
+    ident: Ident,
+    size: usize,
+    align: usize,
+}
+
+/// `vendor_header_t` => `VendorHeader`
+fn camel_case(name: &str) -> String {
+    let name = name.strip_suffix("_t").unwrap_or(name);
+    name.split('_')
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let mut chars = x.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// C 中合法而 Rust 中为关键字的字段名，如 `type`
+fn field_ident(ident: &Ident) -> Ident {
+    let name = ident.to_string();
+    match name.as_str() {
+        "self" | "Self" | "super" | "crate" => format_ident!("{}_", name),
+        _ if syn::parse_str::<Ident>(&name).is_err() => Ident::new_raw(&name, ident.span()),
+        _ => ident.to_owned(),
+    }
+}
+
+fn primitive(name: &str) -> Option<(&'static str, usize)> {
+    Some(match name {
+        "uint8_t" | "char" | "unsigned char" => ("u8", 1),
+        "int8_t" | "signed char" => ("i8", 1),
+        "uint16_t" => ("u16", 2),
+        "int16_t" => ("i16", 2),
+        "uint32_t" => ("u32", 4),
+        "int32_t" => ("i32", 4),
+        "uint64_t" => ("u64", 8),
+        "int64_t" => ("i64", 8),
+        "float" => ("f32", 4),
+        "double" => ("f64", 8),
+        "bool" | "_Bool" => ("bool", 1),
+        _ => return None,
+    })
+}
+
+/// `__attribute__((packed))`，返回是否出现
+fn parse_attribute(input: ParseStream) -> Result<bool> {
+    let mut packed = false;
+    while input.peek(Ident::peek_any) && input.fork().call(Ident::parse_any)? == "__attribute__" {
+        input.call(Ident::parse_any)?;
+        let outer;
+        parenthesized!(outer in input);
+        let inner;
+        parenthesized!(inner in outer);
+        while !inner.is_empty() {
+            let ident = inner.call(Ident::parse_any)?;
+            if ident != "packed" {
+                return Err(Error::new_spanned(
+                    &ident,
+                    "only __attribute__((packed)) is supported",
+                ));
+            }
+            packed = true;
+            if !inner.is_empty() {
+                inner.parse::<Token![,]>()?;
+            }
+        }
+    }
+    Ok(packed)
+}
+
+/// `{ ... }` 中的字段，内联结构体沿用外层的 `attrs` 与 `vis`
+fn parse_fields(input: ParseStream, attrs: &[Attribute], vis: &Visibility) -> Result<Vec<CField>> {
+    let content;
+    braced!(content in input);
+    let mut fields = Vec::new();
+    while !content.is_empty() {
+        if content.peek(Token![const]) {
+            content.parse::<Token![const]>()?;
+        }
+        let first = content.call(Ident::parse_any)?;
+        let ty = match first.to_string().as_str() {
+            "struct" => {
+                let packed = parse_attribute(&content)?;
+                let tag = match content.peek(Ident::peek_any) {
+                    true => Some(content.call(Ident::parse_any)?),
+                    false => None,
+                };
+                match (tag, content.peek(syn::token::Brace)) {
+                    (tag, true) => {
+                        // 名称在展开时由外层名称与字段名确定
+                        let inner = parse_fields(&content, attrs, vis)?;
+                        let packed = parse_attribute(&content)? || packed;
+                        CType::Inline(Box::new(CStruct {
+                            attrs: attrs.to_owned(),
+                            vis: vis.to_owned(),
+                            names: tag.iter().map(|x| x.to_string()).collect(),
+                            ident: format_ident!("Anonymous"),
+                            fields: inner,
+                            packed,
+                        }))
+                    }
+                    (Some(tag), false) => CType::Named(tag),
+                    (None, false) => {
+                        return Err(Error::new(content.span(), "expected struct name or body"))
+                    }
+                }
+            }
+            "union" | "enum" => {
+                return Err(Error::new_spanned(
+                    &first,
+                    format!("`{}` is not supported", first),
+                ))
+            }
+            "unsigned" | "signed" if content.peek(Ident::peek_any) => {
+                let second = content.call(Ident::parse_any)?;
+                let name = format!("{} {}", first, second);
+                let (rust, size) = primitive(&name).ok_or(Error::new_spanned(
+                    &second,
+                    format!("unsupported type `{}`, use a fixed-width type", name),
+                ))?;
+                CType::Primitive(rust, size)
+            }
+            "int" | "short" | "long" | "unsigned" | "signed" => {
+                return Err(Error::new_spanned(
+                    &first,
+                    format!("unsupported type `{}`, use a fixed-width type", first),
+                ))
+            }
+            name => match primitive(name) {
+                Some((rust, size)) => CType::Primitive(rust, size),
+                None => CType::Named(first.to_owned()),
+            },
+        };
+        let mut declarators = 0;
+        loop {
+            if content.peek(Token![*]) {
+                return Err(Error::new(content.span(), "pointers are not supported"));
+            }
+            let ident = content.call(Ident::parse_any)?;
+            let mut dims = Vec::new();
+            while content.peek(syn::token::Bracket) {
+                let len;
+                bracketed!(len in content);
+                if len.is_empty() {
+                    return Err(Error::new_spanned(
+                        &ident,
+                        "flexible array members are not supported",
+                    ));
+                }
+                dims.push(len.parse::<LitInt>()?.base10_parse::<usize>()?);
+            }
+            if content.peek(Token![:]) {
+                return Err(Error::new(
+                    content.span(),
+                    "bit-fields are not supported, use #[bitmap] for the containing integer",
+                ));
+            }
+            // 同一声明中的多个字段共用类型，内联结构体只能有一个字段
+            if matches!(ty, CType::Inline(_)) && declarators > 0 {
+                return Err(Error::new_spanned(
+                    &ident,
+                    "declare one field per inline struct",
+                ));
+            }
+            declarators += 1;
+            fields.push(CField {
+                ident,
+                ty: ty.to_owned(),
+                dims,
+            });
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            } else {
+                break;
+            }
+        }
+        content.parse::<Token![;]>()?;
+    }
+    Ok(fields)
+}
+
+impl Parse for CStruct {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse::<Visibility>()?;
+        let keyword = input.call(Ident::parse_any)?;
+        let typedef = keyword == "typedef";
+        if typedef {
+            let keyword = input.call(Ident::parse_any)?;
+            if keyword != "struct" {
+                return Err(Error::new_spanned(keyword, "expected `struct`"));
+            }
+        } else if keyword != "struct" {
+            return Err(Error::new_spanned(
+                keyword,
+                "expected `struct` or `typedef`",
+            ));
+        }
+        let mut packed = parse_attribute(input)?;
+        let tag = match input.peek(Ident::peek_any) {
+            true => Some(input.call(Ident::parse_any)?),
+            false => None,
+        };
+        let inherited = attrs
+            .iter()
+            .filter(|x| x.path.is_ident("derive") || x.path.is_ident("bytemap"))
+            .cloned()
+            .collect::<Vec<_>>();
+        let fields = parse_fields(input, &inherited, &vis)?;
+        packed |= parse_attribute(input)?;
+        let mut names = tag.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        // typedef 名称优先用作 Rust 结构体名称
+        let name = match typedef {
+            true => input.call(Ident::parse_any)?,
+            false => tag.ok_or(Error::new(input.span(), "expected struct name"))?,
+        };
+        if typedef {
+            names.push(name.to_string());
+        }
+        let ident = format_ident!("{}", camel_case(&name.to_string()), span = name.span());
+        input.parse::<Token![;]>()?;
+        if fields.is_empty() {
+            return Err(Error::new_spanned(&name, "struct has no fields"));
+        }
+        Ok(CStruct {
+            attrs,
+            vis,
+            names,
+            ident,
+            fields,
+            packed,
+        })
+    }
+}
+
+/// 宏的全部输入
+pub(crate) struct CDeclarations {
+    krate: Path,
+    structs: Vec<CStruct>,
+}
+
+impl Parse for CDeclarations {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut krate = default_crate();
+        if input.peek(Token![crate]) {
+            krate = parse_crate_arg(input)?;
+            input.parse::<Token![;]>()?;
+        }
+        let mut structs = Vec::new();
+        while !input.is_empty() {
+            structs.push(input.parse::<CStruct>()?);
+        }
+        Ok(CDeclarations { krate, structs })
+    }
+}
+
+impl CDeclarations {
+    pub(crate) fn expand(self) -> Result<TokenStream> {
+        let mut layouts = HashMap::<String, Layout>::new();
+        let mut tokens = TokenStream::new();
+        for c_struct in self.structs.iter() {
+            c_struct.expand(&self.krate, &mut layouts, &mut tokens)?;
+        }
+        Ok(tokens)
+    }
+}
+
+impl CStruct {
+    /// 计算布局并生成结构体，内联结构体先于外层结构体生成
+    fn expand(
+        &self,
+        krate: &Path,
+        layouts: &mut HashMap<String, Layout>,
+        tokens: &mut TokenStream,
+    ) -> Result<Layout> {
+        let mut offset = 0usize;
+        let mut max_align = 1usize;
+        let mut fields = TokenStream::new();
+        let mut reserved = Vec::new();
+        let vis = &self.vis;
+        for field in self.fields.iter() {
+            let (mut ty, size, align) = match &field.ty {
+                CType::Primitive(rust, size) => {
+                    let rust = format_ident!("{}", rust);
+                    (quote!(#rust), *size, *size)
+                }
+                CType::Named(name) => {
+                    let layout = layouts.get(&name.to_string()).ok_or(Error::new_spanned(
+                        name,
+                        format!(
+                            "unknown type `{}`, declare it earlier in the same bytemap_from_c! block",
+                            name
+                        ),
+                    ))?;
+                    let ident = &layout.ident;
+                    (quote!(#ident), layout.size, layout.align)
+                }
+                CType::Inline(inline) => {
+                    // 有 tag 时以 tag 命名，否则以外层名称与字段名命名
+                    let mut inline = (**inline).to_owned();
+                    inline.ident = match inline.names.first() {
+                        Some(tag) => format_ident!("{}", camel_case(tag)),
+                        None => {
+                            format_ident!("{}{}", self.ident, camel_case(&field.ident.to_string()))
+                        }
+                    };
+                    let layout = inline.expand(krate, layouts, tokens)?;
+                    let ident = &layout.ident;
+                    (quote!(#ident), layout.size, layout.align)
+                }
+            };
+            let mut size = size;
+            for dim in field.dims.iter().rev() {
+                let dim = Literal::usize_unsuffixed(*dim);
+                ty = quote!([#ty; #dim]);
+            }
+            size *= field.dims.iter().product::<usize>();
+            let align = if self.packed { 1 } else { align };
+            max_align = max_align.max(align);
+            let start = offset.next_multiple_of(align);
+            if start > offset {
+                reserved.push((offset, start - 1));
+            }
+            if size == 0 {
+                return Err(Error::new_spanned(&field.ident, "zero sized field"));
+            }
+            let (start_lit, end_lit) = (
+                Literal::usize_unsuffixed(start),
+                Literal::usize_unsuffixed(start + size - 1),
+            );
+            let ident = field_ident(&field.ident);
+            fields.extend(quote! {
+                #[pos(#start_lit..=#end_lit)]
+                #vis #ident: #ty,
+            });
+            offset = start + size;
+        }
+        // C 的 sizeof 包含末尾的对齐填充
+        let size = offset.next_multiple_of(max_align);
+        if size > offset {
+            reserved.push((offset, size - 1));
+        }
+
+        let mut args = TokenStream::new();
+        let mut attrs = TokenStream::new();
+        for attr in self.attrs.iter() {
+            if attr.path.is_ident("bytemap") {
+                let inner = attr.parse_args::<TokenStream>()?;
+                args.extend(quote!(, #inner));
+            } else {
+                attrs.extend(quote!(#attr));
+            }
+        }
+        let reserved = match reserved.is_empty() {
+            true => quote!(),
+            false => {
+                let ranges = reserved.iter().map(|(start, end)| {
+                    let (start, end) = (
+                        Literal::usize_unsuffixed(*start),
+                        Literal::usize_unsuffixed(*end),
+                    );
+                    quote!(#start..=#end)
+                });
+                quote!(#[reserved(#(#ranges),*)])
+            }
+        };
+        let ident = &self.ident;
+        tokens.extend(quote! {
+            #attrs
+            #[#krate::bytemap(crate = #krate #args)]
+            #reserved
+            #vis struct #ident {
+                #fields
+            }
+        });
+        let layout = Layout {
+            ident: ident.to_owned(),
+            size,
+            align: max_align,
+        };
+        for name in self.names.iter() {
+            layouts.insert(name.to_owned(), layout.to_owned());
+        }
+        Ok(layout)
+    }
+}
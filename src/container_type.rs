@@ -1,14 +1,71 @@
-use syn::{parse::Parse, punctuated::Punctuated, Token, TypePath};
+use syn::{parse::Parse, Error, Ident, Path, Token, TypePath};
+
+use crate::crate_path::{default_crate, parse_crate_arg};
+use crate::tlv::TlvArgs;
 
 pub(crate) struct ContainerType {
     pub(crate) types: Vec<TypePath>,
+    /// 运行时 crate 的路径
+    pub(crate) krate: Path,
+    /// 导出 Kaitai Struct 与 JSON 描述
+    pub(crate) schema: bool,
+    /// 生成 C 头文件片段
+    pub(crate) c_header: bool,
+    /// 实现 `wireshark::Dissect`
+    pub(crate) dissector: bool,
+    /// 实现 `mmio::Register`，作为 `#[bytemap(mmio)]` 的寄存器字段
+    pub(crate) mmio: bool,
+    /// 作为 TLV 序列中的项解码，仅用于 restrict
+    pub(crate) tlv: Option<TlvArgs>,
 }
 
 impl Parse for ContainerType {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let puncated_types = Punctuated::<TypePath, Token!(,)>::parse_separated_nonempty(input)?;
         let mut types = Vec::new();
-        puncated_types.into_iter().for_each(|x| types.push(x));
-        Ok(ContainerType { types })
+        let mut krate = default_crate();
+        let mut schema = false;
+        let mut c_header = false;
+        let mut dissector = false;
+        let mut mmio = false;
+        let mut tlv = None;
+        while !input.is_empty() {
+            if input.peek(Token![crate]) {
+                krate = parse_crate_arg(input)?;
+            } else if input.fork().parse::<Ident>().is_ok_and(|x| x == "tlv") {
+                tlv = Some(input.parse::<TlvArgs>()?);
+            } else if let Some(flag) = input.fork().parse::<Ident>().ok().filter(|x| {
+                ["schema", "c_header", "dissector", "mmio"]
+                    .iter()
+                    .any(|flag| x == flag)
+            }) {
+                input.parse::<Ident>()?;
+                match flag.to_string().as_str() {
+                    "schema" => schema = true,
+                    "c_header" => c_header = true,
+                    "mmio" => mmio = true,
+                    _ => dissector = true,
+                }
+            } else {
+                types.push(input.parse::<TypePath>()?);
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if types.is_empty() {
+            return Err(Error::new(
+                input.span(),
+                "at least one container type is required",
+            ));
+        }
+        Ok(ContainerType {
+            types,
+            krate,
+            schema,
+            c_header,
+            dissector,
+            mmio,
+            tlv,
+        })
     }
 }
@@ -0,0 +1,22 @@
+//! `crate = path` 参数，生成代码通过该路径引用运行时，默认为 `::binary_proc_rt`。
+//!
+//! ```ignore
+//! #[bytemap(packed, crate = facade::rt)]
+//! struct A { ... }
+//!
+//! #[bitmap(u8, crate = facade::rt)]
+//! struct B { ... }
+//! ```
+
+use syn::{parse::ParseStream, parse_quote, Path, Result, Token};
+
+pub(crate) fn default_crate() -> Path {
+    parse_quote!(::binary_proc_rt)
+}
+
+/// 解析 `crate = path`
+pub(crate) fn parse_crate_arg(input: ParseStream) -> Result<Path> {
+    input.parse::<Token![crate]>()?;
+    input.parse::<Token![=]>()?;
+    Path::parse_mod_style(input)
+}
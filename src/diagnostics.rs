@@ -0,0 +1,105 @@
+//! 布局诊断：未标记为 reserved 的空隙（警告）、拼写错误的辅助属性（错误）。
+//!
+//! ```ignore
+//! #[bytemap]
+//! #[reserved(2..=3)]
+//! struct A {
+//!     #[pos(0..=1)]
+//!     a: u16,
+//!     // 2..=3 未被字段覆盖，但已标记为 reserved，不产生警告
+//!     #[pos(4..=7)]
+//!     b: u32,
+//! }
+//! ```
+
+use std::ops::RangeInclusive;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote_spanned, ToTokens};
+use syn::{Attribute, Error, Result};
+
+/// 编译器内置、不会被误认为辅助属性的属性
+const BUILTIN_ATTRS: [&str; 10] = [
+    "doc",
+    "cfg",
+    "cfg_attr",
+    "allow",
+    "warn",
+    "deny",
+    "forbid",
+    "expect",
+    "deprecated",
+    "must_use",
+];
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// 与已知辅助属性仅差一两个字符的属性视为拼写错误，如 `#[poss(..)]`
+pub(crate) fn check_attr_typos(attrs: &[Attribute], known: &[&str]) -> Result<()> {
+    for attr in attrs {
+        let name = match attr.path.get_ident() {
+            Some(ident) => ident.to_string(),
+            None => continue,
+        };
+        if known.contains(&name.as_str()) || BUILTIN_ATTRS.contains(&name.as_str()) {
+            continue;
+        }
+        let suggestion = known.iter().find(|x| {
+            let limit = if x.len() >= 5 { 2 } else { 1 };
+            edit_distance(&name, x) <= limit
+        });
+        if let Some(suggestion) = suggestion {
+            return Err(Error::new_spanned(
+                attr,
+                format!(
+                    "unknown attribute `{}`, did you mean `{}`?",
+                    name, suggestion
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `0..=limit` 中未被 `covered` 覆盖的范围
+pub(crate) fn gaps(mut covered: Vec<RangeInclusive<usize>>) -> Vec<RangeInclusive<usize>> {
+    covered.sort_by_key(|x| *x.start());
+    let mut gaps = Vec::new();
+    let mut next = 0usize;
+    for range in covered {
+        if *range.start() > next {
+            gaps.push(next..=*range.start() - 1);
+        }
+        next = next.max(*range.end() + 1);
+    }
+    gaps
+}
+
+/// 稳定版的过程宏无法直接产生警告，借助使用 deprecated 常量产生
+pub(crate) fn warning(target: &impl ToTokens, message: &str) -> TokenStream {
+    let span = target
+        .to_token_stream()
+        .into_iter()
+        .next()
+        .map_or(Span::call_site(), |x| x.span());
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const layout_warning: () = ();
+            layout_warning
+        };
+    }
+}
@@ -0,0 +1,208 @@
+//! `dissector` 参数：实现 `wireshark::Dissect`，供运行时生成 Lua dissector。
+//!
+//! ```ignore
+//! #[bytemap(dissector)]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//! }
+//! let lua = lua_dissector::<Header>("demo", "Demo Protocol", ByteOrder::Little);
+//! ```
+//!
+//! 嵌套字段的布局由其类型自身的 `Dissect` 实现描述，因此嵌套的 bitmap、restrict
+//! 或 bytemap 同样需要 `dissector` 参数。
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, Expr, ExprRange, RangeLimits, Result, Type, TypePath};
+
+use crate::bitmap_struct::BitmapStruct;
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::FieldEncoding;
+use crate::literal_pos::range_from_expr;
+use crate::restrict_enum::RestrictVariant;
+use crate::type_size::int_signedness;
+
+/// 整数类型的位数
+fn int_bits(ty: &Type) -> Option<u32> {
+    let ident = match ty {
+        Type::Path(path) => path.path.get_ident()?.to_string(),
+        _ => return None,
+    };
+    match ident.as_str() {
+        "u8" | "i8" => Some(8),
+        "u16" | "i16" => Some(16),
+        "u32" | "i32" => Some(32),
+        "u64" | "i64" => Some(64),
+        _ => None,
+    }
+}
+
+/// 容器对应的 `FieldKind` 与字节数，不支持 128 位整数
+fn container_kind(ty: &TypePath, krate: &syn::Path) -> Result<(TokenStream, usize)> {
+    let ty = Type::Path(ty.to_owned());
+    let bits = int_bits(&ty).ok_or(Error::new_spanned(
+        &ty,
+        "dissector requires an integer container of at most 64 bits",
+    ))?;
+    let kind = match int_signedness(&ty) {
+        Some(true) => quote!(#krate::wireshark::FieldKind::Int(#bits)),
+        _ => quote!(#krate::wireshark::FieldKind::Uint(#bits)),
+    };
+    Ok((kind, bits as usize / 8))
+}
+
+fn dissect_impl(clean: &DeriveInput, krate: &syn::Path, body: TokenStream) -> TokenStream {
+    let ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #krate::wireshark::Dissect for #ident #ty_generics #where_clause {
+            fn dissect(__name: &str, __offset: usize) -> #krate::wireshark::Field {
+                #body
+            }
+        }
+    }
+}
+
+pub(crate) fn bytemap_dissector(bytemap: &BytemapStruct) -> Result<TokenStream> {
+    let clean = &bytemap.clean_struct;
+    let krate = &bytemap.krate;
+    if let Some(param) = clean.generics.type_params().next() {
+        return Err(Error::new_spanned(
+            param,
+            "dissector does not support type parameters",
+        ));
+    }
+    let wireshark = quote!(#krate::wireshark);
+    let mut children = Vec::new();
+    let mut len = bytemap.reserved_len();
+    for field in bytemap.fields.iter() {
+        let range = field.pos_value.to_owned().ok_or(Error::new_spanned(
+            &field.pos,
+            "dissector requires a literal position",
+        ))?;
+        let (start, width) = (*range.start(), range.end() + 1 - range.start());
+        len = len.max(range.end() + 1);
+        let name = field.ident.to_string();
+        let ty = &field.target_type;
+        let kind = match &field.encoding {
+            FieldEncoding::Borrowed { is_str: true, .. } => Some(quote!(String)),
+            FieldEncoding::Borrowed { .. } => Some(quote!(Bytes)),
+            FieldEncoding::Utf16(_) => Some(quote!(Utf16)),
+            FieldEncoding::Varint(_) | FieldEncoding::Prefixed(_) => Some(quote!(Bytes)),
+            FieldEncoding::Native => match ty {
+                Type::Array(_) => Some(quote!(Bytes)),
+                _ => match (int_bits(ty), int_signedness(ty)) {
+                    (Some(bits), Some(true)) => Some(quote!(Int(#bits))),
+                    (Some(bits), _) => Some(quote!(Uint(#bits))),
+                    // 128 位整数按字节显示
+                    (None, Some(_)) => Some(quote!(Bytes)),
+                    (None, None) => match quote!(#ty).to_string().as_str() {
+                        "bool" => Some(quote!(Bool)),
+                        "f32" => Some(quote!(Float)),
+                        "f64" => Some(quote!(Double)),
+                        _ => None,
+                    },
+                },
+            },
+        };
+        children.push(match kind {
+            Some(kind) => quote! {
+                #wireshark::Field::new(#name, #wireshark::FieldKind::#kind, __offset + #start, #width)
+            },
+            None => quote! {
+                <#ty as #wireshark::Dissect>::dissect(#name, __offset + #start)
+            },
+        });
+    }
+    let body = quote! {
+        let mut __field = #wireshark::Field::new(__name, #wireshark::FieldKind::None, __offset, #len);
+        __field.children = ::std::vec![#(#children),*];
+        __field
+    };
+    Ok(dissect_impl(clean, krate, body))
+}
+
+pub(crate) fn bitmap_dissector(
+    bitmap: &BitmapStruct,
+    types: &[TypePath],
+    krate: &syn::Path,
+) -> Result<TokenStream> {
+    let wireshark = quote!(#krate::wireshark);
+    let (container, len) = container_kind(&types[0], krate)?;
+    let bits = len as u32 * 8;
+    let mut children = Vec::new();
+    for field in bitmap.fields.iter() {
+        let range = range_from_expr(&field.pos)?;
+        let (start, end) = (*range.start(), *range.end());
+        let mask = 1u64
+            .checked_shl((end + 1 - start) as u32)
+            .map_or(u64::MAX, |x| x - 1)
+            .checked_shl(start as u32)
+            .unwrap_or(0);
+        let name = field.ident.to_string();
+        let ty = &field.target_type;
+        let kind = match int_signedness(ty) {
+            _ if quote!(#ty).to_string() == "bool" => quote!(#wireshark::FieldKind::Bool),
+            Some(true) => quote!(#wireshark::FieldKind::Int(#bits)),
+            _ => quote!(#wireshark::FieldKind::Uint(#bits)),
+        };
+        children.push(quote! {
+            #wireshark::Field {
+                mask: ::core::option::Option::Some(#mask),
+                ..#wireshark::Field::new(#name, #kind, __offset, #len)
+            }
+        });
+    }
+    let body = quote! {
+        let mut __field = #wireshark::Field::new(__name, #container, __offset, #len);
+        __field.children = ::std::vec![#(#children),*];
+        __field
+    };
+    Ok(dissect_impl(&bitmap.clean_struct, krate, body))
+}
+
+/// white_list 中的一项，返回以 i128 表示的上下界
+fn white_list_bounds(expr: &Expr, ty: &TypePath) -> (TokenStream, TokenStream) {
+    match expr {
+        Expr::Range(ExprRange {
+            from, to, limits, ..
+        }) => {
+            let lo = match from {
+                Some(from) => quote!((#from) as i128),
+                None => quote!(#ty::MIN as i128),
+            };
+            let hi = match (to, limits) {
+                (Some(to), RangeLimits::HalfOpen(_)) => quote!((#to) as i128 - 1),
+                (Some(to), RangeLimits::Closed(_)) => quote!((#to) as i128),
+                (None, _) => quote!(#ty::MAX as i128),
+            };
+            (lo, hi)
+        }
+        _ => (quote!((#expr) as i128), quote!((#expr) as i128)),
+    }
+}
+
+pub(crate) fn restrict_dissector(
+    clean: &DeriveInput,
+    variants: &[RestrictVariant],
+    types: &[TypePath],
+    krate: &syn::Path,
+) -> Result<TokenStream> {
+    let wireshark = quote!(#krate::wireshark);
+    let (container, len) = container_kind(&types[0], krate)?;
+    let mut values = Vec::new();
+    for variant in variants.iter() {
+        let name = variant.ident.to_string();
+        for expr in variant.restrict.white_list.iter() {
+            let (lo, hi) = white_list_bounds(expr, &types[0]);
+            values.push(quote!((#lo, #hi, ::std::string::String::from(#name))));
+        }
+    }
+    let body = quote! {
+        let mut __field = #wireshark::Field::new(__name, #container, __offset, #len);
+        __field.values = ::std::vec![#(#values),*];
+        __field
+    };
+    Ok(dissect_impl(clean, krate, body))
+}
@@ -0,0 +1,617 @@
+//! ```ignore
+//! #[bytemap]
+//! struct Header<'a> {
+//!     #[pos(0..=3)]
+//!     magic: &'a [u8; 4],
+//!     // C 字符串，剩余部分以 0 填充；与 strncpy 一致，恰好占满 pos 范围时没有 NUL
+//!     #[pos(4..=19)]
+//!     #[cstr]
+//!     name: &'a str,
+//!     // 以空格填充的定长 ASCII
+//!     #[pos(20..=27)]
+//!     #[padded(b' ')]
+//!     vendor: &'a str,
+//!     #[pos(28..=59)]
+//!     #[utf16(le)]
+//!     label: String,
+//!     #[pos(60..=63)]
+//!     payload: &'a [u8],
+//! }
+//! // 变长整数与长度前缀只能用于紧密排列的布局
+//! #[bytemap(packed)]
+//! struct Entry<'a> {
+//!     #[varint(uleb128)]
+//!     offset: u64,
+//!     #[varint(sleb128)]
+//!     delta: i32,
+//!     // 大端 u16 字节数，之后为 1 到 255 字节的数据
+//!     #[prefix(u16, be, min = 1, max = 255)]
+//!     name: &'a str,
+//!     // u8 元素个数，元素按结构体的字节序解码
+//!     #[prefix(u8)]
+//!     ports: Vec<u16>,
+//! }
+//! ```
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::ParseStream;
+use syn::{
+    Attribute, Error, Expr, GenericArgument, Ident, Lifetime, Path, PathArguments, Result, Token,
+    Type,
+};
+
+use crate::type_size::{int_signedness, known_size};
+
+pub(crate) const ENCODING_ATTRS: [&str; 5] = ["cstr", "padded", "utf16", "varint", "prefix"];
+
+#[derive(Clone, Copy)]
+pub(crate) enum Endian {
+    Le,
+    Be,
+}
+
+impl Endian {
+    pub(crate) fn wrapper(&self, krate: &Path) -> TokenStream {
+        match self {
+            Endian::Le => quote!(#krate::endian::Le),
+            Endian::Be => quote!(#krate::endian::Be),
+        }
+    }
+}
+
+/// 借用字段中有效数据之后的部分如何处理。
+#[derive(Clone)]
+pub(crate) enum Terminator {
+    /// 整个 pos 范围都是数据
+    Full,
+    /// 数据截止到第一个 NUL
+    Nul,
+    /// 数据末尾以该字节填充
+    Pad(Box<Expr>),
+}
+
+/// `#[varint(..)]` 的编码方式
+#[derive(Clone, Copy)]
+pub(crate) enum VarintKind {
+    Uleb128,
+    Sleb128,
+    Protobuf,
+}
+
+impl VarintKind {
+    /// 运行时实现该编码的 trait
+    pub(crate) fn trait_path(&self, krate: &Path) -> TokenStream {
+        match self {
+            VarintKind::Uleb128 => quote!(#krate::varint::Uleb128),
+            VarintKind::Sleb128 => quote!(#krate::varint::Sleb128),
+            VarintKind::Protobuf => quote!(#krate::varint::Protobuf),
+        }
+    }
+}
+
+/// 长度前缀之后的数据
+#[derive(Clone)]
+pub(crate) enum PrefixItem {
+    /// `&'a [u8]`
+    Bytes,
+    /// `&'a str`
+    Str,
+    /// UTF-8 `String`
+    String,
+    /// `Vec<T>`，前缀为元素个数
+    Vec(Box<Type>),
+}
+
+/// `#[prefix(u16, be, min = 1, max = 255)]`
+#[derive(Clone)]
+pub(crate) struct Prefix {
+    /// 前缀的整数类型
+    pub(crate) ty: Type,
+    /// `None` 表示跟随结构体的字节序
+    pub(crate) endian: Option<Endian>,
+    pub(crate) min: Option<Expr>,
+    pub(crate) max: Option<Expr>,
+    pub(crate) item: PrefixItem,
+}
+
+impl Prefix {
+    fn parse(attr: &Attribute, ty: &Type) -> Result<Self> {
+        let parser = |input: ParseStream| {
+            let prefix_ty = input.parse::<Type>()?;
+            if !matches!(
+                prefix_ty.to_token_stream().to_string().as_str(),
+                "u8" | "u16" | "u32" | "u64"
+            ) {
+                return Err(Error::new_spanned(
+                    prefix_ty,
+                    "prefix must be `u8`, `u16`, `u32` or `u64`",
+                ));
+            }
+            let (mut endian, mut min, mut max) = (None, None, None);
+            while !input.is_empty() {
+                input.parse::<Token![,]>()?;
+                let ident = input.parse::<Ident>()?;
+                match ident.to_string().as_str() {
+                    "le" => endian = Some(Endian::Le),
+                    "be" => endian = Some(Endian::Be),
+                    "min" => {
+                        input.parse::<Token![=]>()?;
+                        min = Some(input.parse::<Expr>()?);
+                    }
+                    "max" => {
+                        input.parse::<Token![=]>()?;
+                        max = Some(input.parse::<Expr>()?);
+                    }
+                    _ => {
+                        return Err(Error::new_spanned(
+                            ident,
+                            "expected `le`, `be`, `min = ..` or `max = ..`",
+                        ))
+                    }
+                }
+            }
+            Ok((prefix_ty, endian, min, max))
+        };
+        let (prefix_ty, endian, min, max) = attr.parse_args_with(parser)?;
+        let item = match ty {
+            Type::Reference(reference) if reference.lifetime.is_some() => {
+                match reference.elem.as_ref() {
+                    Type::Path(path) if path.path.is_ident("str") => PrefixItem::Str,
+                    Type::Slice(slice) if is_u8(&slice.elem) => PrefixItem::Bytes,
+                    _ => return Err(Error::new_spanned(ty, PREFIX_TYPES)),
+                }
+            }
+            Type::Path(path) if path.path.is_ident("String") => PrefixItem::String,
+            _ => PrefixItem::Vec(Box::new(
+                vec_inner(ty)
+                    .ok_or(Error::new_spanned(ty, PREFIX_TYPES))?
+                    .to_owned(),
+            )),
+        };
+        Ok(Prefix {
+            ty: prefix_ty,
+            endian,
+            min,
+            max,
+            item,
+        })
+    }
+
+    /// 前缀的字节数
+    pub(crate) fn size(&self) -> usize {
+        known_size(&self.ty).unwrap_or(0)
+    }
+
+    /// 每个元素的字节数
+    pub(crate) fn item_size(&self, krate: &Path) -> TokenStream {
+        match &self.item {
+            PrefixItem::Vec(elem) => quote!(<#elem as #krate::BinarySize>::SIZE),
+            _ => quote!(1usize),
+        }
+    }
+
+    /// `count` 超出前缀类型或 min、max 时为 true
+    pub(crate) fn out_of_bounds(&self, count: TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        let mut checks = vec![quote!((#count) as u128 > #ty::MAX as u128)];
+        if let Some(min) = &self.min {
+            checks.push(quote!((#count) < (#min) as usize));
+        }
+        if let Some(max) = &self.max {
+            checks.push(quote!((#count) > (#max) as usize));
+        }
+        quote!(#(#checks)||*)
+    }
+}
+
+const PREFIX_TYPES: &str = "prefix field must be `&[u8]`, `&str`, `String` or `Vec<T>`";
+
+#[derive(Clone)]
+pub(crate) enum FieldEncoding {
+    /// 通过 `TryFrom<Le<&[u8]>>` 与 `IntoLeIter`（或 Be 版本）编解码
+    Native,
+    /// `&'a [u8]`、`&'a [u8; N]` 与 `&'a str`，直接借用输入切片
+    Borrowed {
+        lifetime: Lifetime,
+        is_str: bool,
+        is_array: bool,
+        terminator: Terminator,
+    },
+    /// `String`，`None` 表示跟随结构体的字节序
+    Utf16(Option<Endian>),
+    /// 变长整数，长度由数据决定，与字节序无关
+    Varint(VarintKind),
+    /// 长度前缀及其后的数据
+    Prefixed(Box<Prefix>),
+}
+
+impl FieldEncoding {
+    /// 长度由数据决定，只能用于紧密排列的布局
+    pub(crate) fn is_dynamic(&self) -> bool {
+        matches!(self, FieldEncoding::Varint(_) | FieldEncoding::Prefixed(_))
+    }
+}
+
+fn parse_endian(attr: &Attribute) -> Result<Option<Endian>> {
+    if attr.tokens.is_empty() {
+        return Ok(None);
+    }
+    let ident = attr.parse_args::<Ident>()?;
+    match ident.to_string().as_str() {
+        "le" => Ok(Some(Endian::Le)),
+        "be" => Ok(Some(Endian::Be)),
+        _ => Err(Error::new_spanned(ident, "expected `le` or `be`")),
+    }
+}
+
+impl FieldEncoding {
+    pub(crate) fn from_field(attrs: &[Attribute], ty: &Type) -> Result<Self> {
+        let mut terminator = Terminator::Full;
+        let mut utf16 = None;
+        let mut varint = None;
+        for attr in attrs {
+            match attr.path.to_token_stream().to_string().as_str() {
+                "cstr" => terminator = Terminator::Nul,
+                "padded" => terminator = Terminator::Pad(Box::new(attr.parse_args::<Expr>()?)),
+                "utf16" => utf16 = Some(parse_endian(attr)?),
+                "varint" => varint = Some((attr, attr.parse_args::<Ident>()?)),
+                _ => {}
+            }
+        }
+        if let Some(attr) = attrs.iter().find(|x| x.path.is_ident("prefix")) {
+            if varint.is_some() || utf16.is_some() || !matches!(terminator, Terminator::Full) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "prefix can not be used together with other encodings",
+                ));
+            }
+            return Ok(FieldEncoding::Prefixed(Box::new(Prefix::parse(attr, ty)?)));
+        }
+        if let Some((attr, kind)) = varint {
+            let signedness = int_signedness(ty);
+            let bits = known_size(ty).map(|x| x * 8);
+            let kind = match (kind.to_string().as_str(), signedness, bits) {
+                ("uleb128", Some(false), _) => VarintKind::Uleb128,
+                ("sleb128", Some(true), _) => VarintKind::Sleb128,
+                ("protobuf", Some(_), Some(bits)) if bits <= 64 => VarintKind::Protobuf,
+                ("uleb128", ..) => {
+                    return Err(Error::new_spanned(
+                        ty,
+                        "uleb128 field must be an unsigned integer",
+                    ))
+                }
+                ("sleb128", ..) => {
+                    return Err(Error::new_spanned(
+                        ty,
+                        "sleb128 field must be a signed integer",
+                    ))
+                }
+                ("protobuf", ..) => {
+                    return Err(Error::new_spanned(
+                        ty,
+                        "protobuf varint field must be an integer of at most 64 bits",
+                    ))
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        kind,
+                        "expected `uleb128`, `sleb128` or `protobuf`",
+                    ))
+                }
+            };
+            if utf16.is_some() || !matches!(terminator, Terminator::Full) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "varint can not be used together with cstr, padded or utf16",
+                ));
+            }
+            return Ok(FieldEncoding::Varint(kind));
+        }
+        if let Some(endian) = utf16 {
+            if ty.to_token_stream().to_string() != "String" {
+                return Err(Error::new_spanned(ty, "utf16 field must be `String`"));
+            }
+            return Ok(FieldEncoding::Utf16(endian));
+        }
+        if let Type::Reference(reference) = ty {
+            let lifetime = reference.lifetime.to_owned().ok_or(Error::new_spanned(
+                reference,
+                "borrowed field must have an explicit lifetime",
+            ))?;
+            let (is_str, is_array) = match reference.elem.as_ref() {
+                Type::Path(path) if path.path.is_ident("str") => (true, false),
+                Type::Slice(slice) if is_u8(&slice.elem) => (false, false),
+                Type::Array(array) if is_u8(&array.elem) => (false, true),
+                _ => {
+                    return Err(Error::new_spanned(
+                        reference,
+                        "only `&[u8]`, `&[u8; N]` and `&str` can be borrowed",
+                    ))
+                }
+            };
+            if is_array && !matches!(terminator, Terminator::Full) {
+                return Err(Error::new_spanned(
+                    reference,
+                    "cstr and padded can not be used on arrays",
+                ));
+            }
+            return Ok(FieldEncoding::Borrowed {
+                lifetime,
+                is_str,
+                is_array,
+                terminator,
+            });
+        }
+        if !matches!(terminator, Terminator::Full) {
+            return Err(Error::new_spanned(
+                ty,
+                "cstr and padded can only be used on `&[u8]` or `&str`",
+            ));
+        }
+        Ok(FieldEncoding::Native)
+    }
+
+    /// 从 `raw`（`&[u8]`，已按 pos 截取）解码出字段值，失败时返回 `err`。
+    pub(crate) fn decode(
+        &self,
+        target_type: &Type,
+        raw: TokenStream,
+        err: TokenStream,
+        endian: Endian,
+        krate: &Path,
+    ) -> TokenStream {
+        match self {
+            FieldEncoding::Native => {
+                let wrapper = endian.wrapper(krate);
+                quote! {
+                    <#target_type>::try_from(#wrapper(#raw)).map_err(|_| #err)?
+                }
+            }
+            FieldEncoding::Borrowed {
+                is_str,
+                is_array,
+                terminator,
+                ..
+            } => {
+                let trim = match terminator {
+                    Terminator::Full => quote!(),
+                    Terminator::Nul => quote! {
+                        let raw = &raw[..raw.iter().position(|b| *b == 0).unwrap_or(raw.len())];
+                    },
+                    Terminator::Pad(pad) => quote! {
+                        let raw = &raw[..raw.iter().rposition(|b| *b != #pad).map_or(0, |i| i + 1)];
+                    },
+                };
+                let convert = if *is_array {
+                    quote!(<#target_type>::try_from(raw).map_err(|_| #err)?)
+                } else if *is_str {
+                    let ascii = match terminator {
+                        Terminator::Pad(_) => quote! {
+                            if !raw.is_ascii() {
+                                return Err(#err);
+                            }
+                        },
+                        _ => quote!(),
+                    };
+                    quote! {
+                        #ascii
+                        ::core::str::from_utf8(raw).map_err(|_| #err)?
+                    }
+                } else {
+                    quote!(raw)
+                };
+                quote! {
+                    {
+                        let raw = #raw;
+                        #trim
+                        #convert
+                    }
+                }
+            }
+            FieldEncoding::Varint(kind) => {
+                let varint = kind.trait_path(krate);
+                quote!(<#target_type as #varint>::decode(#raw).ok_or(#err)?)
+            }
+            FieldEncoding::Prefixed(prefix) => {
+                let size = prefix.size();
+                let item_size = prefix.item_size(krate);
+                let ty = &prefix.ty;
+                let wrapper = prefix.endian.unwrap_or(endian).wrapper(krate);
+                let out_of_bounds = prefix.out_of_bounds(quote!(count));
+                let convert = match &prefix.item {
+                    PrefixItem::Bytes => quote!(data),
+                    PrefixItem::Str => quote!(::core::str::from_utf8(data).map_err(|_| #err)?),
+                    PrefixItem::String => quote! {
+                        ::std::string::String::from(::core::str::from_utf8(data).map_err(|_| #err)?)
+                    },
+                    PrefixItem::Vec(elem) if is_u8(elem) => quote!(data.to_vec()),
+                    PrefixItem::Vec(elem) => {
+                        let wrapper = endian.wrapper(krate);
+                        quote! {
+                            data.chunks_exact(#item_size)
+                                .map(|x| <#elem>::try_from(#wrapper(x)))
+                                .collect::<::core::result::Result<::std::vec::Vec<_>, _>>()
+                                .map_err(|_| #err)?
+                        }
+                    }
+                };
+                quote! {
+                    {
+                        let raw = #raw;
+                        let (head, data) = raw.split_at(#size);
+                        // 超出范围的长度在计算位置时已被截去，需按前缀本身判断
+                        let count = <#ty>::try_from(#wrapper(head)).map_err(|_| #err)?;
+                        let count = usize::try_from(count).map_err(|_| #err)?;
+                        if #out_of_bounds {
+                            return Err(#err);
+                        }
+                        #convert
+                    }
+                }
+            }
+            FieldEncoding::Utf16(fixed) => {
+                let from_bytes = match fixed.unwrap_or(endian) {
+                    Endian::Le => quote!(u16::from_le_bytes),
+                    Endian::Be => quote!(u16::from_be_bytes),
+                };
+                quote! {
+                    {
+                        let raw = #raw;
+                        if raw.len() % 2 != 0 {
+                            return Err(#err);
+                        }
+                        let units = raw
+                            .chunks_exact(2)
+                            .map(|c| #from_bytes([c[0], c[1]]))
+                            .take_while(|u| *u != 0);
+                        ::core::char::decode_utf16(units)
+                            .collect::<::core::result::Result<::std::string::String, _>>()
+                            .map_err(|_| #err)?
+                    }
+                }
+            }
+        }
+    }
+
+    /// 编码前检查 `value`（字段值的引用）能否放入 `width` 字节并原样解码，无需检查时为 None
+    ///
+    /// - 无终止符的借用字段必须恰好占满 pos 范围
+    /// - `cstr` 不能含 NUL，`padded` 不能以填充字节结尾，否则解码时会被截去；
+    ///   `cstr` 可以恰好占满 pos 范围而不留 NUL，解码时没有 NUL 即取整个范围
+    /// - `padded` 的字符串必须是 ASCII，UTF-16 字符串不能含 `'\0'`
+    /// - 长度前缀字段的长度不能超出前缀类型与 min、max，否则前缀会被截断
+    pub(crate) fn fits(&self, value: TokenStream, width: TokenStream) -> Option<TokenStream> {
+        match self {
+            FieldEncoding::Borrowed {
+                is_str, terminator, ..
+            } => {
+                let check = match terminator {
+                    Terminator::Full => quote!(__bytes.len() == #width),
+                    Terminator::Nul => quote!(__bytes.len() <= #width && !__bytes.contains(&0)),
+                    Terminator::Pad(pad) => {
+                        let ascii = match is_str {
+                            true => quote!(&&__bytes.is_ascii()),
+                            false => quote!(),
+                        };
+                        quote!(__bytes.len() <= #width && __bytes.last() != Some(&(#pad)) #ascii)
+                    }
+                };
+                Some(quote! {{
+                    let __bytes: &[u8] = ::core::convert::AsRef::<[u8]>::as_ref(#value);
+                    #check
+                }})
+            }
+            FieldEncoding::Utf16(_) => Some(quote! {
+                #value.encode_utf16().count() * 2 <= #width && !#value.contains('\0')
+            }),
+            FieldEncoding::Prefixed(prefix) => {
+                let out_of_bounds = prefix.out_of_bounds(quote!(#value.len()));
+                Some(quote!(!(#out_of_bounds)))
+            }
+            _ => None,
+        }
+    }
+
+    /// 由 `value` 构造编码迭代器。借用字段与字符串在数据之后以填充字节补齐 pos 范围。
+    pub(crate) fn iter_init(
+        &self,
+        value: TokenStream,
+        endian: Endian,
+        krate: &Path,
+    ) -> TokenStream {
+        match self {
+            FieldEncoding::Varint(kind) => {
+                let varint = kind.trait_path(krate);
+                quote!(#varint::encode(#value))
+            }
+            FieldEncoding::Prefixed(prefix) => {
+                let ty = &prefix.ty;
+                let count = match prefix.endian.unwrap_or(endian) {
+                    Endian::Le => {
+                        quote!(#krate::endian::IntoLeIter::into_leiter(value.len() as #ty))
+                    }
+                    Endian::Be => {
+                        quote!(#krate::endian::IntoBeIter::into_beiter(value.len() as #ty))
+                    }
+                };
+                let data = match &prefix.item {
+                    PrefixItem::Bytes => quote!(value.iter().copied()),
+                    PrefixItem::Str => quote!(value.as_bytes().iter().copied()),
+                    PrefixItem::String => quote!(value.into_bytes().into_iter()),
+                    PrefixItem::Vec(_) => match endian {
+                        Endian::Le => quote! {
+                            value.into_iter().flat_map(#krate::endian::IntoLeIter::into_leiter)
+                        },
+                        Endian::Be => quote! {
+                            value.into_iter().flat_map(#krate::endian::IntoBeIter::into_beiter)
+                        },
+                    },
+                };
+                // 长度已在编码前由 `fits` 检查，不会截断
+                quote! {
+                    {
+                        let value = #value;
+                        #count.chain(#data)
+                    }
+                }
+            }
+            FieldEncoding::Native => match endian {
+                Endian::Le => quote!(#krate::endian::IntoLeIter::into_leiter(#value)),
+                Endian::Be => quote!(#krate::endian::IntoBeIter::into_beiter(#value)),
+            },
+            FieldEncoding::Borrowed {
+                is_str, terminator, ..
+            } => {
+                let bytes = if *is_str {
+                    quote!(#value.as_bytes())
+                } else {
+                    value
+                };
+                let pad = match terminator {
+                    Terminator::Pad(pad) => pad.to_token_stream(),
+                    _ => quote!(0u8),
+                };
+                quote! {
+                    #bytes.iter().copied().chain(::core::iter::repeat(#pad))
+                }
+            }
+            FieldEncoding::Utf16(fixed) => {
+                let to_bytes = match fixed.unwrap_or(endian) {
+                    Endian::Le => quote!(u16::to_le_bytes),
+                    Endian::Be => quote!(u16::to_be_bytes),
+                };
+                quote! {
+                    #value
+                        .encode_utf16()
+                        .flat_map(#to_bytes)
+                        .collect::<::std::vec::Vec<u8>>()
+                        .into_iter()
+                        .chain(::core::iter::repeat(0u8))
+                }
+            }
+        }
+    }
+}
+
+/// `Vec<T>` 中的 `T`
+fn vec_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Vec" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
@@ -0,0 +1,541 @@
+//! `arbitrary` 与 `proptest` feature 下生成的模糊测试支持。
+//!
+//! 生成的值总是布局合法的：restrict 携带整数的变体只取 white_list 中的值，
+//! bitmap 整数字段不超出位宽，bytemap 的条件字段与条件一致、magic 字段取固定值。
+//! 对 bytemap 还会生成 `arbitrary_le_bytes`/`le_bytes_strategy` 等函数，
+//! 直接产生结构合法的字节序列。长度前缀字段的长度不超出前缀类型与 min/max；
+//! 无终止符的借用字符串必须占满 pos 范围，输入不是合法的 UTF-8 时返回 `IncorrectFormat`。
+//!
+//! 生成代码通过运行时重新导出的 `arbitrary`、`proptest` 引用这两个 crate，使用者
+//! 只需启用 binary-proc-rt 的同名 feature。proptest 要求类型实现 `Debug`。
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, GenericParam, Generics, Ident, Lifetime, LifetimeDef, Path, RangeLimits, Type};
+
+use crate::bitmap_struct::BitmapStruct;
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::{FieldEncoding, Prefix, PrefixItem, Terminator};
+use crate::literal_pos::range_from_expr;
+use crate::restrict_enum::RestrictVariant;
+use crate::type_size::int_signedness;
+
+/// white_list 中的一项对应的闭区间 `(lo, hi)`，省略的端点取 `ty` 的最值
+fn white_list_bounds(expr: &Expr, ty: &Type) -> (TokenStream, TokenStream) {
+    match expr {
+        Expr::Range(range) => {
+            let lo = match &range.from {
+                Some(from) => quote!(#from),
+                None => quote!(<#ty>::MIN),
+            };
+            let hi = match (&range.to, &range.limits) {
+                (None, _) => quote!(<#ty>::MAX),
+                (Some(to), RangeLimits::HalfOpen(_)) => quote!((#to - 1)),
+                (Some(to), RangeLimits::Closed(_)) => quote!(#to),
+            };
+            (lo, hi)
+        }
+        _ => (quote!(#expr), quote!(#expr)),
+    }
+}
+
+/// 位宽为 `width` 的整数字段的取值范围
+fn bit_bounds(ty: &Type, width: u32) -> Option<(TokenStream, TokenStream)> {
+    match int_signedness(ty)? {
+        false => Some((
+            quote!(0 as #ty),
+            quote!((u128::MAX >> (128 - #width)) as #ty),
+        )),
+        true => Some((
+            quote!((-1i128 << (#width - 1)) as #ty),
+            quote!(((1i128 << (#width - 1)) - 1) as #ty),
+        )),
+    }
+}
+
+/// 长度前缀字段的长度范围：不超出前缀类型与 min/max，且至多比下限多 256，避免生成过大的值
+fn prefix_len_bounds(prefix: &Prefix) -> (TokenStream, TokenStream) {
+    let ty = &prefix.ty;
+    let lo = match &prefix.min {
+        Some(min) => quote!((#min) as usize),
+        None => quote!(0usize),
+    };
+    let max = match &prefix.max {
+        Some(max) => quote!(((#max) as usize)),
+        None => quote!(usize::MAX),
+    };
+    let hi = quote! {
+        #max
+            .min(usize::try_from(#ty::MAX).unwrap_or(usize::MAX))
+            .min((#lo).saturating_add(256))
+    };
+    (lo, hi)
+}
+
+/// `Arbitrary<'a>` 使用的生命周期，结构体没有生命周期参数时新增 `'arbitrary`
+fn arbitrary_generics(generics: &Generics) -> (Generics, Lifetime) {
+    match generics.lifetimes().next() {
+        Some(lifetime) => (generics.to_owned(), lifetime.lifetime.to_owned()),
+        None => {
+            let lifetime = Lifetime::new("'arbitrary", proc_macro2::Span::call_site());
+            let mut generics = generics.to_owned();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.to_owned())),
+            );
+            (generics, lifetime)
+        }
+    }
+}
+
+fn arbitrary_field(encoding: &FieldEncoding, width: TokenStream, krate: &Path) -> TokenStream {
+    match encoding {
+        // 变长整数可以编码目标类型的任意值
+        FieldEncoding::Native | FieldEncoding::Varint(_) => {
+            quote!(#krate::arbitrary::Arbitrary::arbitrary(u)?)
+        }
+        FieldEncoding::Prefixed(prefix) => {
+            let (lo, hi) = prefix_len_bounds(prefix);
+            let value = match &prefix.item {
+                PrefixItem::Bytes => quote!(raw),
+                PrefixItem::Vec(_) => quote! {
+                    (0..len)
+                        .map(|_| #krate::arbitrary::Arbitrary::arbitrary(u))
+                        .collect::<#krate::arbitrary::Result<_>>()?
+                },
+                // 借用的字符串截取合法的 UTF-8 前缀，长度不足 min 时视为输入不可用
+                PrefixItem::Str => quote! {{
+                    let s = match ::core::str::from_utf8(raw) {
+                        Ok(s) => s,
+                        Err(e) => ::core::str::from_utf8(&raw[..e.valid_up_to()]).unwrap(),
+                    };
+                    if s.len() < lo {
+                        return Err(#krate::arbitrary::Error::IncorrectFormat);
+                    }
+                    s
+                }},
+                // 逐个生成字符，放不下时以 ASCII 补足，恰好 len 字节
+                PrefixItem::String => quote! {{
+                    let mut s = ::std::string::String::with_capacity(len);
+                    while s.len() < len {
+                        let c = <char as #krate::arbitrary::Arbitrary>::arbitrary(u)?;
+                        match s.len() + c.len_utf8() <= len {
+                            true => s.push(c),
+                            false => s.push(char::from(c as u32 as u8 & 0x7f)),
+                        }
+                    }
+                    s
+                }},
+            };
+            let raw = match &prefix.item {
+                PrefixItem::Bytes | PrefixItem::Str => quote!(let raw = u.bytes(len)?;),
+                _ => quote!(),
+            };
+            quote! {{
+                let (lo, hi) = (#lo, #hi);
+                if lo > hi {
+                    return Err(#krate::arbitrary::Error::IncorrectFormat);
+                }
+                let len = u.int_in_range(lo..=hi)?;
+                #raw
+                #value
+            }}
+        }
+        FieldEncoding::Borrowed {
+            is_str,
+            is_array,
+            terminator,
+            ..
+        } => {
+            // padded 的字符串只能是 ASCII，先截去非 ASCII 的部分再去掉末尾的填充字节
+            let ascii = match terminator {
+                Terminator::Pad(_) if *is_str => quote! {
+                    let raw = &raw[..raw.iter().position(|b| !b.is_ascii()).unwrap_or(raw.len())];
+                },
+                _ => quote!(),
+            };
+            let trim = match terminator {
+                Terminator::Full => quote!(),
+                Terminator::Nul => quote! {
+                    let raw = &raw[..raw.iter().position(|b| *b == 0).unwrap_or(raw.len())];
+                },
+                Terminator::Pad(pad) => quote! {
+                    let raw = &raw[..raw.iter().rposition(|b| *b != #pad).map_or(0, |i| i + 1)];
+                },
+            };
+            let convert = if *is_array {
+                quote!(raw.try_into().unwrap())
+            } else if *is_str {
+                match terminator {
+                    // 无终止符的字符串必须恰好占满 pos 范围，不能截取
+                    Terminator::Full => quote! {
+                        ::core::str::from_utf8(raw)
+                            .map_err(|_| #krate::arbitrary::Error::IncorrectFormat)?
+                    },
+                    _ => quote! {
+                        match ::core::str::from_utf8(raw) {
+                            Ok(s) => s,
+                            Err(e) => ::core::str::from_utf8(&raw[..e.valid_up_to()]).unwrap(),
+                        }
+                    },
+                }
+            } else {
+                quote!(raw)
+            };
+            quote! {
+                {
+                    let raw = u.bytes(#width)?;
+                    #ascii
+                    #trim
+                    #convert
+                }
+            }
+        }
+        FieldEncoding::Utf16(_) => quote! {
+            {
+                let mut units = 0;
+                <::std::string::String as #krate::arbitrary::Arbitrary>::arbitrary(u)?
+                    .chars()
+                    .filter(|c| *c != '\0')
+                    .take_while(|c| {
+                        units += c.len_utf16();
+                        units * 2 <= #width
+                    })
+                    .collect::<::std::string::String>()
+            }
+        },
+    }
+}
+
+pub(crate) fn bytemap_arbitrary(bytemap: &BytemapStruct) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let krate = &bytemap.krate;
+    let ident = &clean.ident;
+    let (_, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let (generics, lifetime) = arbitrary_generics(&clean.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    let mut fields = TokenStream::new();
+    let mut field_idents = Vec::new();
+    for field in bytemap.fields.iter() {
+        let field_ident = &field.ident;
+        let width = &field.len;
+        let mut value = match &field.magic {
+            Some(magic) => quote!(#magic),
+            None => arbitrary_field(&field.encoding, width.to_owned(), krate),
+        };
+        if let Some(condition) = &field.condition {
+            value = quote!(if #condition { Some(#value) } else { None });
+        }
+        fields.extend(quote!(let #field_ident = #value;));
+        field_idents.push(field_ident);
+    }
+    quote! {
+        impl #impl_generics #krate::arbitrary::Arbitrary<#lifetime> for #ident #ty_generics #where_clause {
+            fn arbitrary(u: &mut #krate::arbitrary::Unstructured<#lifetime>) -> #krate::arbitrary::Result<Self> {
+                #fields
+                Ok(Self {
+                    #(#field_idents),*
+                })
+            }
+        }
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 结构合法的小端字节序列
+            pub fn arbitrary_le_bytes(
+                u: &mut #krate::arbitrary::Unstructured<#lifetime>,
+            ) -> #krate::arbitrary::Result<::std::vec::Vec<u8>> {
+                let value = <Self as #krate::arbitrary::Arbitrary>::arbitrary(u)?;
+                let mut bytes = ::std::vec![0u8; value.encoded_len()];
+                value
+                    .encode_le_into(&mut bytes)
+                    .map_err(|_| #krate::arbitrary::Error::IncorrectFormat)?;
+                Ok(bytes)
+            }
+            /// 结构合法的大端字节序列
+            pub fn arbitrary_be_bytes(
+                u: &mut #krate::arbitrary::Unstructured<#lifetime>,
+            ) -> #krate::arbitrary::Result<::std::vec::Vec<u8>> {
+                let value = <Self as #krate::arbitrary::Arbitrary>::arbitrary(u)?;
+                let mut bytes = ::std::vec![0u8; value.encoded_len()];
+                value
+                    .encode_be_into(&mut bytes)
+                    .map_err(|_| #krate::arbitrary::Error::IncorrectFormat)?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+pub(crate) fn bitmap_arbitrary(bitmap: &BitmapStruct, krate: &Path) -> syn::Result<TokenStream> {
+    let clean = &bitmap.clean_struct;
+    let ident = &clean.ident;
+    let (_, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let (generics, lifetime) = arbitrary_generics(&clean.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let mut fields = TokenStream::new();
+    for field in bitmap.fields.iter() {
+        let field_ident = &field.ident;
+        let range = range_from_expr(&field.pos)?;
+        let width = (range.end() + 1 - range.start()) as u32;
+        let value = match bit_bounds(&field.target_type, width) {
+            Some((lo, hi)) => quote!(u.int_in_range(#lo..=#hi)?),
+            None => quote!(#krate::arbitrary::Arbitrary::arbitrary(u)?),
+        };
+        fields.extend(quote!(#field_ident: #value,));
+    }
+    Ok(quote! {
+        impl #impl_generics #krate::arbitrary::Arbitrary<#lifetime> for #ident #ty_generics #where_clause {
+            fn arbitrary(u: &mut #krate::arbitrary::Unstructured<#lifetime>) -> #krate::arbitrary::Result<Self> {
+                Ok(Self {
+                    #fields
+                })
+            }
+        }
+    })
+}
+
+pub(crate) fn restrict_arbitrary(
+    clean_enum: &syn::DeriveInput,
+    variants: &[RestrictVariant],
+    krate: &Path,
+) -> TokenStream {
+    let ident = &clean_enum.ident;
+    let (_, ty_generics, where_clause) = clean_enum.generics.split_for_impl();
+    let (generics, lifetime) = arbitrary_generics(&clean_enum.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let arms = variants.iter().enumerate().map(|(idx, variant)| {
+        let variant_ident = &variant.ident;
+        let value = match &variant.target_type {
+            Some(ty) if int_signedness(ty).is_some() => {
+                let white_list = &variant.restrict.white_list;
+                let count = white_list.len();
+                let choices = white_list.iter().enumerate().map(|(idx, expr)| {
+                    let (lo, hi) = white_list_bounds(expr, ty);
+                    quote!(#idx => u.int_in_range::<#ty>(#lo..=#hi)?,)
+                });
+                quote! {
+                    Self::#variant_ident(match u.choose_index(#count)? {
+                        #(#choices)*
+                        _ => unreachable!(),
+                    })
+                }
+            }
+            Some(_) => quote!(Self::#variant_ident(#krate::arbitrary::Arbitrary::arbitrary(u)?)),
+            None => quote!(Self::#variant_ident),
+        };
+        quote!(#idx => #value,)
+    });
+    let count = variants.len();
+    quote! {
+        impl #impl_generics #krate::arbitrary::Arbitrary<#lifetime> for #ident #ty_generics #where_clause {
+            fn arbitrary(u: &mut #krate::arbitrary::Unstructured<#lifetime>) -> #krate::arbitrary::Result<Self> {
+                Ok(match u.choose_index(#count)? {
+                    #(#arms)*
+                    _ => unreachable!(),
+                })
+            }
+        }
+    }
+}
+
+/// 含生命周期参数的类型无法生成 `BoxedStrategy`，此时不生成 proptest 支持
+fn has_lifetime(generics: &Generics) -> bool {
+    generics.lifetimes().next().is_some()
+}
+
+fn proptest_impl(
+    clean: &syn::DeriveInput,
+    strategy: TokenStream,
+    extra: TokenStream,
+    krate: &Path,
+) -> TokenStream {
+    let ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #krate::proptest::arbitrary::Arbitrary for #ident #ty_generics #where_clause {
+            type Parameters = ();
+            type Strategy = #krate::proptest::strategy::BoxedStrategy<Self>;
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                use #krate::proptest::strategy::Strategy;
+                #strategy.boxed()
+            }
+        }
+        #extra
+    }
+}
+
+/// 以元组组合 strategy，每层至多 10 项，返回元组 strategy 及对应的模式
+fn tuple_strategy(items: &[(TokenStream, TokenStream)]) -> (TokenStream, TokenStream) {
+    if items.len() <= 10 {
+        let strategies = items.iter().map(|(strategy, _)| strategy);
+        let patterns = items.iter().map(|(_, pattern)| pattern);
+        return (quote!((#(#strategies,)*)), quote!((#(#patterns,)*)));
+    }
+    let chunks = items.chunks(10).map(tuple_strategy).collect::<Vec<_>>();
+    tuple_strategy(&chunks)
+}
+
+/// 各字段独立生成后再构造值，条件字段在此时按之前字段的值决定是否保留。
+/// 逐个字段嵌套 prop_flat_map 在字段较多时会使生成与收缩栈溢出。
+fn fields_strategy(fields: &[(&Ident, TokenStream, Option<&Expr>)], krate: &Path) -> TokenStream {
+    if fields.is_empty() {
+        return quote!(#krate::proptest::strategy::LazyJust::new(|| Self {}));
+    }
+    let items = fields
+        .iter()
+        .map(|(ident, strategy, _)| (strategy.to_owned(), quote!(#ident)))
+        .collect::<Vec<_>>();
+    let (strategy, pattern) = tuple_strategy(&items);
+    let conditions = fields.iter().filter_map(|(ident, _, condition)| {
+        let condition = (*condition)?;
+        Some(quote!(let #ident = if #condition { Some(#ident) } else { None };))
+    });
+    let idents = fields.iter().map(|(ident, _, _)| ident);
+    quote! {
+        #strategy.prop_map(move |#pattern| {
+            #(#conditions)*
+            Self {
+                #(#idents),*
+            }
+        })
+    }
+}
+
+pub(crate) fn bytemap_proptest(bytemap: &BytemapStruct) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    if has_lifetime(&clean.generics) {
+        return quote!();
+    }
+    let ident = &clean.ident;
+    let krate = &bytemap.krate;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let mut fields = Vec::new();
+    for field in bytemap.fields.iter() {
+        let width = &field.len;
+        let value = match (&field.magic, &field.encoding) {
+            (Some(magic), _) => quote!(#krate::proptest::strategy::LazyJust::new(|| #magic)),
+            (None, FieldEncoding::Utf16(_)) => quote! {
+                #krate::proptest::collection::vec(
+                    #krate::proptest::char::any().prop_filter("NUL", |c| *c != '\0'),
+                    0..=#width / 4,
+                )
+                .prop_map(|chars| chars.into_iter().collect::<::std::string::String>())
+            },
+            (None, FieldEncoding::Prefixed(prefix)) => {
+                let (lo, hi) = prefix_len_bounds(prefix);
+                let len = quote!((#lo)..=(#hi));
+                match &prefix.item {
+                    PrefixItem::Vec(elem) => quote! {
+                        #krate::proptest::collection::vec(
+                            #krate::proptest::arbitrary::any::<#elem>(),
+                            #len,
+                        )
+                    },
+                    // 按字符生成后截断到 max 以内，再滤去短于 min 的值
+                    _ => quote! {
+                        #krate::proptest::collection::vec(#krate::proptest::char::any(), #len)
+                            .prop_map(|chars| {
+                                let mut s = ::std::string::String::new();
+                                for c in chars {
+                                    if s.len() + c.len_utf8() > #hi {
+                                        break;
+                                    }
+                                    s.push(c);
+                                }
+                                s
+                            })
+                            .prop_filter("shorter than min", |s| s.len() >= #lo)
+                    },
+                }
+            }
+            (None, _) => {
+                let ty = &field.target_type;
+                quote!(#krate::proptest::arbitrary::any::<#ty>())
+            }
+        };
+        fields.push((&field.ident, value, field.condition.as_ref()));
+    }
+    let extra = quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 结构合法的小端字节序列
+            pub fn le_bytes_strategy(
+            ) -> impl #krate::proptest::strategy::Strategy<Value = ::std::vec::Vec<u8>> {
+                use #krate::proptest::strategy::Strategy;
+                #krate::proptest::arbitrary::any::<Self>().prop_filter_map("not encodable", |value| {
+                    let mut bytes = ::std::vec![0u8; value.encoded_len()];
+                    value.encode_le_into(&mut bytes).ok().map(|_| bytes)
+                })
+            }
+            /// 结构合法的大端字节序列
+            pub fn be_bytes_strategy(
+            ) -> impl #krate::proptest::strategy::Strategy<Value = ::std::vec::Vec<u8>> {
+                use #krate::proptest::strategy::Strategy;
+                #krate::proptest::arbitrary::any::<Self>().prop_filter_map("not encodable", |value| {
+                    let mut bytes = ::std::vec![0u8; value.encoded_len()];
+                    value.encode_be_into(&mut bytes).ok().map(|_| bytes)
+                })
+            }
+        }
+    };
+    proptest_impl(clean, fields_strategy(&fields, krate), extra, krate)
+}
+
+pub(crate) fn bitmap_proptest(bitmap: &BitmapStruct, krate: &Path) -> syn::Result<TokenStream> {
+    let clean = &bitmap.clean_struct;
+    if has_lifetime(&clean.generics) {
+        return Ok(quote!());
+    }
+    let mut fields = Vec::new();
+    for field in bitmap.fields.iter() {
+        let range = range_from_expr(&field.pos)?;
+        let width = (range.end() + 1 - range.start()) as u32;
+        let ty = &field.target_type;
+        let value = match bit_bounds(ty, width) {
+            Some((lo, hi)) => quote!((#lo..=#hi)),
+            None => quote!(#krate::proptest::arbitrary::any::<#ty>()),
+        };
+        fields.push((&field.ident, value, None));
+    }
+    Ok(proptest_impl(
+        clean,
+        fields_strategy(&fields, krate),
+        quote!(),
+        krate,
+    ))
+}
+
+pub(crate) fn restrict_proptest(
+    clean_enum: &syn::DeriveInput,
+    variants: &[RestrictVariant],
+    krate: &Path,
+) -> TokenStream {
+    if has_lifetime(&clean_enum.generics) {
+        return quote!();
+    }
+    let choices = variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        match &variant.target_type {
+            Some(ty) if int_signedness(ty).is_some() => {
+                let ranges = variant.restrict.white_list.iter().map(|expr| {
+                    let (lo, hi) = white_list_bounds(expr, ty);
+                    quote!(((#lo as #ty)..=(#hi as #ty)).boxed())
+                });
+                quote! {
+                    #krate::proptest::strategy::Union::new(::std::vec![#(#ranges),*])
+                        .prop_map(Self::#variant_ident)
+                        .boxed()
+                }
+            }
+            Some(ty) => quote! {
+                #krate::proptest::arbitrary::any::<#ty>().prop_map(Self::#variant_ident).boxed()
+            },
+            None => quote! {
+                #krate::proptest::strategy::LazyJust::new(|| Self::#variant_ident).boxed()
+            },
+        }
+    });
+    let strategy = quote! {
+        #krate::proptest::strategy::Union::new(::std::vec![#(#choices),*])
+    };
+    proptest_impl(clean_enum, strategy, quote!(), krate)
+}
@@ -0,0 +1,182 @@
+//! 附加在生成类型上的布局文档：bytemap 的字节偏移表、bitmap 的位表以及 restrict 的取值表，
+//! 均由 pos 与 white_list 推导，文档与实际布局始终一致。
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{parse_quote, Attribute, Result, TypePath};
+
+use crate::bitmap_struct::BitmapStruct;
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::{Endian, FieldEncoding, VarintKind};
+use crate::literal_pos::range_from_expr;
+use crate::restrict_enum::RestrictVariant;
+use crate::schema::{tokens_string, type_string};
+
+/// 表格单元格中的代码，`|` 需要转义
+fn code(value: &str) -> String {
+    format!("`{}`", value.replace('|', "\\|"))
+}
+
+fn range(start: usize, end: usize) -> String {
+    match start == end {
+        true => code(&start.to_string()),
+        false => code(&format!("{}..={}", start, end)),
+    }
+}
+
+/// 紧密排列时位于变长或条件字段之后的位置，由生成代码中的内部变量在运行时求出
+fn runtime_pos(pos: &impl ToTokens) -> bool {
+    fn visit(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|x| match x {
+            TokenTree::Ident(ident) => ident.to_string().starts_with("__"),
+            TokenTree::Group(group) => visit(group.stream()),
+            _ => false,
+        })
+    }
+    visit(pos.to_token_stream())
+}
+
+fn doc(lines: Vec<String>) -> Attribute {
+    let doc = format!("\n{}", lines.join("\n"));
+    parse_quote!(#[doc = #doc])
+}
+
+pub(crate) fn bytemap_doc(bytemap: &BytemapStruct) -> Attribute {
+    let conditional = bytemap.fields.iter().any(|x| x.condition.is_some());
+    // (排序键, 行)，位置不是字面量时保持声明顺序
+    let mut rows = Vec::new();
+    let unit = bytemap.unit;
+    for (i, field) in bytemap.fields.iter().enumerate() {
+        let bytes = match &field.pos_value {
+            Some(pos) => range(pos.start() / unit, pos.end() / unit),
+            None if runtime_pos(&field.pos) => "*variable*".to_owned(),
+            None => code(&type_string(&field.pos)),
+        };
+        let mut ty = code(&type_string(&field.target_type));
+        if let FieldEncoding::Varint(kind) = &field.encoding {
+            let kind = match kind {
+                VarintKind::Uleb128 => "uleb128",
+                VarintKind::Sleb128 => "sleb128",
+                VarintKind::Protobuf => "protobuf",
+            };
+            ty.push_str(&format!(" ({} varint)", kind));
+        }
+        if let FieldEncoding::Prefixed(prefix) = &field.encoding {
+            let endian = match prefix.endian {
+                Some(Endian::Le) => " le",
+                Some(Endian::Be) => " be",
+                None => "",
+            };
+            ty.push_str(&format!(" ({}{} prefix)", type_string(&prefix.ty), endian));
+        }
+        let mut row = format!(
+            "| {} | {} | {} |",
+            bytes,
+            code(&field.ident.to_string()),
+            ty
+        );
+        if conditional {
+            let condition = field
+                .condition
+                .as_ref()
+                .map_or(String::new(), |x| code(&tokens_string(x)));
+            row.push_str(&format!(" {} |", condition));
+        }
+        rows.push((field.pos_value.as_ref().map(|x| *x.start()), i, row));
+    }
+    for (i, reserved) in bytemap.reserved.iter().enumerate() {
+        let mut row = format!(
+            "| {} | *reserved* | |",
+            range(reserved.start() / unit, reserved.end() / unit)
+        );
+        if conditional {
+            row.push_str(" |");
+        }
+        rows.push((Some(*reserved.start()), bytemap.fields.len() + i, row));
+    }
+    if bytemap.fields.iter().all(|x| x.pos_value.is_some()) {
+        rows.sort_by_key(|(start, i, _)| (*start, *i));
+    }
+
+    let mut lines = vec!["# Layout".to_owned(), String::new()];
+    let column = match unit {
+        1 => "Bytes".to_owned(),
+        _ => {
+            lines.push(format!("Addressed in {}-bit words.", unit * 8));
+            lines.push(String::new());
+            "Words".to_owned()
+        }
+    };
+    match conditional {
+        true => {
+            lines.push(format!("| {} | Field | Type | Present when |", column));
+            lines.push("|---|---|---|---|".to_owned());
+        }
+        false => {
+            lines.push(format!("| {} | Field | Type |", column));
+            lines.push("|---|---|---|".to_owned());
+        }
+    }
+    lines.extend(rows.into_iter().map(|(_, _, row)| row));
+    doc(lines)
+}
+
+pub(crate) fn bitmap_doc(bitmap: &BitmapStruct, types: &[TypePath]) -> Result<Attribute> {
+    let mut rows = Vec::new();
+    for field in bitmap.fields.iter() {
+        let pos = range_from_expr(&field.pos)?;
+        rows.push((
+            *pos.start(),
+            format!(
+                "| {} | {} | {} |",
+                range(*pos.start(), *pos.end()),
+                code(&field.ident.to_string()),
+                code(&type_string(&field.target_type))
+            ),
+        ));
+    }
+    rows.sort_by_key(|(start, _)| *start);
+    let containers = types
+        .iter()
+        .map(|x| code(&tokens_string(x)))
+        .collect::<Vec<_>>();
+    let mut lines = vec![
+        "# Layout".to_owned(),
+        String::new(),
+        format!("Container: {}", containers.join(", ")),
+        String::new(),
+        "| Bits | Field | Type |".to_owned(),
+        "|---|---|---|".to_owned(),
+    ];
+    lines.extend(rows.into_iter().map(|(_, row)| row));
+    Ok(doc(lines))
+}
+
+pub(crate) fn restrict_doc(variants: &[RestrictVariant], types: &[TypePath]) -> Attribute {
+    let containers = types
+        .iter()
+        .map(|x| code(&tokens_string(x)))
+        .collect::<Vec<_>>();
+    let mut lines = vec![
+        "# Accepted values".to_owned(),
+        String::new(),
+        format!("Container: {}", containers.join(", ")),
+        String::new(),
+        "| Variant | Values |".to_owned(),
+        "|---|---|".to_owned(),
+    ];
+    for variant in variants.iter() {
+        let values = variant
+            .restrict
+            .white_list
+            .iter()
+            .map(|x| code(&type_string(x)))
+            .collect::<Vec<_>>();
+        let ident = match &variant.target_type {
+            Some(ty) => format!("{}({})", variant.ident, type_string(ty)),
+            None => variant.ident.to_string(),
+        };
+        lines.push(format!("| {} | {} |", code(&ident), values.join(", ")));
+    }
+    doc(lines)
+}
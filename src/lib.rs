@@ -2,166 +2,752 @@
 
 use crate::restrict_enum::RestrictEnum;
 use bitmap_struct::BitmapStruct;
+use bytemap_args::BytemapArgs;
 use bytemap_struct::BytemapStruct;
+use c_import::CDeclarations;
 use container_type::ContainerType;
+use field_encoding::Endian;
+use literal_pos::range_from_expr;
 use proc_macro::TokenStream;
-use quote::format_ident;
+use quote::{format_ident, ToTokens};
+use syn::parse::{ParseStream, Parser};
 use syn::parse_macro_input;
+use type_size::{int_signedness, lossless_into};
 
 extern crate quote;
 
 mod bitmap_struct;
+mod bytemap_args;
+mod bytemap_builder;
 mod bytemap_struct;
+mod c_header;
+mod c_import;
 mod container_type;
+mod crate_path;
+mod diagnostics;
+mod dissector;
+mod field_encoding;
+mod fuzz;
+mod layout_doc;
 mod literal_pos;
+mod mmio;
+mod nom_parse;
+mod partial;
 mod restrict_enum;
+mod schema;
+mod tlv;
+mod type_size;
+mod word_unit;
+mod zerocopy;
 
 #[proc_macro_attribute]
 pub fn bytemap(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    // let types = parse_macro_input!(_attr as ContainerType).types;
-    let bytemap = parse_macro_input!(item as BytemapStruct);
+    let args = parse_macro_input!(_attr as BytemapArgs);
+    let parser = |input: ParseStream| BytemapStruct::parse_with_args(input, &args);
+    let bytemap = match parser.parse(item) {
+        Ok(bytemap) => bytemap,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let ident = bytemap.clean_struct.to_owned().ident;
-    let clean = bytemap.clean_struct.to_owned();
+    let mut clean = bytemap.clean_struct.to_owned();
+    clean.attrs.push(layout_doc::bytemap_doc(&bytemap));
+    let krate = &bytemap.krate;
+    let warnings = &bytemap.warnings;
     let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
     let mut bytes_read_from_le = proc_macro2::TokenStream::new();
     let mut bytes_read_from_be = proc_macro2::TokenStream::new();
-    let mut le_iter_fields = proc_macro2::TokenStream::new();
-    let mut be_iter_fields = proc_macro2::TokenStream::new();
-    let mut le_iter_fields_into = proc_macro2::TokenStream::new();
-    let mut be_iter_fields_into = proc_macro2::TokenStream::new();
-    let mut next_return = proc_macro2::TokenStream::new();
+    let mut encode_layout = proc_macro2::TokenStream::new();
+    let mut le_encode_fields = proc_macro2::TokenStream::new();
+    let mut be_encode_fields = proc_macro2::TokenStream::new();
+    let mut nom_read_le = proc_macro2::TokenStream::new();
+    let mut nom_read_be = proc_macro2::TokenStream::new();
+    let mut check_fields = proc_macro2::TokenStream::new();
+    let mut field_idents = Vec::new();
     for field in bytemap.fields.clone() {
-        let field_ident = field.ident;
-        let field_pos = field.pos;
-        let target_type = field.target_type;
+        let field_ident = field.ident.to_owned();
+        let pos_ident = field.pos_ident();
+        let target_type = field.target_type.to_owned();
+        let raw = quote::quote!(__value.0.get(#pos_ident.clone()).ok_or(#pos_ident.clone())?);
+        let err = quote::quote!(#pos_ident.clone());
+        let mut le_decode =
+            field
+                .encoding
+                .decode(&target_type, raw.clone(), err.clone(), Endian::Le, krate);
+        let mut be_decode = field
+            .encoding
+            .decode(&target_type, raw, err, Endian::Be, krate);
+        if let Some(condition) = &field.condition {
+            le_decode = quote::quote!(if #condition { Some(#le_decode) } else { None });
+            be_decode = quote::quote!(if #condition { Some(#be_decode) } else { None });
+        }
+        let (decode_present, encode_present) = match field.condition {
+            Some(_) => (
+                quote::quote!(#field_ident.is_some()),
+                quote::quote!(self.#field_ident.is_some()),
+            ),
+            None => (quote::quote!(true), quote::quote!(true)),
+        };
+        let layout = field.layout(krate);
+        let decode_advance = field.advance(decode_present);
+        let check_magic = match &field.magic {
+            Some(magic) => quote::quote! {
+                if #field_ident != #magic {
+                    return Err(#pos_ident);
+                }
+            },
+            None => quote::quote!(),
+        };
         let field_read_from_le = quote::quote! {
-            #field_ident: <#target_type>::try_from(::binary::endian::Le(value.0.get(#field_pos).ok_or(#field_pos)?)).map_err(|_|{#field_pos})?,
+            #layout
+            let #field_ident = #le_decode;
+            #check_magic
+            #decode_advance
         };
         let field_read_from_be = quote::quote! {
-            #field_ident: <#target_type>::try_from(::binary::endian::Be(value.0.get(#field_pos).ok_or(#field_pos)?)).map_err(|_|{#field_pos})?,
+            #layout
+            let #field_ident = #be_decode;
+            #check_magic
+            #decode_advance
         };
         bytes_read_from_le.extend(field_read_from_le);
         bytes_read_from_be.extend(field_read_from_be);
-        let iter_field_name = format_ident!("{}_iter", field_ident);
-        let le_iter_field = quote::quote! {
-            #iter_field_name: <#target_type as ::binary::endian::IntoLeIter>::IntoIter,
-        };
-        let be_iter_field = quote::quote! {
-            #iter_field_name: <#target_type as ::binary::endian::IntoBeIter>::IntoIter,
-        };
-        let le_iter_field_into = quote::quote! {
-            #iter_field_name: self.#field_ident.into_leiter(),
-        };
-        let be_iter_field_into = quote::quote! {
-            #iter_field_name: self.#field_ident.into_beiter(),
+        if cfg!(feature = "nom") {
+            let (le, be) = nom_parse::read_field(&field, krate);
+            nom_read_le.extend(le);
+            nom_read_be.extend(be);
+        }
+        let encode_advance = field.advance(encode_present.clone());
+        let extend_len = match field.condition {
+            Some(_) => quote::quote! {
+                if #encode_present && *#pos_ident.end() + 1 > _len {
+                    _len = *#pos_ident.end() + 1;
+                }
+            },
+            None => quote::quote! {
+                if *#pos_ident.end() + 1 > _len {
+                    _len = *#pos_ident.end() + 1;
+                }
+            },
         };
-        let next_field_return = quote::quote! {
-            if (#field_pos).contains(&self._current_idx) {
-                self._current_idx += 1;
-                return self.#iter_field_name.next();
+        let layout = field.encode_layout(krate);
+        encode_layout.extend(quote::quote! {
+            #layout
+            #encode_advance
+            #extend_len
+        });
+        field_idents.push(field_ident.to_owned());
+
+        // 无法原样解码的值在写入前拒绝，而不是截断或补齐
+        let width = quote::quote!((*#pos_ident.end() + 1 - *#pos_ident.start()));
+        if let Some(fits) = field.encoding.fits(quote::quote!(__v), width) {
+            let name = field_ident.to_string();
+            let check = quote::quote! {
+                if !(#fits) {
+                    return Err(#krate::EncodeError::Invalid(#name));
+                }
+            };
+            check_fields.extend(match field.condition {
+                Some(_) => quote::quote!(if let Some(__v) = &self.#field_ident { #check }),
+                None => quote::quote!({ let __v = &self.#field_ident; #check }),
+            });
+        }
+
+        // 每个字段直接写入其 pos 范围，多余的位置保持为 0
+        let (le_encode, be_encode) = match field.condition {
+            Some(_) => {
+                let le_iter = field
+                    .encoding
+                    .iter_init(quote::quote!(__v), Endian::Le, krate);
+                let be_iter = field
+                    .encoding
+                    .iter_init(quote::quote!(__v), Endian::Be, krate);
+                (
+                    quote::quote! {
+                        if let Some(__v) = self.#field_ident {
+                            __buf[#pos_ident].iter_mut().zip(#le_iter).for_each(|(d, s)| *d = s);
+                        }
+                    },
+                    quote::quote! {
+                        if let Some(__v) = self.#field_ident {
+                            __buf[#pos_ident].iter_mut().zip(#be_iter).for_each(|(d, s)| *d = s);
+                        }
+                    },
+                )
+            }
+            None => {
+                let le_iter =
+                    field
+                        .encoding
+                        .iter_init(quote::quote!(self.#field_ident), Endian::Le, krate);
+                let be_iter =
+                    field
+                        .encoding
+                        .iter_init(quote::quote!(self.#field_ident), Endian::Be, krate);
+                (
+                    quote::quote! {
+                        __buf[#pos_ident].iter_mut().zip(#le_iter).for_each(|(d, s)| *d = s);
+                    },
+                    quote::quote! {
+                        __buf[#pos_ident].iter_mut().zip(#be_iter).for_each(|(d, s)| *d = s);
+                    },
+                )
             }
         };
-        le_iter_fields.extend(le_iter_field);
-        be_iter_fields.extend(be_iter_field);
-        next_return.extend(next_field_return);
-        le_iter_fields_into.extend(le_iter_field_into);
-        be_iter_fields_into.extend(be_iter_field_into);
+        le_encode_fields.extend(le_encode);
+        be_encode_fields.extend(be_encode);
     }
-    let limit = bytemap.fields.last().unwrap().pos_value.end();
+    let name = ident.to_string();
     let le_iter_name = format_ident!("{}LeIter", ident);
     let be_iter_name = format_ident!("{}BeIter", ident);
+    // 编码结果的迭代器：定长布局在栈上的数组中编码，不分配内存
+    let mut iters = proc_macro2::TokenStream::new();
+    for (iter_name, trait_name, method, encode_into, encode) in [
+        (
+            &le_iter_name,
+            format_ident!("IntoLeIter"),
+            format_ident!("into_leiter"),
+            format_ident!("encode_le_into"),
+            format_ident!("encode_le"),
+        ),
+        (
+            &be_iter_name,
+            format_ident!("IntoBeIter"),
+            format_ident!("into_beiter"),
+            format_ident!("encode_be_into"),
+            format_ident!("encode_be"),
+        ),
+    ] {
+        let panics = format!(
+            "字段的值不能原样解码时 panic，见 `{0}`；需要处理该错误时使用 `{0}`",
+            encode_into
+        );
+        let (doc, bytes_ty, init) = match bytemap.const_len() {
+            Some(len) => (
+                format!("`{}` 结果的迭代器，以数组保存编码结果", encode),
+                quote::quote!(::core::array::IntoIter<u8, { #len }>),
+                quote::quote! {
+                    let mut bytes = [0u8; #len];
+                    if let Err(err) = self.#encode_into(&mut bytes) {
+                        panic!("can not encode `{}`: {:?}", #name, err);
+                    }
+                    bytes.into_iter()
+                },
+            ),
+            None => (
+                format!("`{}` 结果的迭代器", encode),
+                quote::quote!(::std::vec::IntoIter<u8>),
+                quote::quote!(self.#encode().into_iter()),
+            ),
+        };
+        iters.extend(quote::quote! {
+            #[doc = #doc]
+            pub struct #iter_name {
+                bytes: #bytes_ty,
+            }
+            impl ::core::iter::Iterator for #iter_name {
+                type Item = u8;
+                fn next(&mut self) -> Option<Self::Item> {
+                    self.bytes.next()
+                }
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.bytes.size_hint()
+                }
+            }
+            impl ::core::iter::ExactSizeIterator for #iter_name {}
+            impl #impl_generics #krate::endian::#trait_name for #ident #ty_generics #where_clause {
+                type Item = u8;
+                type IntoIter = #iter_name;
+                /// # Panics
+                ///
+                #[doc = #panics]
+                fn #method(self) -> Self::IntoIter {
+                    #iter_name {
+                        bytes: { #init },
+                    }
+                }
+            }
+        });
+    }
+    // 借用字段的生命周期与输入切片一致
+    let input_lifetime = clean.generics.lifetimes().next().map(|x| &x.lifetime);
+    // 末尾的 reserved 字节同样需要编码
+    let reserved_len = bytemap.reserved_len();
+    // 含条件字段时布局长度可变
+    let binary_size = if bytemap.is_variable() {
+        quote::quote!()
+    } else {
+        let limit = bytemap.limit();
+        quote::quote! {
+            impl #impl_generics #krate::BinarySize for #ident #ty_generics #where_clause {
+                const SIZE: usize = #limit + 1;
+            }
+        }
+    };
+
+    let builder = if args.builder {
+        bytemap_builder::builder(&bytemap)
+    } else {
+        quote::quote!()
+    };
+    let default = if args.default {
+        bytemap_builder::default(&bytemap)
+    } else {
+        quote::quote!()
+    };
+    let partial = if args.partial || args.accumulate {
+        partial::partial(&bytemap, args.accumulate)
+    } else {
+        quote::quote!()
+    };
+    let schema = if args.schema {
+        schema::bytemap_schema(&bytemap, args.packed)
+    } else {
+        quote::quote!()
+    };
+    let c_header = if args.c_header {
+        match c_header::bytemap_header(&bytemap) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let dissector = if args.dissector {
+        match dissector::bytemap_dissector(&bytemap) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let zerocopy = match args.zerocopy {
+        Some(endian) => match zerocopy::raw_mirror(&bytemap, endian) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        },
+        None => quote::quote!(),
+    };
+    let mmio = if args.mmio {
+        match mmio::register_block(&bytemap) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    // 定长布局可直接得出所需的全部字节数，否则以首个越界字段的范围计算
+    let size_check = if bytemap.is_variable() {
+        quote::quote!()
+    } else {
+        quote::quote! {
+            let __size = <Self as #krate::BinarySize>::SIZE;
+            if __len < __size {
+                return #krate::Decode::Incomplete {
+                    needed: __size - __len,
+                };
+            }
+        }
+    };
+    let mut try_decode = proc_macro2::TokenStream::new();
+    for wrapper in [quote::quote!(Le), quote::quote!(Be)] {
+        try_decode.extend(quote::quote! {
+            impl #impl_generics #krate::TryDecode<#krate::endian::#wrapper<&#input_lifetime [u8]>> for #ident #ty_generics #where_clause {
+                fn try_decode(value: #krate::endian::#wrapper<&#input_lifetime [u8]>) -> #krate::Decode<Self> {
+                    let __len = value.0.len();
+                    #size_check
+                    match <Self as ::core::convert::TryFrom<_>>::try_from(value) {
+                        Ok(decoded) => {
+                            let consumed = decoded.encoded_len();
+                            // 末尾的 reserved 字节不经字段读取，需另外确认已全部到达
+                            if consumed > __len {
+                                return #krate::Decode::Incomplete {
+                                    needed: consumed - __len,
+                                };
+                            }
+                            #krate::Decode::Complete(decoded, consumed)
+                        }
+                        Err(range) if *range.end() >= __len => #krate::Decode::Incomplete {
+                            needed: *range.end() + 1 - __len,
+                        },
+                        Err(range) => #krate::Decode::Error(range),
+                    }
+                }
+            }
+        });
+    }
+    // 借用输入的结构体无法从 Buf 中解码
+    let buf = if cfg!(feature = "bytes") && input_lifetime.is_none() {
+        quote::quote! {
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// 从 `bytes::Buf` 解码，成功时推进 `buf`；数据可跨越多个 chunk
+                pub fn decode_from_buf<V>(buf: V) -> #krate::Decode<Self>
+                where
+                    Self: #krate::buf::DecodeFromBuf<V>,
+                {
+                    <Self as #krate::buf::DecodeFromBuf<V>>::decode_from_buf(buf)
+                }
+                /// 以小端编码写入 `bytes::BufMut`，返回写入的字节数，错误同 `encode_le_into`
+                ///
+                /// 与 `encode_le_into` 一样消耗 `self`：字段按值交给各自的 `IntoLeIter` 编码，
+                /// 需要保留原值时先 `clone`。当前 chunk 足够时直接编码到其中，不分配内存
+                pub fn encode_to_buf_le(self, buf: &mut impl #krate::bytes::BufMut) -> Result<usize, #krate::EncodeError> {
+                    let len = self.encoded_len();
+                    #krate::buf::encode_to_buf(buf, len, |bytes| self.encode_le_into(bytes))
+                }
+                /// 以大端编码写入 `bytes::BufMut`，返回写入的字节数，错误同 `encode_le_into`
+                pub fn encode_to_buf_be(self, buf: &mut impl #krate::bytes::BufMut) -> Result<usize, #krate::EncodeError> {
+                    let len = self.encoded_len();
+                    #krate::buf::encode_to_buf(buf, len, |bytes| self.encode_be_into(bytes))
+                }
+            }
+        }
+    } else {
+        quote::quote!()
+    };
+    let words = match &args.unit {
+        Some(unit) if bytemap.unit > 1 => word_unit::words(&bytemap, unit),
+        _ => quote::quote!(),
+    };
+    let nom = if cfg!(feature = "nom") {
+        nom_parse::parse_fns(&bytemap, nom_read_le, nom_read_be, &field_idents)
+    } else {
+        quote::quote!()
+    };
+    let mut fuzz = proc_macro2::TokenStream::new();
+    if cfg!(feature = "arbitrary") {
+        fuzz.extend(fuzz::bytemap_arbitrary(&bytemap));
+    }
+    if cfg!(feature = "proptest") {
+        fuzz.extend(fuzz::bytemap_proptest(&bytemap));
+    }
 
     quote::quote! {
         #clean
-        impl #impl_generics ::core::convert::TryFrom<::binary::endian::Le<&[u8]>> for #ident #ty_generics #where_clause {
+        #warnings
+        #binary_size
+        #builder
+        #default
+        #partial
+        #schema
+        #c_header
+        #dissector
+        #zerocopy
+        #mmio
+        #fuzz
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Le<&#input_lifetime [u8]>> for #ident #ty_generics #where_clause {
             type Error = ::core::ops::RangeInclusive<usize>;
-            fn try_from(value: ::binary::endian::Le<&[u8]>)->Result<Self, Self::Error> {
+            fn try_from(__value: #krate::endian::Le<&#input_lifetime [u8]>)->Result<Self, Self::Error> {
+                #bytes_read_from_le
                 Ok(Self {
-                    #bytes_read_from_le
+                    #(#field_idents),*
                 })
             }
         }
-        impl #impl_generics ::core::convert::TryFrom<::binary::endian::Be<&[u8]>> for #ident #ty_generics #where_clause {
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Be<&#input_lifetime [u8]>> for #ident #ty_generics #where_clause {
             type Error = ::core::ops::RangeInclusive<usize>;
-            fn try_from(value: ::binary::endian::Be<&[u8]>)->Result<Self, Self::Error> {
+            fn try_from(__value: #krate::endian::Be<&#input_lifetime [u8]>)->Result<Self, Self::Error> {
+                #bytes_read_from_be
                 Ok(Self {
-                    #bytes_read_from_be
+                    #(#field_idents),*
                 })
             }
         }
-        pub struct #le_iter_name #ty_generics {
-            #le_iter_fields
-            _current_idx:usize,
-        }
-        impl #impl_generics ::core::iter::Iterator for #le_iter_name #ty_generics #where_clause {
-            type Item = u8;
-            fn next(&mut self) -> Option<Self::Item> {
-                if self._current_idx > #limit {
-                    return None;
-                }
-                #next_return
-                self._current_idx += 1;
-                return Some(0);
+        #try_decode
+        #buf
+        #nom
+        #words
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 逐步解码，输入不完整时返回还需要的字节数
+            pub fn try_decode<V>(value: V) -> #krate::Decode<Self>
+            where
+                Self: #krate::TryDecode<V>,
+            {
+                <Self as #krate::TryDecode<V>>::try_decode(value)
             }
-        }
-        impl #impl_generics ::binary::endian::IntoLeIter for #ident #ty_generics #where_clause {
-            type Item = u8;
-            type IntoIter = #le_iter_name;
-            fn into_leiter(self) -> Self::IntoIter {
-                #le_iter_name {
-                    #le_iter_fields_into
-                    _current_idx: 0usize,
-                }
+            /// 编码后的字节数
+            pub fn encoded_len(&self) -> usize {
+                let mut _len: usize = #reserved_len;
+                #encode_layout
+                _len
             }
-        }
-        pub struct #be_iter_name #ty_generics {
-            #be_iter_fields
-            _current_idx:usize,
-        }
-        impl #impl_generics ::core::iter::Iterator for #be_iter_name #ty_generics #where_clause {
-            type Item = u8;
-            fn next(&mut self) -> Option<Self::Item> {
-                if self._current_idx > #limit {
-                    return None;
+            /// 以小端编码写入 `buf` 的开头，返回写入的字节数
+            ///
+            /// `buf` 不足时返回所需的字节数；字段的值不能原样解码时（如借用字段与 pos 范围
+            /// 长度不符）返回该字段名，`buf` 保持不变
+            pub fn encode_le_into(self, buf: &mut [u8]) -> Result<usize, #krate::EncodeError> {
+                let mut _len: usize = #reserved_len;
+                #encode_layout
+                #check_fields
+                let __buf = buf
+                    .get_mut(.._len)
+                    .ok_or(#krate::EncodeError::BufferTooSmall { needed: _len })?;
+                __buf.fill(0);
+                #le_encode_fields
+                Ok(_len)
+            }
+            /// 以大端编码写入 `buf` 的开头，返回写入的字节数，错误同 `encode_le_into`
+            pub fn encode_be_into(self, buf: &mut [u8]) -> Result<usize, #krate::EncodeError> {
+                let mut _len: usize = #reserved_len;
+                #encode_layout
+                #check_fields
+                let __buf = buf
+                    .get_mut(.._len)
+                    .ok_or(#krate::EncodeError::BufferTooSmall { needed: _len })?;
+                __buf.fill(0);
+                #be_encode_fields
+                Ok(_len)
+            }
+            /// # Panics
+            ///
+            /// 字段的值不能原样解码时 panic，见 `encode_le_into`
+            pub fn encode_le(self) -> ::std::vec::Vec<u8> {
+                let mut buf = ::std::vec![0u8; self.encoded_len()];
+                if let Err(err) = self.encode_le_into(&mut buf) {
+                    panic!("can not encode `{}`: {:?}", #name, err);
                 }
-                #next_return
-                self._current_idx += 1;
-                return Some(0);
+                buf
             }
-        }
-        impl #impl_generics ::binary::endian::IntoBeIter for #ident #ty_generics #where_clause {
-            type Item = u8;
-            type IntoIter = #be_iter_name;
-            fn into_beiter(self) -> Self::IntoIter {
-                #be_iter_name {
-                    #be_iter_fields_into
-                    _current_idx: 0usize,
+            /// # Panics
+            ///
+            /// 字段的值不能原样解码时 panic，见 `encode_le_into`
+            pub fn encode_be(self) -> ::std::vec::Vec<u8> {
+                let mut buf = ::std::vec![0u8; self.encoded_len()];
+                if let Err(err) = self.encode_be_into(&mut buf) {
+                    panic!("can not encode `{}`: {:?}", #name, err);
                 }
+                buf
             }
         }
+        #iters
     }
     .into()
 }
 
+/// 由 C 结构体声明生成 `#[bytemap]` 结构体，按 C 的对齐规则计算各字段的 pos
+///
+/// ```ignore
+/// bytemap_from_c! {
+///     #[derive(Debug)]
+///     typedef struct __attribute__((packed)) {
+///         uint32_t magic;
+///         uint8_t flags;
+///         uint16_t len[2];
+///     } vendor_header_t;
+/// }
+/// let header = VendorHeader::try_from(Le(bytes))?;
+/// ```
+#[proc_macro]
+pub fn bytemap_from_c(input: TokenStream) -> TokenStream {
+    let declarations = parse_macro_input!(input as CDeclarations);
+    match declarations.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 #[proc_macro_attribute]
 pub fn bitmap(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let types = parse_macro_input!(_attr as ContainerType).types;
+    let ContainerType {
+        types,
+        krate,
+        schema,
+        c_header,
+        dissector,
+        mmio,
+        tlv,
+    } = parse_macro_input!(_attr as ContainerType);
+    if let Some(tlv) = tlv {
+        return syn::Error::new_spanned(tlv.ident, "tlv is only supported by restrict")
+            .to_compile_error()
+            .into();
+    }
     let bitmap = parse_macro_input!(item as BitmapStruct);
+    // 位置不能超出任何一个容器的位宽，须在生成 schema 等附加输出之前检查
+    for field in bitmap.fields.iter() {
+        let (start, end) = match range_from_expr(&field.pos) {
+            Ok(range) => (*range.start() as u32, *range.end() as u32),
+            Err(err) => return err.to_compile_error().into(),
+        };
+        if start > end {
+            return syn::Error::new_spanned(&field.pos, "bit range must not be empty")
+                .to_compile_error()
+                .into();
+        }
+        for ty in types.iter() {
+            let bits = match type_size::known_size(&syn::Type::Path(ty.to_owned())) {
+                Some(size) => size as u32 * 8,
+                None => continue,
+            };
+            if end >= bits {
+                return syn::Error::new_spanned(
+                    &field.pos,
+                    format!("bit {} is out of range for `{}`", end, ty.to_token_stream()),
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    }
     let ident = bitmap.clean_struct.to_owned().ident;
-    let clean = bitmap.clean_struct.to_owned();
+    let mut clean = bitmap.clean_struct.to_owned();
+    match layout_doc::bitmap_doc(&bitmap, &types) {
+        Ok(doc) => clean.attrs.push(doc),
+        Err(err) => return err.to_compile_error().into(),
+    }
     let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let schema = if schema {
+        match schema::bitmap_schema(&bitmap, &types) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let c_header = if c_header {
+        match c_header::bitmap_header(&bitmap, &types) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let dissector = if dissector {
+        match dissector::bitmap_dissector(&bitmap, &types, &krate) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let mut fuzz = proc_macro2::TokenStream::new();
+    if cfg!(feature = "arbitrary") {
+        match fuzz::bitmap_arbitrary(&bitmap, &krate) {
+            Ok(tokens) => fuzz.extend(tokens),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
+    if cfg!(feature = "proptest") {
+        match fuzz::bitmap_proptest(&bitmap, &krate) {
+            Ok(tokens) => fuzz.extend(tokens),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
     let mut bits_read = proc_macro2::TokenStream::new();
+    let mut bits_write = proc_macro2::TokenStream::new();
+    let mut validate_fields = proc_macro2::TokenStream::new();
+    let mut opaque = None;
     for field in bitmap.fields {
         let field_ident = field.ident;
-        let field_pos = field.pos;
+        let target_type = field.target_type;
+        let range = match range_from_expr(&field.pos) {
+            Ok(range) => range,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let (start, end) = (*range.start() as u32, *range.end() as u32);
+        let width = end + 1 - start;
+        let field_pos = quote::quote!(#start..=#end);
+        let signedness = int_signedness(&target_type);
+        // 整数字段的值不能超出其位宽
+        match signedness {
+            Some(false) => validate_fields.extend(quote::quote! {
+                if (self.#field_ident as u128).checked_shr(#width).unwrap_or(0) != 0 {
+                    return false;
+                }
+            }),
+            Some(true) => validate_fields.extend(quote::quote! {
+                let high = (self.#field_ident as i128) >> (#width - 1);
+                if high != 0 && high != -1 {
+                    return false;
+                }
+            }),
+            None => {}
+        }
+        let is_bool = target_type.to_token_stream().to_string() == "bool";
+        let read = if is_bool {
+            quote::quote!(bits.read() != 0)
+        } else if signedness == Some(true) {
+            // 符号扩展
+            let shift = 128 - width;
+            quote::quote!((((bits.read() as i128) << #shift) >> #shift).try_into().map_err(|_| bits.range)?)
+        } else {
+            quote::quote!(bits.read().try_into().map_err(|_| bits.range)?)
+        };
         let field_read = quote::quote! {
             #field_ident: {
-                let bits = value.bits(#field_pos);
-                bits.read().try_into().map_err(|_| bits.range)
-            }?,
+                let bits = #krate::bits::Bits::bits(value, #field_pos);
+                #read
+            },
         };
         bits_read.extend(field_read);
+        // 其他类型的字段只要求 `TryFrom<容器类型>`，不能编码回容器
+        if !is_bool && signedness.is_none() {
+            opaque.get_or_insert(target_type.to_owned());
+        }
+        bits_write.extend(quote::quote! {
+            bits = #krate::bits::Bits::set_bits(bits, #field_pos, value.#field_ident as _);
+        });
     }
+    // 作为 bytemap 字段时使用第一个容器类型
+    let first_type = &types[0];
+    // 所有字段都是 bool 或整数时才能编码回容器
+    let encode = if opaque.is_none() {
+        quote::quote! {
+            #(
+                impl #impl_generics ::core::convert::From<#ident #ty_generics> for #types #where_clause {
+                    fn from(value: #ident #ty_generics) -> Self {
+                        let mut bits: #types = 0;
+                        #bits_write
+                        bits
+                    }
+                }
+            )*
+            impl #impl_generics #krate::endian::IntoLeIter for #ident #ty_generics #where_clause {
+                type Item = u8;
+                type IntoIter = <#first_type as #krate::endian::IntoLeIter>::IntoIter;
+                fn into_leiter(self) -> Self::IntoIter {
+                    #krate::endian::IntoLeIter::into_leiter(<#first_type>::from(self))
+                }
+            }
+            impl #impl_generics #krate::endian::IntoBeIter for #ident #ty_generics #where_clause {
+                type Item = u8;
+                type IntoIter = <#first_type as #krate::endian::IntoBeIter>::IntoIter;
+                fn into_beiter(self) -> Self::IntoIter {
+                    #krate::endian::IntoBeIter::into_beiter(<#first_type>::from(self))
+                }
+            }
+        }
+    } else {
+        quote::quote!()
+    };
+    let register = match (mmio, opaque) {
+        (false, _) => quote::quote!(),
+        (true, Some(ty)) => {
+            return syn::Error::new_spanned(
+                ty,
+                "mmio requires every field to be bool or an integer",
+            )
+            .to_compile_error()
+            .into()
+        }
+        (true, None) => quote::quote! {
+            impl #impl_generics #krate::mmio::Register for #ident #ty_generics #where_clause {
+                type Raw = #first_type;
+                type Error = ::core::ops::RangeInclusive<u32>;
+                fn from_raw(raw: #first_type) -> Result<Self, ::core::ops::RangeInclusive<u32>> {
+                    ::core::convert::TryFrom::try_from(raw)
+                }
+                fn into_raw(self) -> #first_type {
+                    <#first_type>::from(self)
+                }
+                fn merge_into(self, raw: #first_type) -> #first_type {
+                    let value = self;
+                    let mut bits = raw;
+                    #bits_write
+                    bits
+                }
+            }
+        },
+    };
     quote::quote! {
         #clean
         #(
@@ -174,11 +760,40 @@ pub fn bitmap(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             }
         )*
+        impl #impl_generics #krate::BinarySize for #ident #ty_generics #where_clause {
+            const SIZE: usize = <#first_type as #krate::BinarySize>::SIZE;
+        }
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Le<&[u8]>> for #ident #ty_generics #where_clause {
+            type Error = #krate::endian::InvalidBytes;
+            fn try_from(value: #krate::endian::Le<&[u8]>) -> Result<Self, Self::Error> {
+                let bits = <#first_type>::try_from(value)?;
+                Self::try_from(bits).map_err(|_| #krate::endian::InvalidBytes)
+            }
+        }
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Be<&[u8]>> for #ident #ty_generics #where_clause {
+            type Error = #krate::endian::InvalidBytes;
+            fn try_from(value: #krate::endian::Be<&[u8]>) -> Result<Self, Self::Error> {
+                let bits = <#first_type>::try_from(value)?;
+                Self::try_from(bits).map_err(|_| #krate::endian::InvalidBytes)
+            }
+        }
+        impl #impl_generics #krate::Validate for #ident #ty_generics #where_clause {
+            fn validate(&self) -> bool {
+                #validate_fields
+                true
+            }
+        }
+        #encode
+        #register
+        #schema
+        #c_header
+        #dissector
+        #fuzz
     }
     .into()
 }
 
-/// ```
+/// ```ignore
 /// #[restrict(u8,u16)]
 /// enum A {
 /// #[white_list(1,2,3)]
@@ -191,18 +806,115 @@ pub fn bitmap(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///     D4(U),
 /// }
 /// ```
-
 #[proc_macro_attribute]
 pub fn restrict(_attr: TokenStream, _item: TokenStream) -> TokenStream {
     let restrict_enum = parse_macro_input!(_item as RestrictEnum);
-    let clean_enum = restrict_enum.pure_enum;
+    let ContainerType {
+        types: all_type,
+        krate,
+        schema,
+        c_header,
+        dissector,
+        mmio,
+        tlv,
+    } = parse_macro_input!(_attr as ContainerType);
+    let mut clean_enum = restrict_enum.pure_enum;
+    clean_enum
+        .attrs
+        .push(layout_doc::restrict_doc(&restrict_enum.variant, &all_type));
+    // 变体的数据为 TLV 的值而不是类型码，不再生成与容器类型之间的转换
+    if let Some(tlv) = tlv {
+        if schema || c_header || dissector || mmio {
+            return syn::Error::new_spanned(
+                tlv.ident,
+                "tlv can not be used together with schema, c_header, dissector or mmio",
+            )
+            .to_compile_error()
+            .into();
+        }
+        return match tlv::restrict_tlv(
+            &clean_enum,
+            &restrict_enum.variant,
+            &all_type[0],
+            &tlv,
+            &krate,
+        ) {
+            Ok(tokens) => quote::quote!(#clean_enum #tokens).into(),
+            Err(err) => err.to_compile_error().into(),
+        };
+    }
     let (impl_generics, ty_generics, where_clause) = clean_enum.generics.split_for_impl();
     let enum_ident = clean_enum.ident.to_owned();
-    let all_type = (parse_macro_input!(_attr as ContainerType)).types;
+    let schema = if schema {
+        schema::restrict_schema(&clean_enum, &restrict_enum.variant, &all_type)
+    } else {
+        quote::quote!()
+    };
+    let c_header = if c_header {
+        match c_header::restrict_header(&clean_enum, &restrict_enum.variant, &all_type) {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let dissector = if dissector {
+        match dissector::restrict_dissector(&clean_enum, &restrict_enum.variant, &all_type, &krate)
+        {
+            Ok(tokens) => tokens,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote::quote!()
+    };
+    let mut fuzz = proc_macro2::TokenStream::new();
+    if cfg!(feature = "arbitrary") {
+        fuzz.extend(fuzz::restrict_arbitrary(
+            &clean_enum,
+            &restrict_enum.variant,
+            &krate,
+        ));
+    }
+    if cfg!(feature = "proptest") {
+        fuzz.extend(fuzz::restrict_proptest(
+            &clean_enum,
+            &restrict_enum.variant,
+            &krate,
+        ));
+    }
+    // 作为 bytemap 字段时使用第一个容器类型
+    let first_type = all_type[0].to_owned();
     let mut match_expr = proc_macro2::TokenStream::new();
+    let mut validate_variants = proc_macro2::TokenStream::new();
+    let mut into_container = proc_macro2::TokenStream::new();
+    let mut payload_types = Vec::new();
     restrict_enum.variant.into_iter().for_each(|x| {
         let ident = x.ident;
         let expr = x.restrict.white_list;
+        // 编码时，无数据的变体取 white_list 中的第一个值
+        let first = match &expr[0] {
+            syn::Expr::Range(range) => range.from.to_token_stream(),
+            other => other.to_token_stream(),
+        };
+        into_container.extend(match &x.target_type {
+            Some(ty) => {
+                payload_types.push(ty.to_owned());
+                quote::quote! {
+                    #enum_ident::#ident(value) => ::core::convert::From::from(value),
+                }
+            }
+            None => quote::quote! {
+                #enum_ident::#ident => (#first) as _,
+            },
+        });
+        // 携带原生整数的变体，其值必须在 white_list 中
+        if let Some(ty) = &x.target_type {
+            if int_signedness(ty).is_some() {
+                validate_variants.extend(quote::quote! {
+                    Self::#ident(value) => matches!(*value as i128, #(#expr)|*),
+                });
+            }
+        }
         let tmp = match x.target_type {
             Some(ty) => {
                 quote::quote! {
@@ -217,6 +929,70 @@ pub fn restrict(_attr: TokenStream, _item: TokenStream) -> TokenStream {
         };
         match_expr.extend(tmp);
     });
+    // 只有数据都能无损转换为容器类型时才能编码，如 `TryFrom<u8>` 的自定义类型或更宽的整数不能
+    let into_types = all_type
+        .iter()
+        .filter(|ty| {
+            let ty = syn::Type::Path((*ty).to_owned());
+            payload_types.iter().all(|x| lossless_into(x, &ty))
+        })
+        .collect::<Vec<_>>();
+    let mut encode = quote::quote! {
+        #(
+            impl #impl_generics ::core::convert::From<#enum_ident #ty_generics> for #into_types #where_clause {
+                fn from(value: #enum_ident #ty_generics) -> Self {
+                    match value {
+                        #into_container
+                    }
+                }
+            }
+        )*
+    };
+    // 作为 bytemap 字段时按第一个容器类型编码
+    if into_types.first() == all_type.first().as_ref() {
+        encode.extend(quote::quote! {
+            impl #impl_generics #krate::endian::IntoLeIter for #enum_ident #ty_generics #where_clause {
+                type Item = u8;
+                type IntoIter = <#first_type as #krate::endian::IntoLeIter>::IntoIter;
+                fn into_leiter(self) -> Self::IntoIter {
+                    #krate::endian::IntoLeIter::into_leiter(<#first_type>::from(self))
+                }
+            }
+            impl #impl_generics #krate::endian::IntoBeIter for #enum_ident #ty_generics #where_clause {
+                type Item = u8;
+                type IntoIter = <#first_type as #krate::endian::IntoBeIter>::IntoIter;
+                fn into_beiter(self) -> Self::IntoIter {
+                    #krate::endian::IntoBeIter::into_beiter(<#first_type>::from(self))
+                }
+            }
+        });
+    }
+    if mmio {
+        let first = syn::Type::Path(first_type.to_owned());
+        if let Some(ty) = payload_types.iter().find(|x| !lossless_into(x, &first)) {
+            return syn::Error::new_spanned(
+                ty,
+                format!(
+                    "mmio requires variant data that converts losslessly to `{}`",
+                    first_type.to_token_stream()
+                ),
+            )
+            .to_compile_error()
+            .into();
+        }
+        encode.extend(quote::quote! {
+            impl #impl_generics #krate::mmio::Register for #enum_ident #ty_generics #where_clause {
+                type Raw = #first_type;
+                type Error = #first_type;
+                fn from_raw(raw: #first_type) -> Result<Self, #first_type> {
+                    ::core::convert::TryFrom::try_from(raw)
+                }
+                fn into_raw(self) -> #first_type {
+                    <#first_type>::from(self)
+                }
+            }
+        });
+    }
     quote::quote! {
         #clean_enum
         #(
@@ -230,6 +1006,37 @@ pub fn restrict(_attr: TokenStream, _item: TokenStream) -> TokenStream {
                 }
             }
         )*
+        #encode
+        impl #impl_generics #krate::BinarySize for #enum_ident #ty_generics #where_clause {
+            const SIZE: usize = <#first_type as #krate::BinarySize>::SIZE;
+        }
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Le<&[u8]>> for #enum_ident #ty_generics #where_clause {
+            type Error = #krate::endian::InvalidBytes;
+            fn try_from(value: #krate::endian::Le<&[u8]>) -> Result<Self, Self::Error> {
+                let value = <#first_type>::try_from(value)?;
+                Self::try_from(value).map_err(|_| #krate::endian::InvalidBytes)
+            }
+        }
+        impl #impl_generics ::core::convert::TryFrom<#krate::endian::Be<&[u8]>> for #enum_ident #ty_generics #where_clause {
+            type Error = #krate::endian::InvalidBytes;
+            fn try_from(value: #krate::endian::Be<&[u8]>) -> Result<Self, Self::Error> {
+                let value = <#first_type>::try_from(value)?;
+                Self::try_from(value).map_err(|_| #krate::endian::InvalidBytes)
+            }
+        }
+        impl #impl_generics #krate::Validate for #enum_ident #ty_generics #where_clause {
+            #[allow(unreachable_patterns)]
+            fn validate(&self) -> bool {
+                match self {
+                    #validate_variants
+                    _ => true,
+                }
+            }
+        }
+        #schema
+        #c_header
+        #dissector
+        #fuzz
     }
     .into()
 }
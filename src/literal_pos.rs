@@ -31,19 +31,21 @@ pub(crate) fn range_from_expr(expr: &Expr) -> Result<RangeInclusive<usize>> {
             Err(Error::new_spanned(expr, ""))?
         };
         if let RangeLimits::HalfOpen(_) = range.limits {
-            hi_value -= 1
+            hi_value = hi_value
+                .checked_sub(1)
+                .ok_or(Error::new_spanned(expr, "The range must not be empty"))?;
         }
-        return Ok(lo_value..=hi_value);
+        Ok(lo_value..=hi_value)
     } else if let Expr::Lit(ExprLit {
         lit: Int(int_lit), ..
     }) = expr
     {
         let lit_value = int_lit.base10_parse::<usize>()?;
-        return Ok(lit_value..=lit_value);
+        Ok(lit_value..=lit_value)
     } else {
-        return Err(Error::new_spanned(
+        Err(Error::new_spanned(
             expr.to_token_stream(),
             "Only literal range or literal int is suppored",
-        ));
+        ))
     }
 }
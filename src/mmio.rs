@@ -0,0 +1,159 @@
+//! `mmio` 参数：生成以基地址访问的寄存器块 `{Name}Mmio`。
+//!
+//! ```ignore
+//! #[bytemap(mmio)]
+//! struct Uart {
+//!     #[pos(0..=3)]
+//!     ctrl: Ctrl, // #[bitmap(u32, mmio)]
+//!     #[pos(4..=7)]
+//!     data: u32,
+//! }
+//! let uart = unsafe { UartMmio::new(0x4000_0000 as *mut u8) };
+//! uart.write_data(0x41);
+//! uart.modify_ctrl(|ctrl| ctrl.enable = true)?;
+//! ```
+//!
+//! 整数字段直接读写；其他类型需实现 `mmio::Register`（bitmap 与 restrict 需指定 `mmio` 参数），
+//! 读取时可能失败。
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Error, Result, Type};
+
+use crate::bytemap_struct::BytemapStruct;
+use crate::type_size::{int_signedness, known_size};
+
+pub(crate) fn register_block(bytemap: &BytemapStruct) -> Result<TokenStream> {
+    let clean = &bytemap.clean_struct;
+    let krate = &bytemap.krate;
+    if let Some(param) = clean.generics.params.first() {
+        return Err(Error::new_spanned(param, "mmio does not support generics"));
+    }
+    let vis = &clean.vis;
+    let block_ident = format_ident!("{}Mmio", clean.ident);
+    let mut methods = TokenStream::new();
+    let mut asserts = TokenStream::new();
+    let mut size = bytemap.reserved_len();
+    let mut align = 1usize;
+    for field in bytemap.fields.iter() {
+        if let Some(condition) = &field.condition {
+            return Err(Error::new_spanned(
+                condition,
+                "mmio does not support conditional fields",
+            ));
+        }
+        let range = field.pos_value.to_owned().ok_or(Error::new_spanned(
+            &field.pos,
+            "mmio requires a literal position",
+        ))?;
+        let ty = &field.target_type;
+        if let Type::Array(_) = ty {
+            return Err(Error::new_spanned(ty, "mmio register must not be an array"));
+        }
+        let (start, width) = (*range.start(), range.end() + 1 - range.start());
+        // volatile 访问要求寄存器按自身宽度对齐
+        if !width.is_power_of_two() || width > 8 || start % width != 0 {
+            return Err(Error::new_spanned(
+                &field.pos,
+                format!(
+                    "register of {} bytes at offset {} is not aligned",
+                    width, start
+                ),
+            ));
+        }
+        size = size.max(start + width);
+        align = align.max(width);
+
+        let field_ident = &field.ident;
+        let name = field_ident.to_string();
+        let read = format_ident!("read_{}", field_ident);
+        let write = format_ident!("write_{}", field_ident);
+        let ptr = quote!(self.base.add(#start));
+        let is_int = int_signedness(ty).is_some() && known_size(ty).is_some_and(|x| x <= 8);
+        if is_int {
+            let read_doc = format!("volatile 读取 `{}`", name);
+            let write_doc = format!("volatile 写入 `{}`", name);
+            methods.extend(quote! {
+                #[doc = #read_doc]
+                pub fn #read(&self) -> #ty {
+                    // SAFETY: 由 new 的调用者保证地址有效且对齐
+                    unsafe { ::core::ptr::read_volatile(#ptr as *const #ty) }
+                }
+                #[doc = #write_doc]
+                pub fn #write(&self, value: #ty) {
+                    // SAFETY: 由 new 的调用者保证地址有效且对齐
+                    unsafe { ::core::ptr::write_volatile(#ptr as *mut #ty, value) }
+                }
+            });
+            continue;
+        }
+        let register = quote!(<#ty as #krate::mmio::Register>);
+        let read_raw = format_ident!("read_{}_raw", field_ident);
+        let modify = format_ident!("modify_{}", field_ident);
+        let read_doc = format!("volatile 读取 `{}`，寄存器的值无效时返回原始值的错误", name);
+        let read_raw_doc = format!("volatile 读取 `{}` 的原始值", name);
+        let write_doc = format!("volatile 写入 `{}`，未被字段覆盖的位写为 0", name);
+        let modify_doc = format!(
+            "读取 `{}`，经 `f` 修改后写回，未被字段覆盖的位保持不变",
+            name
+        );
+        asserts.extend(quote! {
+            assert!(::core::mem::size_of::<#register::Raw>() == #width);
+        });
+        methods.extend(quote! {
+            #[doc = #read_raw_doc]
+            pub fn #read_raw(&self) -> #register::Raw {
+                // SAFETY: 由 new 的调用者保证地址有效且对齐，宽度已在编译期断言
+                unsafe { ::core::ptr::read_volatile(#ptr as *const #register::Raw) }
+            }
+            #[doc = #read_doc]
+            pub fn #read(&self) -> ::core::result::Result<#ty, #register::Error> {
+                #register::from_raw(self.#read_raw())
+            }
+            #[doc = #write_doc]
+            pub fn #write(&self, value: #ty) {
+                let raw = #register::into_raw(value);
+                // SAFETY: 由 new 的调用者保证地址有效且对齐，宽度已在编译期断言
+                unsafe { ::core::ptr::write_volatile(#ptr as *mut #register::Raw, raw) }
+            }
+            #[doc = #modify_doc]
+            pub fn #modify(&self, f: impl ::core::ops::FnOnce(&mut #ty)) -> ::core::result::Result<(), #register::Error> {
+                let raw = self.#read_raw();
+                let mut value = #register::from_raw(raw)?;
+                f(&mut value);
+                let raw = #register::merge_into(value, raw);
+                // SAFETY: 由 new 的调用者保证地址有效且对齐，宽度已在编译期断言
+                unsafe { ::core::ptr::write_volatile(#ptr as *mut #register::Raw, raw) };
+                Ok(())
+            }
+        });
+    }
+    let doc = format!("以基地址访问的 [`{}`] 寄存器块", clean.ident);
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy)]
+        #vis struct #block_ident {
+            base: *mut u8,
+        }
+        const _: () = {
+            #asserts
+        };
+        impl #block_ident {
+            /// 寄存器块的字节数
+            pub const SIZE: usize = #size;
+            /// 基地址所需的对齐
+            pub const ALIGN: usize = #align;
+            /// # Safety
+            ///
+            /// 在返回值存在期间，`base` 必须指向至少 `SIZE` 字节可读写的内存，且按 `ALIGN` 对齐
+            pub unsafe fn new(base: *mut u8) -> Self {
+                debug_assert!(base as usize % Self::ALIGN == 0);
+                Self { base }
+            }
+            pub fn as_ptr(&self) -> *mut u8 {
+                self.base
+            }
+            #methods
+        }
+    })
+}
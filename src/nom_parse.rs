@@ -0,0 +1,140 @@
+//! `nom` feature：生成可与 nom 组合子混用的 `parse_le`、`parse_be`。
+//!
+//! ```ignore
+//! #[bytemap]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//! }
+//! let (rest, header) = Header::parse_le::<VerboseError<_>>(input)?;
+//! ```
+//!
+//! 解析失败时以出错字段名与结构体名作为 context；输入不足时返回 `ErrorKind::Eof`，
+//! 与 `nom::number::complete` 一致，不返回 `Err::Incomplete`。
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+use crate::bytemap_struct::{ByteField, BytemapStruct};
+use crate::field_encoding::Endian;
+
+/// 与 `TryFrom` 相同的字段解码，失败时返回 `(字段名, 范围)`
+pub(crate) fn read_field(field: &ByteField, krate: &syn::Path) -> (TokenStream, TokenStream) {
+    let field_ident = &field.ident;
+    let name = field_ident.to_string();
+    let pos_ident = field.pos_ident();
+    let target_type = &field.target_type;
+    let raw = quote!(__value.0.get(#pos_ident.clone()).ok_or((#name, #pos_ident.clone()))?);
+    let err = quote!((#name, #pos_ident.clone()));
+    let present = match field.condition {
+        Some(_) => quote!(#field_ident.is_some()),
+        None => quote!(true),
+    };
+    let layout = field.layout(krate);
+    let advance = field.advance(present);
+    let check_magic = match &field.magic {
+        Some(magic) => quote! {
+            if #field_ident != #magic {
+                return Err((#name, #pos_ident));
+            }
+        },
+        None => quote!(),
+    };
+    let read = |endian| {
+        let mut decode =
+            field
+                .encoding
+                .decode(target_type, raw.clone(), err.clone(), endian, krate);
+        if let Some(condition) = &field.condition {
+            decode = quote!(if #condition { Some(#decode) } else { None });
+        }
+        quote! {
+            #layout
+            let #field_ident = #decode;
+            #check_magic
+            #advance
+        }
+    };
+    (read(Endian::Le), read(Endian::Be))
+}
+
+pub(crate) fn parse_fns(
+    bytemap: &BytemapStruct,
+    read_le: TokenStream,
+    read_be: TokenStream,
+    field_idents: &[Ident],
+) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let ident = &clean.ident;
+    let name = ident.to_string();
+    let krate = &bytemap.krate;
+    let nom = quote!(#krate::nom);
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    // 借用字段的生命周期与输入切片一致
+    let (lifetime, lifetime_param) = match clean.generics.lifetimes().next() {
+        Some(def) => (def.lifetime.to_owned(), quote!()),
+        None => {
+            let lifetime = syn::Lifetime::new("'__input", proc_macro2::Span::call_site());
+            (lifetime.to_owned(), quote!(#lifetime,))
+        }
+    };
+    let parse = |fn_ident: Ident, wrapper: TokenStream, read: TokenStream, doc: &str| {
+        quote! {
+            #[doc = #doc]
+            pub fn #fn_ident<#lifetime_param __E>(
+                input: &#lifetime [u8],
+            ) -> #nom::IResult<&#lifetime [u8], Self, __E>
+            where
+                __E: #nom::error::ParseError<&#lifetime [u8]> + #nom::error::ContextError<&#lifetime [u8]>,
+            {
+                let __read = || -> ::core::result::Result<Self, (&'static str, ::core::ops::RangeInclusive<usize>)> {
+                    let __value = #krate::endian::#wrapper(input);
+                    #read
+                    Ok(Self {
+                        #(#field_idents),*
+                    })
+                };
+                match __read() {
+                    Ok(decoded) => match input.get(decoded.encoded_len()..) {
+                        Some(rest) => Ok((rest, decoded)),
+                        // 末尾的 reserved 字节不经字段读取，输入可能在其之前结束
+                        None => {
+                            let at = &input[input.len()..];
+                            let err = __E::from_error_kind(at, #nom::error::ErrorKind::Eof);
+                            Err(#nom::Err::Error(__E::add_context(input, #name, err)))
+                        }
+                    },
+                    Err((field, range)) => {
+                        let kind = match *range.end() >= input.len() {
+                            true => #nom::error::ErrorKind::Eof,
+                            false => #nom::error::ErrorKind::Verify,
+                        };
+                        let at = &input[(*range.start()).min(input.len())..];
+                        let err = __E::from_error_kind(at, kind);
+                        let err = __E::add_context(at, field, err);
+                        Err(#nom::Err::Error(__E::add_context(input, #name, err)))
+                    }
+                }
+            }
+        }
+    };
+    let parse_le = parse(
+        format_ident!("parse_le"),
+        quote!(Le),
+        read_le,
+        "以 nom 解析小端编码，失败时以字段名作为 context",
+    );
+    let parse_be = parse(
+        format_ident!("parse_be"),
+        quote!(Be),
+        read_be,
+        "以 nom 解析大端编码，失败时以字段名作为 context",
+    );
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #parse_le
+            #parse_be
+        }
+    }
+}
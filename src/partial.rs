@@ -0,0 +1,242 @@
+//! ```ignore
+//! #[bytemap(packed, partial)]
+//! #[derive(Debug)]
+//! struct Header {
+//!     kind: u8,
+//!     #[when(kind == 1)]
+//!     ext: Option<u16>,
+//!     len: u32,
+//! }
+//! // 输入被截断时，已完整出现的字段仍可取得
+//! let partial = Header::decode_partial(Le(&[1u8, 2, 0][..]));
+//! assert_eq!(partial.kind, Some(1));
+//! assert_eq!(partial.ext, Some(Some(2)));
+//! assert_eq!(partial.len, None);
+//! ```
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::Ident;
+
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::Endian;
+
+/// 条件表达式中引用到的标识符
+fn referenced_idents(tokens: TokenStream, idents: &mut Vec<Ident>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident),
+            TokenTree::Group(group) => referenced_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+/// 按 `endian` 依次解码每个字段并写入 `__partial`，失败的字段记录到 `__errors`，
+/// 无法确定后续字段位置时停止
+fn decode_fields(bytemap: &BytemapStruct, endian: Endian) -> TokenStream {
+    let krate = &bytemap.krate;
+    let mut decoded = Vec::<&Ident>::new();
+    let mut fields = TokenStream::new();
+    let mut stops = false;
+    for field in bytemap.fields.iter() {
+        let field_ident = &field.ident;
+        let pos_ident = field.pos_ident();
+        let target_type = &field.target_type;
+        let raw = quote!(__value.0.get(#pos_ident.clone()).ok_or(#pos_ident.clone())?);
+        let err = quote!(#pos_ident.clone());
+        let decode = field.encoding.decode(target_type, raw, err, endian, krate);
+        let check_magic = match &field.magic {
+            Some(magic) => quote! {
+                if #field_ident != #magic {
+                    return Err(#pos_ident);
+                }
+            },
+            None => quote!(),
+        };
+        let layout = field.layout(krate);
+        let field_name = field_ident.to_string();
+        let decode = quote! {
+            match (|| -> ::core::result::Result<#target_type, ::core::ops::RangeInclusive<usize>> {
+                let #field_ident = #decode;
+                #check_magic
+                Ok(#field_ident)
+            })() {
+                Ok(value) => Some(value),
+                Err(range) => {
+                    __errors.push(#krate::FieldError {
+                        field: #field_name,
+                        range,
+                    });
+                    None
+                }
+            }
+        };
+        match &field.condition {
+            Some(condition) => {
+                // 条件只能在其引用的字段均已解码时求值
+                let mut idents = Vec::new();
+                referenced_idents(condition.to_token_stream(), &mut idents);
+                let refs = decoded
+                    .iter()
+                    .filter(|x| idents.contains(x))
+                    .collect::<Vec<_>>();
+                let present_ident = format_ident!("__present_{}", field_ident);
+                fields.extend(quote! {
+                    #layout
+                    let #present_ident = (|| Some({
+                        #(let #refs = __partial.#refs.clone()?;)*
+                        #condition
+                    }))();
+                    __partial.#field_ident = match #present_ident {
+                        Some(true) => #decode.map(Some),
+                        Some(false) => Some(None),
+                        None => None,
+                    };
+                });
+                if let Some(before) = &field.cursor_before {
+                    let next_ident = format_ident!("__next_{}", field_ident);
+                    fields.extend(quote! {
+                        let #next_ident = match #present_ident {
+                            Some(true) => *#pos_ident.end() + 1,
+                            Some(false) => #before,
+                            None => break 'partial,
+                        };
+                    });
+                    stops = true;
+                }
+            }
+            None => fields.extend(quote! {
+                #layout
+                __partial.#field_ident = #decode;
+            }),
+        }
+        decoded.push(field_ident);
+    }
+    if stops {
+        quote!('partial: { #fields })
+    } else {
+        fields
+    }
+}
+
+/// `{Name}Partial`：每个字段以 `Option` 表示是否成功解码，条件字段为 `Option<Option<T>>`。
+/// `accumulate` 时额外生成 `DecodeAll` 实现，返回所有失败的字段。
+pub(crate) fn partial(bytemap: &BytemapStruct, accumulate: bool) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let krate = &bytemap.krate;
+    let ident = &clean.ident;
+    let vis = &clean.vis;
+    let generics = &clean.generics;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let partial_ident = format_ident!("{}Partial", ident);
+    let input_lifetime = clean.generics.lifetimes().next().map(|x| &x.lifetime);
+    // 与原结构体相同的 derive
+    let derives = clean
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .collect::<Vec<_>>();
+
+    let mut partial_fields = TokenStream::new();
+    let mut field_idents = Vec::new();
+    for field in bytemap.fields.iter() {
+        let field_ident = &field.ident;
+        let target_type = &field.target_type;
+        let ty = match field.condition {
+            Some(_) => quote!(Option<Option<#target_type>>),
+            None => quote!(Option<#target_type>),
+        };
+        partial_fields.extend(quote!(pub #field_ident: #ty,));
+        field_idents.push(field_ident);
+    }
+    let mut tokens = quote! {
+        #(#derives)*
+        #vis struct #partial_ident #generics #where_clause {
+            #partial_fields
+        }
+    };
+    for (endian, wrapper, decode_ident) in [
+        (
+            Endian::Le,
+            quote!(#krate::endian::Le),
+            format_ident!("__decode_le"),
+        ),
+        (
+            Endian::Be,
+            quote!(#krate::endian::Be),
+            format_ident!("__decode_be"),
+        ),
+    ] {
+        let decode = decode_fields(bytemap, endian);
+        tokens.extend(quote! {
+            impl #impl_generics #partial_ident #ty_generics #where_clause {
+                #[doc(hidden)]
+                #[allow(clippy::redundant_closure_call)]
+                pub fn #decode_ident(
+                    __value: #wrapper<&#input_lifetime [u8]>,
+                    __errors: &mut ::std::vec::Vec<#krate::FieldError>,
+                ) -> Self {
+                    let mut __partial = #partial_ident {
+                        #(#field_idents: None,)*
+                    };
+                    #decode
+                    __partial
+                }
+            }
+            impl #impl_generics ::core::convert::From<#wrapper<&#input_lifetime [u8]>> for #partial_ident #ty_generics #where_clause {
+                fn from(value: #wrapper<&#input_lifetime [u8]>) -> Self {
+                    Self::#decode_ident(value, &mut ::std::vec::Vec::new())
+                }
+            }
+        });
+        if accumulate {
+            tokens.extend(quote! {
+                impl #impl_generics #krate::DecodeAll<#wrapper<&#input_lifetime [u8]>> for #ident #ty_generics #where_clause {
+                    fn decode_all(
+                        value: #wrapper<&#input_lifetime [u8]>,
+                    ) -> ::core::result::Result<Self, ::std::vec::Vec<#krate::FieldError>> {
+                        let mut errors = ::std::vec::Vec::new();
+                        let partial = #partial_ident::#decode_ident(value, &mut errors);
+                        match partial {
+                            #partial_ident {
+                                #(#field_idents: Some(#field_idents),)*
+                            } if errors.is_empty() => Ok(Self {
+                                #(#field_idents),*
+                            }),
+                            _ => Err(errors),
+                        }
+                    }
+                }
+            });
+        }
+    }
+    let decode_all = if accumulate {
+        quote! {
+            /// 解码所有字段，失败时返回全部失败的字段及其范围
+            pub fn decode_all<V>(
+                value: V,
+            ) -> ::core::result::Result<Self, ::std::vec::Vec<#krate::FieldError>>
+            where
+                Self: #krate::DecodeAll<V>,
+            {
+                <Self as #krate::DecodeAll<V>>::decode_all(value)
+            }
+        }
+    } else {
+        quote!()
+    };
+    tokens.extend(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 宽松解码，截断或非法的字段为 None，其余字段照常解码
+            pub fn decode_partial<V>(value: V) -> #partial_ident #ty_generics
+            where
+                V: ::core::convert::Into<#partial_ident #ty_generics>,
+            {
+                value.into()
+            }
+            #decode_all
+        }
+    });
+    tokens
+}
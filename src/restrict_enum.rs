@@ -3,12 +3,13 @@ use syn::{
     parse::Parse, punctuated::Punctuated, Data, DeriveInput, Error, Expr, Result, Token, Type,
 };
 
-const TOP_LEVEL_PATH: &'static str = "restrict";
-const SECOND_LEVEL_PATH: &'static str = "white_list";
+use crate::diagnostics::check_attr_typos;
+
+const TOP_LEVEL_PATH: &str = "restrict";
+const SECOND_LEVEL_PATH: &str = "white_list";
 
 /// (1,2,3,4..=5)
 /// 以 , 分割的字面量数值表达式，且不重合
-
 pub(crate) struct AllowedRange {
     pub(crate) white_list: Vec<Expr>,
 }
@@ -33,6 +34,7 @@ pub(crate) struct RestrictVariant {
 impl Parse for RestrictVariant {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let variant = input.parse::<syn::Variant>()?;
+        check_attr_typos(&variant.attrs, &[SECOND_LEVEL_PATH])?;
         if variant.fields.len() > 1 {
             return Err(Error::new_spanned(
                 variant.to_token_stream(),
@@ -41,7 +43,7 @@ impl Parse for RestrictVariant {
         }
         let mut target_type = None;
         for field in variant.fields.iter() {
-            if field.ident != None {
+            if field.ident.is_some() {
                 return Err(Error::new_spanned(
                     field.to_token_stream(),
                     "Only tuple is supported",
@@ -0,0 +1,592 @@
+//! `schema` 参数：导出 Kaitai Struct（.ksy）与中立的 JSON 布局描述。
+//!
+//! ```ignore
+//! #[bytemap(schema)]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//! }
+//! std::fs::write("header.ksy", Header::KSY_LE)?;
+//! std::fs::write("header.json", Header::SCHEMA_JSON)?;
+//! ```
+//!
+//! 嵌套的 bitmap、restrict 或 bytemap 字段以 snake_case 类型名引用，并列入 `meta/imports`。
+//! Kaitai 无法引用 Rust 常量：white_list 含非字面量界的变体没有 `is_` 实例，
+//! 非字面量的 `padded` 填充字节不生成 `pad-right`；JSON 中以表达式字符串表示。
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{Expr, ExprLit, ExprRange, ExprUnary, Lit, RangeLimits, Type, TypePath, UnOp};
+
+use crate::bitmap_struct::BitmapStruct;
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::{Endian, FieldEncoding, PrefixItem, Terminator, VarintKind};
+use crate::literal_pos::range_from_expr;
+use crate::restrict_enum::RestrictVariant;
+use crate::type_size::{known_size, literal_usize};
+
+/// `FooBar` => `foo_bar`
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+pub(crate) fn tokens_string(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string()
+}
+
+/// 去掉类型中多余的空格，`& 'a str` => `&'a str`
+pub(crate) fn type_string(ty: &impl ToTokens) -> String {
+    let tokens = tokens_string(ty);
+    let chars = tokens.chars().collect::<Vec<_>>();
+    let is_word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            **c != ' '
+                || (is_word(i.checked_sub(1).and_then(|i| chars.get(i)))
+                    && is_word(chars.get(i + 1)))
+        })
+        .map(|(_, c)| c)
+        .collect()
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_option(value: Option<usize>) -> String {
+    value.map_or("null".to_owned(), |x| x.to_string())
+}
+
+/// Rust 条件表达式转换为 Kaitai 表达式
+fn ksy_expr(expr: &Expr) -> String {
+    tokens_string(expr)
+        .replace("&&", "and")
+        .replace("||", "or")
+        .replace("! ", "not ")
+}
+
+/// `b"..."` 或 `*b"..."` 形式的 magic
+fn byte_string(expr: &Expr) -> Option<Vec<u8>> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::ByteStr(bytes),
+            ..
+        }) => Some(bytes.value()),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Deref(_),
+            expr,
+            ..
+        }) => byte_string(expr),
+        _ => None,
+    }
+}
+
+pub(crate) fn int_value(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse::<i128>().ok(),
+        Expr::Lit(ExprLit {
+            lit: Lit::Byte(byte),
+            ..
+        }) => Some(byte.value() as i128),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => int_value(expr).map(|x| -x),
+        Expr::Paren(paren) => int_value(&paren.expr),
+        _ => None,
+    }
+}
+
+/// white_list 中的一项，返回上下界，`None` 表示无界
+pub(crate) fn white_list_bounds(expr: &Expr) -> (Option<String>, Option<String>) {
+    let value = |expr: &Expr| int_value(expr).map_or(tokens_string(expr), |x| x.to_string());
+    match expr {
+        Expr::Range(ExprRange {
+            from, to, limits, ..
+        }) => {
+            let hi = match (limits, to.as_deref()) {
+                (RangeLimits::HalfOpen(_), Some(to)) => Some(match int_value(to) {
+                    Some(to) => (to - 1).to_string(),
+                    None => format!("{} - 1", value(to)),
+                }),
+                (_, to) => to.map(value),
+            };
+            (from.as_deref().map(value), hi)
+        }
+        _ => {
+            let value = value(expr);
+            (Some(value.to_owned()), Some(value))
+        }
+    }
+}
+
+/// 原生整数与浮点数对应的 Kaitai 类型
+fn ksy_primitive(ident: &str) -> Option<&'static str> {
+    Some(match ident {
+        "u8" | "bool" => "u1",
+        "u16" => "u2",
+        "u32" => "u4",
+        "u64" => "u8",
+        "i8" => "s1",
+        "i16" => "s2",
+        "i32" => "s4",
+        "i64" => "s8",
+        "f32" => "f4",
+        "f64" => "f8",
+        _ => return None,
+    })
+}
+
+/// 字段在 Kaitai seq 中的属性（不含 id），以及引用到的自定义类型
+fn ksy_field(
+    ty: &Type,
+    encoding: &FieldEncoding,
+    len: &TokenStream,
+    imports: &mut Vec<String>,
+) -> Vec<(String, String)> {
+    let size = tokens_string(len);
+    match encoding {
+        // Kaitai 的 vlq_base128_le 即 LEB128，有符号值由其 value_signed 给出
+        FieldEncoding::Varint(_) => {
+            let import = "/common/vlq_base128_le".to_owned();
+            if !imports.contains(&import) {
+                imports.push(import);
+            }
+            vec![("type".to_owned(), "vlq_base128_le".to_owned())]
+        }
+        // 长度由前一个 seq 项 `{id}_len` 给出，见 bytemap_schema
+        FieldEncoding::Prefixed(prefix) => match &prefix.item {
+            PrefixItem::Bytes => vec![("size".to_owned(), size)],
+            PrefixItem::Str | PrefixItem::String => vec![
+                ("size".to_owned(), size),
+                ("type".to_owned(), "str".to_owned()),
+                ("encoding".to_owned(), "UTF-8".to_owned()),
+            ],
+            PrefixItem::Vec(elem) => {
+                let mut attrs = ksy_field(elem, &FieldEncoding::Native, &quote!(), imports);
+                attrs.push(("repeat".to_owned(), "expr".to_owned()));
+                attrs.push(("repeat-expr".to_owned(), size));
+                attrs
+            }
+        },
+        FieldEncoding::Borrowed {
+            is_str, terminator, ..
+        } => {
+            let mut attrs = vec![("size".to_owned(), size)];
+            if *is_str {
+                attrs.push(("type".to_owned(), "str".to_owned()));
+                attrs.push(("encoding".to_owned(), "UTF-8".to_owned()));
+            }
+            match terminator {
+                Terminator::Full => {}
+                // cstr 可以占满整个范围而没有 NUL
+                Terminator::Nul => {
+                    attrs.push(("terminator".to_owned(), "0".to_owned()));
+                    attrs.push(("eos-error".to_owned(), "false".to_owned()));
+                }
+                // 非字面量的填充字节无法在 Kaitai 中表示，保留填充
+                Terminator::Pad(pad) => {
+                    if let Some(pad) = int_value(pad) {
+                        attrs.push(("pad-right".to_owned(), pad.to_string()));
+                    }
+                }
+            }
+            attrs
+        }
+        FieldEncoding::Utf16(endian) => {
+            let encoding = match endian {
+                Some(Endian::Be) => "UTF-16BE",
+                _ => "UTF-16LE",
+            };
+            vec![
+                ("type".to_owned(), "str".to_owned()),
+                ("size".to_owned(), size),
+                ("encoding".to_owned(), encoding.to_owned()),
+                ("terminator".to_owned(), "0".to_owned()),
+            ]
+        }
+        FieldEncoding::Native => match ty {
+            Type::Array(array) if matches!(known_size(&array.elem), Some(1)) => {
+                vec![("size".to_owned(), size)]
+            }
+            Type::Array(array) => {
+                let mut attrs = ksy_field(&array.elem, encoding, &quote!(), imports);
+                attrs.push(("repeat".to_owned(), "expr".to_owned()));
+                attrs.push(("repeat-expr".to_owned(), tokens_string(&array.len)));
+                attrs
+            }
+            Type::Path(path) => {
+                let ident = path.path.segments.last().map(|x| x.ident.to_string());
+                match ident.as_deref() {
+                    // bool 可占用多个字节，Kaitai 没有 128 位整数
+                    Some("bool") if literal_usize_str(&size) != Some(1) => {
+                        vec![("size".to_owned(), size)]
+                    }
+                    Some("u128" | "i128") => vec![("size".to_owned(), size)],
+                    Some(primitive) if ksy_primitive(primitive).is_some() => {
+                        vec![(
+                            "type".to_owned(),
+                            ksy_primitive(primitive).unwrap().to_owned(),
+                        )]
+                    }
+                    _ => {
+                        let name = snake_case(&ident.unwrap_or_default());
+                        if !imports.contains(&name) {
+                            imports.push(name.to_owned());
+                        }
+                        vec![("type".to_owned(), name)]
+                    }
+                }
+            }
+            _ => vec![("size".to_owned(), size)],
+        },
+    }
+}
+
+fn literal_usize_str(value: &str) -> Option<usize> {
+    syn::parse_str::<Expr>(value)
+        .ok()
+        .and_then(|x| literal_usize(&x))
+}
+
+fn ksy_document(
+    id: &str,
+    endian: &str,
+    imports: &[String],
+    seq: &[(String, Vec<(String, String)>)],
+    instances: &[(String, String)],
+) -> String {
+    let mut ksy = format!("meta:\n  id: {}\n  endian: {}\n", id, endian);
+    if !imports.is_empty() {
+        ksy.push_str("  imports:\n");
+        for import in imports {
+            ksy.push_str(&format!("    - {}\n", import));
+        }
+    }
+    ksy.push_str("seq:\n");
+    for (id, attrs) in seq {
+        ksy.push_str(&format!("  - id: {}\n", id));
+        for (key, value) in attrs {
+            ksy.push_str(&format!("    {}: {}\n", key, value));
+        }
+    }
+    if !instances.is_empty() {
+        ksy.push_str("instances:\n");
+        for (id, value) in instances {
+            ksy.push_str(&format!("  {}:\n    value: {}\n", id, value));
+        }
+    }
+    ksy
+}
+
+fn schema_impl(
+    clean: &syn::DeriveInput,
+    ksy: impl Fn(&str) -> String,
+    json: String,
+) -> TokenStream {
+    let ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let ksy_le = ksy("le");
+    let ksy_be = ksy("be");
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 小端 Kaitai Struct 定义
+            pub const KSY_LE: &'static str = #ksy_le;
+            /// 大端 Kaitai Struct 定义
+            pub const KSY_BE: &'static str = #ksy_be;
+            /// 字段名、类型、偏移与大小的 JSON 描述
+            pub const SCHEMA_JSON: &'static str = #json;
+        }
+    }
+}
+
+fn encoding_name(encoding: &FieldEncoding) -> &'static str {
+    match encoding {
+        FieldEncoding::Native => "native",
+        FieldEncoding::Borrowed {
+            terminator: Terminator::Nul,
+            ..
+        } => "cstr",
+        FieldEncoding::Borrowed {
+            terminator: Terminator::Pad(_),
+            ..
+        } => "padded",
+        FieldEncoding::Borrowed { .. } => "bytes",
+        FieldEncoding::Utf16(_) => "utf16",
+        FieldEncoding::Varint(VarintKind::Uleb128) => "uleb128",
+        FieldEncoding::Varint(VarintKind::Sleb128) => "sleb128",
+        FieldEncoding::Varint(VarintKind::Protobuf) => "protobuf",
+        FieldEncoding::Prefixed(_) => "prefix",
+    }
+}
+
+pub(crate) fn bytemap_schema(bytemap: &BytemapStruct, packed: bool) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let name = clean.ident.to_string();
+    let mut fields = bytemap.fields.iter().collect::<Vec<_>>();
+    if !packed {
+        fields.sort_by_key(|x| x.pos_value.as_ref().map(|x| *x.start()));
+    }
+
+    let mut imports = Vec::new();
+    let mut seq = Vec::new();
+    let mut json_fields = Vec::new();
+    let mut cursor = Some(0usize);
+    for field in fields {
+        let start = field.pos_value.as_ref().map(|x| *x.start());
+        let size = field
+            .pos_value
+            .as_ref()
+            .map(|x| x.end() + 1 - x.start())
+            .or_else(|| literal_usize_str(&tokens_string(&field.len)));
+        // 两个已知位置之间的空隙
+        if let (Some(cursor), Some(start)) = (cursor, start) {
+            if start > cursor {
+                seq.push((
+                    format!("reserved_{}", cursor),
+                    vec![("size".to_owned(), (start - cursor).to_string())],
+                ));
+            }
+        }
+        let condition = field
+            .condition
+            .as_ref()
+            .map(|x| ("if".to_owned(), ksy_expr(x)));
+        let mut len = field.len.to_owned();
+        if let FieldEncoding::Prefixed(prefix) = &field.encoding {
+            let len_ident = format_ident!("{}_len", field.ident);
+            let ty = ksy_primitive(&tokens_string(&prefix.ty)).unwrap_or("u1");
+            let ty = match (prefix.endian, ty) {
+                (_, "u1") | (None, _) => ty.to_owned(),
+                (Some(Endian::Le), _) => format!("{}le", ty),
+                (Some(Endian::Be), _) => format!("{}be", ty),
+            };
+            let mut attrs = vec![("type".to_owned(), ty)];
+            // 只有字面量的 min、max 能写入 Kaitai
+            let bounds = [("min", &prefix.min), ("max", &prefix.max)]
+                .into_iter()
+                .filter_map(|(key, x)| Some(format!("{}: {}", key, int_value(x.as_ref()?)?)))
+                .collect::<Vec<_>>();
+            if !bounds.is_empty() {
+                attrs.push(("valid".to_owned(), format!("{{ {} }}", bounds.join(", "))));
+            }
+            attrs.extend(condition.to_owned());
+            seq.push((len_ident.to_string(), attrs));
+            len = quote!(#len_ident);
+        }
+        let mut attrs = ksy_field(&field.target_type, &field.encoding, &len, &mut imports);
+        attrs.extend(condition);
+        match field.magic.as_ref().map(|x| (int_value(x), byte_string(x))) {
+            Some((Some(value), _)) => attrs.push(("valid".to_owned(), value.to_string())),
+            Some((_, Some(bytes))) => {
+                let bytes = bytes
+                    .iter()
+                    .map(|x| format!("{:#04x}", x))
+                    .collect::<Vec<_>>();
+                attrs = vec![("contents".to_owned(), format!("[{}]", bytes.join(", ")))];
+            }
+            _ => {}
+        }
+        seq.push((field.ident.to_string(), attrs));
+        cursor = match (start, size) {
+            (Some(start), Some(size)) if field.condition.is_none() => Some(start + size),
+            _ => None,
+        };
+
+        let condition = field
+            .condition
+            .as_ref()
+            .map_or("null".to_owned(), |x| json_string(&tokens_string(x)));
+        json_fields.push(format!(
+            "{{\"name\":{},\"type\":{},\"offset\":{},\"size\":{},\"encoding\":{},\"condition\":{}}}",
+            json_string(&field.ident.to_string()),
+            json_string(&type_string(&field.target_type)),
+            json_option(start),
+            json_option(size),
+            json_string(encoding_name(&field.encoding)),
+            condition,
+        ));
+    }
+    // 末尾的 reserved 字节
+    let reserved_len = bytemap.reserved_len();
+    if let Some(cursor) = cursor.filter(|x| reserved_len > *x) {
+        seq.push((
+            format!("reserved_{}", cursor),
+            vec![("size".to_owned(), (reserved_len - cursor).to_string())],
+        ));
+    }
+    let id = snake_case(&name);
+    let json = format!(
+        "{{\"name\":{},\"kind\":\"bytemap\",\"fields\":[{}]}}",
+        json_string(&name),
+        json_fields.join(",")
+    );
+    schema_impl(
+        clean,
+        |endian| ksy_document(&id, endian, &imports, &seq, &[]),
+        json,
+    )
+}
+
+fn container_ksy(ty: &TypePath) -> String {
+    let name = tokens_string(ty);
+    ksy_primitive(&name).map_or(snake_case(&name), |x| x.to_owned())
+}
+
+pub(crate) fn bitmap_schema(bitmap: &BitmapStruct, types: &[TypePath]) -> syn::Result<TokenStream> {
+    let clean = &bitmap.clean_struct;
+    let name = clean.ident.to_string();
+    let mut instances = Vec::new();
+    let mut json_fields = Vec::new();
+    for field in bitmap.fields.iter() {
+        let range = range_from_expr(&field.pos)?;
+        let (start, end) = (*range.start(), *range.end());
+        let mask = 1u128
+            .checked_shl((end + 1 - start) as u32)
+            .map_or(u128::MAX, |x| x - 1);
+        let ty = tokens_string(&field.target_type);
+        let value = if ty == "bool" {
+            format!("((raw >> {}) & {:#x}) != 0", start, mask)
+        } else {
+            format!("(raw >> {}) & {:#x}", start, mask)
+        };
+        instances.push((field.ident.to_string(), value));
+        json_fields.push(format!(
+            "{{\"name\":{},\"type\":{},\"bits\":[{},{}]}}",
+            json_string(&field.ident.to_string()),
+            json_string(&type_string(&field.target_type)),
+            start,
+            end
+        ));
+    }
+    let seq = vec![(
+        "raw".to_owned(),
+        vec![("type".to_owned(), container_ksy(&types[0]))],
+    )];
+    let containers = types
+        .iter()
+        .map(|x| json_string(&tokens_string(x)))
+        .collect::<Vec<_>>();
+    let id = snake_case(&name);
+    let json = format!(
+        "{{\"name\":{},\"kind\":\"bitmap\",\"containers\":[{}],\"fields\":[{}]}}",
+        json_string(&name),
+        containers.join(","),
+        json_fields.join(",")
+    );
+    Ok(schema_impl(
+        clean,
+        |endian| ksy_document(&id, endian, &[], &seq, &instances),
+        json,
+    ))
+}
+
+pub(crate) fn restrict_schema(
+    clean: &syn::DeriveInput,
+    variants: &[RestrictVariant],
+    types: &[TypePath],
+) -> TokenStream {
+    let name = clean.ident.to_string();
+    let mut instances = Vec::new();
+    let mut json_variants = Vec::new();
+    for variant in variants.iter() {
+        let bounds = variant
+            .restrict
+            .white_list
+            .iter()
+            .map(white_list_bounds)
+            .collect::<Vec<_>>();
+        // Kaitai 无法求值 Rust 常量，含非字面量界的变体不生成 `is_` 实例
+        let literal = bounds
+            .iter()
+            .flat_map(|(lo, hi)| [lo, hi])
+            .flatten()
+            .all(|x| x.parse::<i128>().is_ok());
+        if literal {
+            let value = bounds
+                .iter()
+                .map(|bounds| match bounds {
+                    (Some(lo), Some(hi)) if lo == hi => format!("value == {}", lo),
+                    (Some(lo), Some(hi)) => format!("(value >= {} and value <= {})", lo, hi),
+                    (Some(lo), None) => format!("value >= {}", lo),
+                    (None, Some(hi)) => format!("value <= {}", hi),
+                    (None, None) => "true".to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join(" or ");
+            instances.push((
+                format!("is_{}", snake_case(&variant.ident.to_string())),
+                value,
+            ));
+        }
+        let values = bounds
+            .iter()
+            .map(|(lo, hi)| {
+                // 非字面量的界（如常量）以表达式字符串表示
+                let value = |x: &Option<String>| match x {
+                    Some(x) if x.parse::<i128>().is_ok() => x.to_owned(),
+                    Some(x) => json_string(x),
+                    None => "null".to_owned(),
+                };
+                format!("[{},{}]", value(lo), value(hi))
+            })
+            .collect::<Vec<_>>();
+        let ty = variant
+            .target_type
+            .as_ref()
+            .map_or("null".to_owned(), |x| json_string(&type_string(x)));
+        json_variants.push(format!(
+            "{{\"name\":{},\"values\":[{}],\"type\":{}}}",
+            json_string(&variant.ident.to_string()),
+            values.join(","),
+            ty
+        ));
+    }
+    let seq = vec![(
+        "value".to_owned(),
+        vec![("type".to_owned(), container_ksy(&types[0]))],
+    )];
+    let containers = types
+        .iter()
+        .map(|x| json_string(&tokens_string(x)))
+        .collect::<Vec<_>>();
+    let id = snake_case(&name);
+    let json = format!(
+        "{{\"name\":{},\"kind\":\"restrict\",\"containers\":[{}],\"variants\":[{}]}}",
+        json_string(&name),
+        containers.join(","),
+        json_variants.join(",")
+    );
+    schema_impl(
+        clean,
+        |endian| ksy_document(&id, endian, &[], &seq, &instances),
+        json,
+    )
+}
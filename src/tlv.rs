@@ -0,0 +1,217 @@
+//! restrict 的 `tlv(..)` 参数：将枚举作为 TLV 序列中的项解码。
+//!
+//! ```ignore
+//! #[restrict(u8, tlv(len = u8, len_offset = 2))]
+//! enum TcpOption {
+//!     #[white_list(0)]
+//!     Eol,
+//!     #[white_list(1)]
+//!     Nop,
+//!     #[white_list(2)]
+//!     Mss(u16),
+//! }
+//! for option in TcpOption::tlv_iter(Be(options)).known() { ... }
+//! ```
+//!
+//! 携带数据的变体的数据为该类型的值，与 bytemap 字段一样通过 `TryFrom<Le<&[u8]>>`
+//! 解码，或直接借用为 `&'a [u8]`/`&'a str`；无数据的变体在类型码在前时不占长度与值。
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::Parse, DeriveInput, Error, Expr, GenericParam, Ident, Lifetime, LifetimeDef, Path,
+    Result, Token, TypePath,
+};
+
+use crate::field_encoding::{Endian, FieldEncoding};
+use crate::restrict_enum::RestrictVariant;
+use crate::type_size::{int_signedness, known_size};
+
+/// `tlv(len = u8, len_first, len_offset = 2)`
+pub(crate) struct TlvArgs {
+    pub(crate) ident: Ident,
+    /// 长度的整数类型
+    pub(crate) len: TypePath,
+    /// 长度在类型码之前
+    pub(crate) len_first: bool,
+    /// 长度中不属于值的字节数
+    pub(crate) len_offset: Option<Expr>,
+}
+
+impl Parse for TlvArgs {
+    fn parse(input: syn::parse::ParseStream) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let mut len = None;
+        let mut len_first = false;
+        let mut len_offset = None;
+        while !content.is_empty() {
+            let key = content.parse::<Ident>()?;
+            match key.to_string().as_str() {
+                "len" => {
+                    content.parse::<Token![=]>()?;
+                    len = Some(content.parse::<TypePath>()?);
+                }
+                "len_first" => len_first = true,
+                "len_offset" => {
+                    content.parse::<Token![=]>()?;
+                    len_offset = Some(content.parse::<Expr>()?);
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        key,
+                        "expected `len`, `len_first` or `len_offset`",
+                    ))
+                }
+            }
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        let len = len.ok_or(Error::new_spanned(
+            &ident,
+            "tlv requires `len = <integer type>`",
+        ))?;
+        Ok(TlvArgs {
+            ident,
+            len,
+            len_first,
+            len_offset,
+        })
+    }
+}
+
+/// 整数类型的字节数
+fn int_size(ty: &TypePath) -> Result<usize> {
+    let ty = syn::Type::Path(ty.to_owned());
+    match (int_signedness(&ty), known_size(&ty)) {
+        (Some(_), Some(size)) => Ok(size),
+        _ => Err(Error::new_spanned(ty, "tlv requires an integer type")),
+    }
+}
+
+/// `DecodeTlv` 的 Le 与 Be 实现，以及 `tlv_iter`
+pub(crate) fn restrict_tlv(
+    clean: &DeriveInput,
+    variants: &[RestrictVariant],
+    code_ty: &TypePath,
+    tlv: &TlvArgs,
+    krate: &Path,
+) -> Result<TokenStream> {
+    let enum_ident = &clean.ident;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let code_size = int_size(code_ty)?;
+    let len_ty = &tlv.len;
+    let len_size = int_size(len_ty)?;
+    let (code_at, len_at) = match tlv.len_first {
+        true => (len_size, 0),
+        false => (0, code_size),
+    };
+    let start = code_size + len_size;
+    let code_range = quote!(#code_at..=#code_at + #code_size - 1);
+    let len_range = quote!(#len_at..=#len_at + #len_size - 1);
+    let len_offset = tlv
+        .len_offset
+        .as_ref()
+        .map_or(quote!(0), |x| x.to_token_stream());
+
+    // 值借用输入，与枚举的生命周期一致；枚举没有生命周期参数时新增 `'__input`
+    let mut generics = clean.generics.to_owned();
+    let lifetime = match clean.generics.lifetimes().next() {
+        Some(lifetime) => lifetime.lifetime.to_owned(),
+        None => {
+            let lifetime = Lifetime::new("'__input", Span::call_site());
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.to_owned())),
+            );
+            lifetime
+        }
+    };
+    let (tlv_impl_generics, _, _) = generics.split_for_impl();
+
+    let mut impls = TokenStream::new();
+    for endian in [Endian::Le, Endian::Be] {
+        let wrapper = endian.wrapper(krate);
+        let mut headers = TokenStream::new();
+        let mut values = TokenStream::new();
+        for variant in variants.iter() {
+            let ident = &variant.ident;
+            let white_list = &variant.restrict.white_list;
+            match &variant.target_type {
+                Some(ty) => {
+                    let decode = FieldEncoding::from_field(&[], ty)?.decode(
+                        ty,
+                        quote!(__raw),
+                        quote!(__body.clone()),
+                        endian,
+                        krate,
+                    );
+                    values.extend(quote! {
+                        #(#white_list)|* => #krate::tlv::Tlv::Known(Self::#ident(#decode)),
+                    });
+                }
+                // 长度在后时，无数据的变体只有类型码
+                None if !tlv.len_first => headers.extend(quote! {
+                    #(#white_list)|* => {
+                        return Ok((#krate::tlv::Tlv::Known(Self::#ident), #code_size));
+                    }
+                }),
+                None => values.extend(quote! {
+                    #(#white_list)|* => #krate::tlv::Tlv::Known(Self::#ident),
+                }),
+            }
+        }
+        let headers = match headers.is_empty() {
+            true => quote!(),
+            false => quote! {
+                match __code {
+                    #headers
+                    _ => {}
+                }
+            },
+        };
+        impls.extend(quote! {
+            impl #tlv_impl_generics #krate::tlv::DecodeTlv<#lifetime, #wrapper<&#lifetime [u8]>> for #enum_ident #ty_generics #where_clause {
+                type Code = #code_ty;
+                #[allow(unreachable_patterns)]
+                fn decode_tlv(
+                    __value: #wrapper<&#lifetime [u8]>,
+                ) -> #krate::tlv::TlvResult<#lifetime, Self, #code_ty> {
+                    let __input = __value.0;
+                    let __raw = __input.get(#code_at..#code_at + #code_size).ok_or(#code_range)?;
+                    let __code = <#code_ty>::try_from(__value.with(__raw)).map_err(|_| #code_range)?;
+                    #headers
+                    let __raw = __input.get(#len_at..#len_at + #len_size).ok_or(#len_range)?;
+                    let __len = <#len_ty>::try_from(__value.with(__raw)).map_err(|_| #len_range)?;
+                    let __end = usize::try_from(__len)
+                        .ok()
+                        .and_then(|x| x.checked_sub(#len_offset))
+                        .and_then(|x| x.checked_add(#start))
+                        .ok_or(#len_range)?;
+                    // 值为空时以长度本身作为错误的范围
+                    let __body = (__end - 1).min(#start)..=__end - 1;
+                    let __raw = __input.get(#start..__end).ok_or(__body.clone())?;
+                    let __item = match __code {
+                        #values
+                        _ => #krate::tlv::Tlv::Unknown {
+                            code: __code,
+                            value: __raw,
+                        },
+                    };
+                    Ok((__item, __end))
+                }
+            }
+        });
+    }
+    Ok(quote! {
+        impl #impl_generics #enum_ident #ty_generics #where_clause {
+            /// 依次解码 `value` 中的各项，`value` 为 `Le(&[u8])` 或 `Be(&[u8])`
+            pub fn tlv_iter<__V>(value: __V) -> #krate::tlv::TlvIter<__V, Self> {
+                #krate::tlv::TlvIter::new(value)
+            }
+        }
+        #impls
+    })
+}
@@ -0,0 +1,66 @@
+use syn::{Expr, ExprLit, Lit, Type};
+
+/// 宏展开时即可确定字节长度的类型
+pub(crate) fn known_size(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Path(path) => {
+            let ident = path.path.get_ident()?.to_string();
+            match ident.as_str() {
+                "u8" | "i8" | "bool" => Some(1),
+                "u16" | "i16" => Some(2),
+                "u32" | "i32" | "f32" => Some(4),
+                "u64" | "i64" | "f64" => Some(8),
+                "u128" | "i128" => Some(16),
+                _ => None,
+            }
+        }
+        Type::Array(array) => Some(known_size(&array.elem)? * literal_usize(&array.len)?),
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Array(_) => known_size(&reference.elem),
+            _ => None,
+        },
+        Type::Paren(paren) => known_size(&paren.elem),
+        _ => None,
+    }
+}
+
+pub(crate) fn literal_usize(expr: &Expr) -> Option<usize> {
+    if let Expr::Lit(ExprLit {
+        lit: Lit::Int(int_lit),
+        ..
+    }) = expr
+    {
+        int_lit.base10_parse::<usize>().ok()
+    } else {
+        None
+    }
+}
+
+/// 原生整数类型，返回是否有符号
+pub(crate) fn int_signedness(ty: &Type) -> Option<bool> {
+    let ident = match ty {
+        Type::Path(path) => path.path.get_ident()?.to_string(),
+        _ => return None,
+    };
+    match ident.as_str() {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Some(false),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => Some(true),
+        _ => None,
+    }
+}
+
+/// 标准库为 `from` 到 `to` 实现了无损的 `From`：bool 或位宽不超过 `to` 的整数
+pub(crate) fn lossless_into(from: &Type, to: &Type) -> bool {
+    let (to_signed, to_size) = match (int_signedness(to), known_size(to)) {
+        (Some(signed), Some(size)) => (signed, size),
+        _ => return false,
+    };
+    if matches!(from, Type::Path(path) if path.path.is_ident("bool")) {
+        return true;
+    }
+    match (int_signedness(from), known_size(from)) {
+        (Some(false), Some(size)) if to_signed => size < to_size,
+        (Some(signed), Some(size)) => signed == to_signed && size <= to_size,
+        _ => false,
+    }
+}
@@ -0,0 +1,76 @@
+//! `unit` 参数：以字而非字节寻址的布局，常见于 DSP 与 flash 的存储映射。
+//!
+//! ```ignore
+//! #[bytemap(unit = u16)]
+//! struct Block {
+//!     #[pos(0)]
+//!     id: u16,
+//!     // 字 1..=2，即字节 2..=5
+//!     #[pos(1..=2)]
+//!     addr: u32,
+//! }
+//! let block = Block::try_from(Le(&words[..]))?;
+//! let words: Vec<u16> = block.encode_words_le();
+//! ```
+//!
+//! pos 与 reserved 在解析时即换算为字节范围，字节的编解码不受影响；`Le`、`Be`
+//! 同时决定字内的字节序与跨多个字的字段中字的顺序：`Le` 时低位字在前、字节数组从字的
+//! 低位字节开始，`Be` 时高位字在前、字节数组从字的高位字节开始。两者不能分别指定，
+//! 如高位字在前而字内为小端的混合字节序需先自行交换字的顺序。
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+use crate::bytemap_struct::BytemapStruct;
+
+pub(crate) fn words(bytemap: &BytemapStruct, unit: &Ident) -> TokenStream {
+    let clean = &bytemap.clean_struct;
+    let ident = &clean.ident;
+    let krate = &bytemap.krate;
+    let size = bytemap.unit;
+    let (impl_generics, ty_generics, where_clause) = clean.generics.split_for_impl();
+    let mut decode = TokenStream::new();
+    // 借用输入的字段无法指向由字转换而来的字节
+    if clean.generics.lifetimes().next().is_none() {
+        for (wrapper, to_bytes) in [
+            (quote!(Le), quote!(to_le_bytes)),
+            (quote!(Be), quote!(to_be_bytes)),
+        ] {
+            decode.extend(quote! {
+                impl #impl_generics ::core::convert::TryFrom<#krate::endian::#wrapper<&[#unit]>> for #ident #ty_generics #where_clause {
+                    /// 出错的字范围
+                    type Error = ::core::ops::RangeInclusive<usize>;
+                    fn try_from(value: #krate::endian::#wrapper<&[#unit]>) -> Result<Self, Self::Error> {
+                        let bytes = value
+                            .0
+                            .iter()
+                            .flat_map(|x| x.#to_bytes())
+                            .collect::<::std::vec::Vec<u8>>();
+                        <Self as ::core::convert::TryFrom<_>>::try_from(#krate::endian::#wrapper(&bytes[..]))
+                            .map_err(|range| range.start() / #size..=range.end() / #size)
+                    }
+                }
+            });
+        }
+    }
+    quote! {
+        #decode
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// 以小端编码为字：跨多个字的字段低位字在前，字节数组从字的低位字节开始
+            pub fn encode_words_le(self) -> ::std::vec::Vec<#unit> {
+                self.encode_le()
+                    .chunks_exact(#size)
+                    .map(|x| #unit::from_le_bytes(x.try_into().unwrap()))
+                    .collect()
+            }
+            /// 以大端编码为字：跨多个字的字段高位字在前，字节数组从字的高位字节开始
+            pub fn encode_words_be(self) -> ::std::vec::Vec<#unit> {
+                self.encode_be()
+                    .chunks_exact(#size)
+                    .map(|x| #unit::from_be_bytes(x.try_into().unwrap()))
+                    .collect()
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! ```ignore
+//! #[bytemap(zerocopy)]
+//! struct Header {
+//!     #[pos(0..=1)]
+//!     kind: u16,
+//!     #[pos(4..=7)]
+//!     len: u32,
+//! }
+//! // 生成 #[repr(C)] 的 HeaderRaw { kind: U16Le, __gap_2: [u8; 2], len: U32Le }
+//! let raw = HeaderRaw::ref_from_bytes(bytes).unwrap();
+//! let header = Header::from(raw);
+//! ```
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{Error, Result, Type};
+
+use crate::bytemap_struct::BytemapStruct;
+use crate::field_encoding::Endian;
+
+/// 字段类型在镜像结构中对应的类型，单字节整数保持不变，数组逐元素映射
+fn raw_type(ty: &Type, endian: Endian, krate: &syn::Path) -> Option<TokenStream> {
+    match ty {
+        Type::Path(path) => {
+            let ident = path.path.get_ident()?.to_string();
+            match ident.as_str() {
+                "u8" | "i8" => Some(ty.to_token_stream()),
+                "u16" | "u32" | "u64" | "u128" | "i16" | "i32" | "i64" | "i128" | "f32" | "f64" => {
+                    let suffix = match endian {
+                        Endian::Le => "Le",
+                        Endian::Be => "Be",
+                    };
+                    let wrapper = format_ident!("{}{}", ident.to_uppercase(), suffix);
+                    Some(quote!(#krate::raw::#wrapper))
+                }
+                _ => None,
+            }
+        }
+        Type::Array(array) => {
+            let elem = raw_type(&array.elem, endian, krate)?;
+            let len = &array.len;
+            Some(quote!([#elem; #len]))
+        }
+        Type::Paren(paren) => raw_type(&paren.elem, endian, krate),
+        _ => None,
+    }
+}
+
+fn is_byte(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8") || path.path.is_ident("i8"))
+}
+
+/// 镜像字段转换为字段值
+fn from_raw(value: TokenStream, ty: &Type) -> TokenStream {
+    match ty {
+        Type::Array(array) => {
+            let elem = from_raw(quote!(__x), &array.elem);
+            quote!(#value.map(|__x| #elem))
+        }
+        Type::Paren(paren) => from_raw(value, &paren.elem),
+        _ if is_byte(ty) => value,
+        _ => quote!(#value.get()),
+    }
+}
+
+/// 字段值转换为镜像字段
+fn to_raw(value: TokenStream, ty: &Type) -> TokenStream {
+    match ty {
+        Type::Array(array) => {
+            let elem = to_raw(quote!(__x), &array.elem);
+            quote!(#value.map(|__x| #elem))
+        }
+        Type::Paren(paren) => to_raw(value, &paren.elem),
+        _ if is_byte(ty) => value,
+        _ => quote!(::core::convert::From::from(#value)),
+    }
+}
+
+/// `{Name}Raw` 镜像结构、编译期大小与偏移断言以及与 bytemap 之间的转换
+pub(crate) fn raw_mirror(bytemap: &BytemapStruct, endian: Endian) -> Result<TokenStream> {
+    let clean = &bytemap.clean_struct;
+    let krate = &bytemap.krate;
+    if !clean.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &clean.generics,
+            "zerocopy does not support generics",
+        ));
+    }
+    let ident = &clean.ident;
+    let vis = &clean.vis;
+    let raw_ident = format_ident!("{}Raw", ident);
+
+    let mut fields = Vec::new();
+    for field in bytemap.fields.iter() {
+        if let Some(condition) = &field.condition {
+            return Err(Error::new_spanned(
+                condition,
+                "zerocopy does not support conditional fields",
+            ));
+        }
+        let range = field.pos_value.to_owned().ok_or(Error::new_spanned(
+            &field.pos,
+            "zerocopy requires a literal position",
+        ))?;
+        let raw_ty = raw_type(&field.target_type, endian, krate).ok_or(Error::new_spanned(
+            &field.target_type,
+            "zerocopy field must be a number or an array of numbers",
+        ))?;
+        fields.push((field, range, raw_ty));
+    }
+    fields.sort_by_key(|(_, range, _)| *range.start());
+
+    let mut raw_fields = TokenStream::new();
+    let mut asserts = TokenStream::new();
+    let mut from_raw_fields = TokenStream::new();
+    let mut to_raw_fields = TokenStream::new();
+    let mut offset = 0usize;
+    for (field, range, raw_ty) in fields.iter() {
+        let field_ident = &field.ident;
+        let (start, end) = (*range.start(), *range.end());
+        // pos 之间的空隙以字节数组占位
+        if start > offset {
+            let gap = start - offset;
+            let gap_ident = format_ident!("__gap_{}", offset);
+            raw_fields.extend(quote!(#gap_ident: [u8; #gap],));
+            to_raw_fields.extend(quote!(#gap_ident: [0u8; #gap],));
+        }
+        let width = end + 1 - start;
+        raw_fields.extend(quote!(pub #field_ident: #raw_ty,));
+        asserts.extend(quote! {
+            assert!(::core::mem::size_of::<#raw_ty>() == #width);
+            assert!(::core::mem::offset_of!(#raw_ident, #field_ident) == #start);
+        });
+        let from = from_raw(quote!(raw.#field_ident), &field.target_type);
+        from_raw_fields.extend(quote!(#field_ident: #from,));
+        let to = to_raw(quote!(value.#field_ident), &field.target_type);
+        to_raw_fields.extend(quote!(#field_ident: #to,));
+        offset = end + 1;
+    }
+    // 末尾的 reserved 字节
+    let reserved_len = bytemap.reserved_len();
+    if reserved_len > offset {
+        let gap = reserved_len - offset;
+        let gap_ident = format_ident!("__gap_{}", offset);
+        raw_fields.extend(quote!(#gap_ident: [u8; #gap],));
+        to_raw_fields.extend(quote!(#gap_ident: [0u8; #gap],));
+        offset = reserved_len;
+    }
+
+    let bytemuck = if cfg!(feature = "bytemuck") {
+        quote! {
+            unsafe impl #krate::bytemuck::Zeroable for #raw_ident {}
+            unsafe impl #krate::bytemuck::Pod for #raw_ident {}
+        }
+    } else {
+        quote!()
+    };
+    Ok(quote! {
+        #[derive(Clone, Copy, Debug)]
+        #[repr(C)]
+        #vis struct #raw_ident {
+            #raw_fields
+        }
+        const _: () = {
+            assert!(::core::mem::size_of::<#raw_ident>() == #offset);
+            assert!(::core::mem::align_of::<#raw_ident>() == 1);
+            #asserts
+        };
+        // SAFETY: 所有字段均为 RawBytes，且大小、偏移与对齐已在编译期断言
+        unsafe impl #krate::raw::RawBytes for #raw_ident {}
+        #bytemuck
+        impl ::core::convert::From<&#raw_ident> for #ident {
+            fn from(raw: &#raw_ident) -> Self {
+                Self {
+                    #from_raw_fields
+                }
+            }
+        }
+        impl ::core::convert::From<&#ident> for #raw_ident {
+            fn from(value: &#ident) -> Self {
+                Self {
+                    #to_raw_fields
+                }
+            }
+        }
+    })
+}